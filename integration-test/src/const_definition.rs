@@ -11,6 +11,10 @@ use std::sync::Mutex;
 pub const RPC_TRY_COUNT: usize = 10;
 pub const RPC_TRY_INTERVAL_SECS: u64 = 5;
 
+// the time for the chain watcher and the plugins to move an otx to the expected status
+pub const OTX_STATUS_TIMEOUT_SECS: u64 = 60;
+pub const OTX_STATUS_POLL_INTERVAL_MILLIS: u64 = 500;
+
 pub const CELL_BASE_MATURE_EPOCH: u64 = 4;
 pub const GENESIS_EPOCH_LENGTH: u64 = 10;
 
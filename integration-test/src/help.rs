@@ -1,5 +1,6 @@
 use super::const_definition::{
-    CKB_URI, MERCURY_URI, OTX_POOL_URI, RPC_TRY_COUNT, RPC_TRY_INTERVAL_SECS, SCRIPT_CONFIG,
+    CKB_URI, MERCURY_URI, OTX_POOL_URI, OTX_STATUS_POLL_INTERVAL_MILLIS, OTX_STATUS_TIMEOUT_SECS,
+    RPC_TRY_COUNT, RPC_TRY_INTERVAL_SECS, SCRIPT_CONFIG,
 };
 use crate::const_definition::CURRENT_OTX_POOL_SERVICE_PROCESS;
 use crate::utils::client::ckb_client::CkbRpcClient;
@@ -7,6 +8,7 @@ use crate::utils::client::mercury_client::MercuryRpcClient;
 use crate::utils::instruction::command::run_command_spawn;
 use crate::utils::instruction::{ckb::generate_blocks, ckb::unlock_frozen_capacity_in_genesis};
 
+use otx_format::types::OpenTxStatus;
 use otx_pool_client::OtxPoolRpcClient;
 use otx_pool_config::{parse, AppConfig, ConfigFile};
 
@@ -18,7 +20,7 @@ use std::env;
 use std::panic;
 use std::process::Child;
 use std::thread::sleep;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 pub fn setup() -> Vec<Child> {
     println!("Setup test environment...");
@@ -150,3 +152,25 @@ pub(crate) fn start_otx_pool(address: Address, pk: H256) {
     teardown(vec![service]);
     panic!("start otx pool service failed");
 }
+
+/// Poll the status of the otx until it is the expected one, the otxs are moved
+/// by the chain watcher and the plugins in the background.
+pub(crate) fn wait_otx_status(
+    client: &OtxPoolRpcClient,
+    id: &H256,
+    expected: impl Fn(&OpenTxStatus) -> bool,
+) -> OpenTxStatus {
+    let deadline = Instant::now() + Duration::from_secs(OTX_STATUS_TIMEOUT_SECS);
+    loop {
+        let status = client.query_otx_status_by_id(id.to_owned());
+        if let Ok(Some(status)) = &status {
+            if expected(status) {
+                return status.to_owned();
+            }
+        }
+        if Instant::now() >= deadline {
+            panic!("otx {:#x} is not in the expected status: {:?}", id, status);
+        }
+        sleep(Duration::from_millis(OTX_STATUS_POLL_INTERVAL_MILLIS));
+    }
+}
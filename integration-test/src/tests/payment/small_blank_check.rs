@@ -1,5 +1,5 @@
 use crate::const_definition::{CKB_URI, MERCURY_URI, OTX_POOL_URI, SCRIPT_CONFIG};
-use crate::help::start_otx_pool;
+use crate::help::{start_otx_pool, wait_otx_status};
 use crate::utils::client::ckb_cli_client::ckb_cli_transfer_ckb;
 use crate::utils::client::mercury_client::types::{GetBalancePayload, JsonItem};
 use crate::utils::client::mercury_client::MercuryRpcClient;
@@ -29,8 +29,6 @@ use ckb_types::{
 };
 
 use std::collections::HashSet;
-use std::thread::sleep;
-use std::time::Duration;
 
 inventory::submit!(IntegrationTest {
     name: "test_payment_small_blank_check",
@@ -105,27 +103,31 @@ fn test_payment_small_blank_check() {
         .unwrap();
     assert_eq!(bob_otx_status, OpenTxStatus::Pending);
 
-    sleep(Duration::from_secs(12));
+    // mine the final tx once the plugins have sent it
+    for id in [&alice_otx_id, &bob_otx_id] {
+        wait_otx_status(&service_client, id, |status| {
+            matches!(status, OpenTxStatus::Committed(_))
+        });
+    }
     aggregate_transactions_into_blocks().unwrap();
 
-    // query otxs after a few secs
-    let alice_otx_status = service_client
-        .query_otx_status_by_id(alice_otx_id)
-        .unwrap()
-        .unwrap();
-    let bob_otx_status = service_client
-        .query_otx_status_by_id(bob_otx_id)
-        .unwrap()
-        .unwrap();
-    assert!(matches!(alice_otx_status, OpenTxStatus::Committed(_)));
-    assert!(matches!(bob_otx_status, OpenTxStatus::Committed(_)));
-    assert_eq!(alice_otx_status, bob_otx_status);
-    if let OpenTxStatus::Committed(tx_hash) = alice_otx_status {
+    // query otxs once the chain watcher sees the final tx in block
+    let alice_otx_status = wait_otx_status(&service_client, &alice_otx_id, |status| {
+        matches!(status, OpenTxStatus::InBlock(_))
+    });
+    let bob_otx_status = wait_otx_status(&service_client, &bob_otx_id, |status| {
+        matches!(status, OpenTxStatus::InBlock(_))
+    });
+    assert_eq!(
+        alice_otx_status.get_final_tx_hash(),
+        bob_otx_status.get_final_tx_hash()
+    );
+    if let OpenTxStatus::InBlock(info) = alice_otx_status {
         let merged_otx_status = service_client
-            .query_otx_status_by_id(tx_hash)
+            .query_otx_status_by_id(info.tx_hash)
             .unwrap()
             .unwrap();
-        assert!(matches!(merged_otx_status, OpenTxStatus::Committed(_)));
+        assert!(matches!(merged_otx_status, OpenTxStatus::InBlock(_)));
     } else {
         panic!()
     }
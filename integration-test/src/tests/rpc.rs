@@ -5,7 +5,7 @@ use crate::utils::lock::secp::generate_rand_secp_address_pk_pair;
 use crate::IntegrationTest;
 
 use otx_format::jsonrpc_types::OpenTransaction;
-use otx_format::types::OpenTxStatus;
+use otx_format::types::{OpenTxStatus, OpenTxStatusType, OtxFilter, PoolEvent};
 use otx_pool_client::OtxPoolRpcClient;
 use otx_sdk::address::build_otx_address_from_secp_address;
 
@@ -22,10 +22,11 @@ fn test_service_rpc() {
     let service_client = OtxPoolRpcClient::new(OTX_POOL_URI.to_string());
     let otx = OpenTransaction::default();
     let id = otx.get_tx_hash().unwrap();
+    // an otx without inputs is refused by the structure validator
     let ret = service_client.submit_otx(otx);
-    assert!(ret.is_ok());
-    let ret = service_client.query_otx_status_by_id(id);
-    assert!(ret.is_ok());
+    assert!(ret.is_err());
+    let ret = service_client.query_otx_status_by_id(id).unwrap();
+    assert!(ret.is_none());
 }
 
 inventory::submit!(IntegrationTest {
@@ -55,11 +56,47 @@ fn test_service_rpc_submit_otx() {
     .unwrap();
 
     let service_client = OtxPoolRpcClient::new(OTX_POOL_URI.to_string());
+    let head = service_client.get_events(0, 0).unwrap();
+    assert!(head.events.is_empty());
     let id = service_client.submit_otx(otx).unwrap();
     log::debug!("id: {:?}", id);
-    let status = service_client.query_otx_status_by_id(id).unwrap().unwrap();
+    let status = service_client
+        .query_otx_status_by_id(id.clone())
+        .unwrap()
+        .unwrap();
     assert_eq!(status, OpenTxStatus::Pending);
 
+    let filter = OtxFilter {
+        status: Some(OpenTxStatusType::Pending),
+        ..Default::default()
+    };
+    let page = service_client.get_otxs(filter, None, 10, None).unwrap();
+    assert!(page.objects.iter().any(|otx| otx.id == id));
+
+    let detail = service_client.get_otx_by_id(id.clone()).unwrap().unwrap();
+    assert_eq!(detail.otx_with_status.status, OpenTxStatus::Pending);
+    assert!(detail.merged_otx_ids.is_empty());
+    assert!(detail.final_tx_hash.is_none());
+    let details = service_client
+        .get_otxs_by_ids(vec![id.clone(), H256::default()])
+        .unwrap();
+    assert!(details[0].is_some());
+    assert!(details[1].is_none());
+
+    let history = service_client.get_otx_status_history(id.clone()).unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0].from, None);
+    assert_eq!(history[0].to, OpenTxStatus::Pending);
+    assert_eq!(history[0].actor, "rpc");
+
+    // the new otx is recorded in the event log after the head read before the submission
+    let page = service_client.get_events(head.last_seq + 1, 1000).unwrap();
+    assert!(page.events.iter().all(|record| record.seq > head.last_seq));
+    assert!(page.events.iter().any(|record| matches!(
+        &record.event,
+        PoolEvent::NewOtx(otx) if otx.get_tx_hash().unwrap() == id
+    )));
+
     let ret = service_client
         .query_otx_status_by_id(H256::default())
         .unwrap();
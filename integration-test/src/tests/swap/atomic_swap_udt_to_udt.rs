@@ -5,7 +5,7 @@ use crate::const_definition::{
     CKB_URI, MERCURY_URI, OTX_POOL_URI, SCRIPT_CONFIG, UDT_1_HASH, UDT_1_HOLDER_SECP_ADDRESS,
     UDT_2_HASH, UDT_2_HOLDER_SECP_ADDRESS,
 };
-use crate::help::start_otx_pool;
+use crate::help::{start_otx_pool, wait_otx_status};
 use crate::utils::client::ckb_cli_client::ckb_cli_transfer_ckb;
 use crate::utils::client::mercury_client::types::{AssetInfo, GetBalancePayload, JsonItem};
 use crate::utils::client::mercury_client::MercuryRpcClient;
@@ -35,8 +35,6 @@ use ckb_types::{
 };
 
 use std::collections::HashSet;
-use std::thread::sleep;
-use std::time::Duration;
 
 inventory::submit!(IntegrationTest {
     name: "test_otx_swap_udt_to_udt",
@@ -108,21 +106,25 @@ fn test_otx_swap_udt_to_udt() {
     // submit bob otxs
     let bob_otx_id = service_client.submit_otx(bob_otx).unwrap();
 
-    sleep(Duration::from_secs(5));
+    // mine the final tx once the plugins have sent it
+    for id in [&alice_otx_id, &bob_otx_id] {
+        wait_otx_status(&service_client, id, |status| {
+            matches!(status, OpenTxStatus::Committed(_))
+        });
+    }
     aggregate_transactions_into_blocks().unwrap();
 
-    // query otxs
-    let alice_otx_with_status = service_client
-        .query_otx_status_by_id(alice_otx_id)
-        .unwrap()
-        .unwrap();
-    let bob_otx_status = service_client
-        .query_otx_status_by_id(bob_otx_id)
-        .unwrap()
-        .unwrap();
-    assert!(matches!(alice_otx_with_status, OpenTxStatus::Committed(_)));
-    assert!(matches!(bob_otx_status, OpenTxStatus::Committed(_)));
-    assert_eq!(alice_otx_with_status, bob_otx_status);
+    // query otxs once the chain watcher sees the final tx in block
+    let alice_otx_with_status = wait_otx_status(&service_client, &alice_otx_id, |status| {
+        matches!(status, OpenTxStatus::InBlock(_))
+    });
+    let bob_otx_status = wait_otx_status(&service_client, &bob_otx_id, |status| {
+        matches!(status, OpenTxStatus::InBlock(_))
+    });
+    assert_eq!(
+        alice_otx_with_status.get_final_tx_hash(),
+        bob_otx_status.get_final_tx_hash()
+    );
 
     let mercury_client = MercuryRpcClient::new(MERCURY_URI.to_string());
 
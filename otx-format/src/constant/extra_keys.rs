@@ -30,9 +30,9 @@ pub const OTX_ACCOUNTING_META_OUTPUT_SUDT: u32 = 0x10047;
 
 /// Locating (0x10050)
 pub const OTX_LOCATING_INPUT_CAPACITY: u32 = 0x10050;
-// pub const OTX_LOCATING_INPUT_LOCK_CODE_HASH: u32 = 0x10051;
-// pub const OTX_LOCATING_INPUT_LOCK_HASH_TYPE: u32 = 0x10052;
-// pub const OTX_LOCATING_INPUT_LOCK_ARGS: u32 = 0x10053;
+pub const OTX_LOCATING_INPUT_LOCK_CODE_HASH: u32 = 0x10051;
+pub const OTX_LOCATING_INPUT_LOCK_HASH_TYPE: u32 = 0x10052;
+pub const OTX_LOCATING_INPUT_LOCK_ARGS: u32 = 0x10053;
 // pub const OTX_LOCATING_INPUT_TYPE_CODE_HASH: u32 = 0x10054;
 // pub const OTX_LOCATING_INPUT_TYPE_HASH_TYPE: u32 = 0x10055;
 // pub const OTX_LOCATING_INPUT_TYPE_ARGS: u32 = 0x10056;
@@ -3,3 +3,4 @@ mod otx_map;
 pub mod tx_view;
 
 pub use opentx::*;
+pub use otx_map::{OtxKeyPair, OtxMap};
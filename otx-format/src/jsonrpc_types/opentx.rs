@@ -4,7 +4,8 @@ use crate::constant::extra_keys::{
     OTX_ACCOUNTING_META_INPUT_CKB, OTX_ACCOUNTING_META_INPUT_SUDT, OTX_ACCOUNTING_META_INPUT_XUDT,
     OTX_ACCOUNTING_META_MAX_FEE, OTX_ACCOUNTING_META_OUTPUT_CKB, OTX_ACCOUNTING_META_OUTPUT_SUDT,
     OTX_ACCOUNTING_META_OUTPUT_XUDT, OTX_IDENTIFYING_META_AGGREGATE_COUNT,
-    OTX_IDENTIFYING_META_TX_HASH,
+    OTX_IDENTIFYING_META_TX_HASH, OTX_LOCATING_INPUT_LOCK_ARGS, OTX_LOCATING_INPUT_LOCK_CODE_HASH,
+    OTX_LOCATING_INPUT_LOCK_HASH_TYPE,
 };
 use crate::error::OtxFormatError;
 use crate::jsonrpc_types::otx_map::{OtxKeyPair, OtxMap};
@@ -12,9 +13,11 @@ use crate::types::packed::{self, OpenTransactionBuilder, OtxMapVecBuilder};
 use crate::types::PaymentAmount;
 
 use anyhow::Result;
-use ckb_jsonrpc_types::{CellDep, CellInput, CellOutput, JsonBytes, TransactionView};
+use ckb_jsonrpc_types::{
+    CellDep, CellInput, CellOutput, JsonBytes, OutPoint, Script, TransactionView,
+};
 use ckb_types::constants::TX_VERSION;
-use ckb_types::core::{self, TransactionBuilder};
+use ckb_types::core::{self, ScriptHashType, TransactionBuilder};
 use ckb_types::packed::{Uint128, Uint64};
 use ckb_types::{self, prelude::*, H256};
 use serde::{Deserialize, Serialize};
@@ -111,6 +114,68 @@ impl OpenTransaction {
         tx_view.map(|tx| tx.witness_hash().unpack())
     }
 
+    pub fn get_input_out_points(&self) -> Result<Vec<OutPoint>, OtxFormatError> {
+        self.inputs
+            .clone()
+            .into_iter()
+            .map(|input| CellInput::try_from(input).map(|input| input.previous_output))
+            .collect()
+    }
+
+    /// Get the lock scripts of the input cells, which are located in the input maps.
+    /// `None` is returned for the inputs without the locating keys.
+    pub fn get_input_lock_scripts(&self) -> Result<Vec<Option<Script>>, OtxFormatError> {
+        let key_data: Option<JsonBytes> = Some(packed_byte_default());
+        self.inputs
+            .clone()
+            .into_iter()
+            .map(|input| {
+                let code_hash =
+                    input.get(OTX_LOCATING_INPUT_LOCK_CODE_HASH.into(), key_data.clone());
+                let hash_type =
+                    input.get(OTX_LOCATING_INPUT_LOCK_HASH_TYPE.into(), key_data.clone());
+                let args = input.get(OTX_LOCATING_INPUT_LOCK_ARGS.into(), key_data.clone());
+                let (code_hash, hash_type, args) = match (code_hash, hash_type, args) {
+                    (Some(code_hash), Some(hash_type), Some(args)) => (code_hash, hash_type, args),
+                    _ => return Ok(None),
+                };
+                let code_hash = H256::from_slice(code_hash.as_bytes()).map_err(|e| {
+                    OtxFormatError::OtxMapParseFailed(
+                        OTX_LOCATING_INPUT_LOCK_CODE_HASH,
+                        e.to_string(),
+                    )
+                })?;
+                let hash_type: u8 = ckb_types::packed::Byte::from_slice(hash_type.as_bytes())
+                    .map_err(|e| {
+                        OtxFormatError::OtxMapParseFailed(
+                            OTX_LOCATING_INPUT_LOCK_HASH_TYPE,
+                            e.to_string(),
+                        )
+                    })?
+                    .into();
+                let hash_type: ScriptHashType = hash_type.try_into().map_err(|_| {
+                    OtxFormatError::OtxMapParseFailed(
+                        OTX_LOCATING_INPUT_LOCK_HASH_TYPE,
+                        "ScriptHashType".to_string(),
+                    )
+                })?;
+                Ok(Some(Script {
+                    code_hash,
+                    hash_type: hash_type.into(),
+                    args,
+                }))
+            })
+            .collect()
+    }
+
+    pub fn get_outputs(&self) -> Result<Vec<(CellOutput, OutputData)>, OtxFormatError> {
+        self.outputs
+            .clone()
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect()
+    }
+
     pub fn get_aggregate_count(&self) -> Result<u32, OtxFormatError> {
         let aggregate_count = self
             .meta
@@ -130,15 +195,15 @@ impl OpenTransaction {
     }
 
     pub fn get_max_fee(&self) -> u64 {
+        self.try_get_max_fee().expect("get max fee")
+    }
+
+    /// The max fee in the meta, 0 if there is none.
+    pub fn try_get_max_fee(&self) -> Result<u64, OtxFormatError> {
         self.meta
             .get(OTX_ACCOUNTING_META_MAX_FEE.into(), None)
-            .map(|max_fee| {
-                let count: u64 = ckb_types::packed::Uint64::from_slice(max_fee.as_bytes())
-                    .expect("get aggregate count")
-                    .unpack();
-                count
-            })
-            .unwrap_or(0)
+            .map(|max_fee| decode_u64(OTX_ACCOUNTING_META_MAX_FEE, &max_fee))
+            .unwrap_or(Ok(0))
     }
 
     pub fn get_payment_amount(&self) -> Result<PaymentAmount, OtxFormatError> {
@@ -146,31 +211,21 @@ impl OpenTransaction {
         let input_capacity = self
             .meta
             .get(OTX_ACCOUNTING_META_INPUT_CKB.into(), None)
-            .map(|input_ckb| {
-                let capacity: u64 = Uint64::from_slice(input_ckb.as_bytes())
-                    .expect("get input ckb")
-                    .unpack();
-                capacity
-            })
             .ok_or_else(|| {
                 OtxFormatError::OtxMapParseMissingField(OTX_ACCOUNTING_META_INPUT_CKB.to_string())
-            })?;
+            })
+            .and_then(|input_ckb| decode_u64(OTX_ACCOUNTING_META_INPUT_CKB, &input_ckb))?;
 
         let output_capacity = self
             .meta
             .get(OTX_ACCOUNTING_META_OUTPUT_CKB.into(), None)
-            .map(|output_ckb| {
-                let capacity: u64 = Uint64::from_slice(output_ckb.as_bytes())
-                    .expect("get output ckb")
-                    .unpack();
-                capacity
-            })
             .ok_or_else(|| {
                 OtxFormatError::OtxMapParseMissingField(OTX_ACCOUNTING_META_OUTPUT_CKB.to_string())
-            })?;
+            })
+            .and_then(|output_ckb| decode_u64(OTX_ACCOUNTING_META_OUTPUT_CKB, &output_ckb))?;
 
         // fee
-        let fee = self.get_max_fee();
+        let fee = self.try_get_max_fee()?;
 
         let mut kv_map = self.meta.clone();
         let mut x_udt_amount = HashMap::new();
@@ -181,16 +236,17 @@ impl OpenTransaction {
                 break;
             }
             let ((_, script), input_xudt_amount) = input_xudt_amount.unwrap();
-            let script = ckb_types::packed::Script::from_slice(
-                script.to_owned().expect("get script").as_bytes(),
-            )
-            .map_err(|e| {
-                OtxFormatError::OtxMapParseFailed(OTX_ACCOUNTING_META_INPUT_XUDT, e.to_string())
-            })?
-            .into();
-            let input_xudt_amount: u128 = Uint128::from_slice(input_xudt_amount.as_bytes())
-                .expect("get input xudt amount")
-                .unpack();
+            let script =
+                ckb_types::packed::Script::from_slice(script.unwrap_or_default().as_bytes())
+                    .map_err(|e| {
+                        OtxFormatError::OtxMapParseFailed(
+                            OTX_ACCOUNTING_META_INPUT_XUDT,
+                            e.to_string(),
+                        )
+                    })?
+                    .into();
+            let input_xudt_amount =
+                decode_u128(OTX_ACCOUNTING_META_INPUT_XUDT, &input_xudt_amount)?;
             *x_udt_amount.entry(script).or_insert(0) += input_xudt_amount as i128;
         }
         loop {
@@ -200,16 +256,17 @@ impl OpenTransaction {
                 break;
             }
             let ((_, script), output_xudt_amount) = output_xudt_amount.unwrap();
-            let script = ckb_types::packed::Script::from_slice(
-                script.to_owned().expect("get script").as_bytes(),
-            )
-            .map_err(|e| {
-                OtxFormatError::OtxMapParseFailed(OTX_ACCOUNTING_META_OUTPUT_XUDT, e.to_string())
-            })?
-            .into();
-            let output_xudt_amount: u128 = Uint128::from_slice(output_xudt_amount.as_bytes())
-                .expect("get output xudt amount")
-                .unpack();
+            let script =
+                ckb_types::packed::Script::from_slice(script.unwrap_or_default().as_bytes())
+                    .map_err(|e| {
+                        OtxFormatError::OtxMapParseFailed(
+                            OTX_ACCOUNTING_META_OUTPUT_XUDT,
+                            e.to_string(),
+                        )
+                    })?
+                    .into();
+            let output_xudt_amount =
+                decode_u128(OTX_ACCOUNTING_META_OUTPUT_XUDT, &output_xudt_amount)?;
             *x_udt_amount.entry(script).or_insert(0) -= output_xudt_amount as i128;
         }
 
@@ -221,16 +278,17 @@ impl OpenTransaction {
                 break;
             }
             let ((_, script), input_sudt_amount) = input_sudt_amount.unwrap();
-            let script = ckb_types::packed::Script::from_slice(
-                script.to_owned().expect("get script").as_bytes(),
-            )
-            .map_err(|e| {
-                OtxFormatError::OtxMapParseFailed(OTX_ACCOUNTING_META_INPUT_SUDT, e.to_string())
-            })?
-            .into();
-            let input_sudt_amount: u128 = Uint128::from_slice(input_sudt_amount.as_bytes())
-                .expect("get input sudt amount")
-                .unpack();
+            let script =
+                ckb_types::packed::Script::from_slice(script.unwrap_or_default().as_bytes())
+                    .map_err(|e| {
+                        OtxFormatError::OtxMapParseFailed(
+                            OTX_ACCOUNTING_META_INPUT_SUDT,
+                            e.to_string(),
+                        )
+                    })?
+                    .into();
+            let input_sudt_amount =
+                decode_u128(OTX_ACCOUNTING_META_INPUT_SUDT, &input_sudt_amount)?;
             *s_udt_amount.entry(script).or_insert(0) += input_sudt_amount as i128;
         }
         loop {
@@ -240,16 +298,17 @@ impl OpenTransaction {
                 break;
             }
             let ((_, script), output_sudt_amount) = output_sudt_amount.unwrap();
-            let script = ckb_types::packed::Script::from_slice(
-                script.to_owned().expect("get script").as_bytes(),
-            )
-            .map_err(|e| {
-                OtxFormatError::OtxMapParseFailed(OTX_ACCOUNTING_META_OUTPUT_SUDT, e.to_string())
-            })?
-            .into();
-            let output_sudt_amount: u128 = Uint128::from_slice(output_sudt_amount.as_bytes())
-                .expect("get output sudt amount")
-                .unpack();
+            let script =
+                ckb_types::packed::Script::from_slice(script.unwrap_or_default().as_bytes())
+                    .map_err(|e| {
+                        OtxFormatError::OtxMapParseFailed(
+                            OTX_ACCOUNTING_META_OUTPUT_SUDT,
+                            e.to_string(),
+                        )
+                    })?
+                    .into();
+            let output_sudt_amount =
+                decode_u128(OTX_ACCOUNTING_META_OUTPUT_SUDT, &output_sudt_amount)?;
             *s_udt_amount.entry(script).or_insert(0) -= output_sudt_amount as i128;
         }
 
@@ -346,3 +405,19 @@ impl TryFrom<OpenTransaction> for TransactionView {
         TryInto::<core::TransactionView>::try_into(otx).map(Into::into)
     }
 }
+
+fn packed_byte_default() -> JsonBytes {
+    ckb_types::packed::Byte::default().as_bytes().pack().into()
+}
+
+fn decode_u64(key: u32, value: &JsonBytes) -> Result<u64, OtxFormatError> {
+    Uint64::from_slice(value.as_bytes())
+        .map(|value| value.unpack())
+        .map_err(|e| OtxFormatError::OtxMapParseFailed(key, e.to_string()))
+}
+
+fn decode_u128(key: u32, value: &JsonBytes) -> Result<u128, OtxFormatError> {
+    Uint128::from_slice(value.as_bytes())
+        .map(|value| value.unpack())
+        .map_err(|e| OtxFormatError::OtxMapParseFailed(key, e.to_string()))
+}
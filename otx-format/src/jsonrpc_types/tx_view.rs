@@ -6,7 +6,8 @@ use crate::constant::extra_keys::{
     OTX_ACCOUNTING_META_MAX_FEE, OTX_ACCOUNTING_META_OUTPUT_CKB, OTX_ACCOUNTING_META_OUTPUT_SUDT,
     OTX_ACCOUNTING_META_OUTPUT_XUDT, OTX_IDENTIFYING_META_AGGREGATE_COUNT,
     OTX_IDENTIFYING_META_TX_HASH, OTX_IDENTIFYING_META_TX_WITNESS_HASH,
-    OTX_LOCATING_INPUT_CAPACITY, OTX_VERSIONING_META_OPEN_TX_VERSION,
+    OTX_LOCATING_INPUT_CAPACITY, OTX_LOCATING_INPUT_LOCK_ARGS, OTX_LOCATING_INPUT_LOCK_CODE_HASH,
+    OTX_LOCATING_INPUT_LOCK_HASH_TYPE, OTX_VERSIONING_META_OPEN_TX_VERSION,
 };
 use crate::error::OtxFormatError;
 use crate::jsonrpc_types::otx_map::{OtxKeyPair, OtxMap};
@@ -16,6 +17,7 @@ use anyhow::Result;
 use ckb_jsonrpc_types::{JsonBytes, Script, TransactionView, Uint32};
 use ckb_jsonrpc_types::{Uint128, Uint64};
 use ckb_sdk::CkbRpcClient;
+use ckb_types::core::ScriptHashType;
 use ckb_types::packed::{self, Transaction};
 use ckb_types::{prelude::*, H256};
 use serde::Serialize;
@@ -158,6 +160,23 @@ pub fn tx_view_to_otx(
             JsonBytes::from_bytes(cell.output.capacity.pack().as_bytes()),
         );
         otx_map.push(input_capacity);
+        let lock_hash_type: ScriptHashType = cell.output.lock.hash_type.into();
+        let lock_hash_type: packed::Byte = lock_hash_type.into();
+        otx_map.push(OtxKeyPair::new(
+            OTX_LOCATING_INPUT_LOCK_CODE_HASH.into(),
+            Some(packed::Byte::default().as_bytes().pack().into()),
+            JsonBytes::from_bytes(cell.output.lock.code_hash.pack().as_bytes()),
+        ));
+        otx_map.push(OtxKeyPair::new(
+            OTX_LOCATING_INPUT_LOCK_HASH_TYPE.into(),
+            Some(packed::Byte::default().as_bytes().pack().into()),
+            JsonBytes::from_bytes(lock_hash_type.as_bytes()),
+        ));
+        otx_map.push(OtxKeyPair::new(
+            OTX_LOCATING_INPUT_LOCK_ARGS.into(),
+            Some(packed::Byte::default().as_bytes().pack().into()),
+            cell.output.lock.args,
+        ));
         input_ckb_capacity += <Uint64 as Into<u64>>::into(cell.output.capacity);
         inputs.push(otx_map);
 
@@ -276,7 +295,7 @@ pub fn tx_view_to_otx(
     ))
 }
 
-fn decode_udt_amount(data: &[u8]) -> Option<u128> {
+pub fn decode_udt_amount(data: &[u8]) -> Option<u128> {
     if data.len() < 16 {
         return None;
     }
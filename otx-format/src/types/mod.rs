@@ -1,10 +1,11 @@
 pub use crate::generated::packed;
 use crate::jsonrpc_types::OpenTransaction;
 
-use ckb_jsonrpc_types::{Deserialize, Script, Serialize};
+use ckb_jsonrpc_types::{Deserialize, JsonBytes, Script, Serialize};
 use ckb_types::H256;
 
 use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum OpenTxStatus {
@@ -12,17 +13,99 @@ pub enum OpenTxStatus {
     Pending,
     /// Status "merged". The open transaction is generated by merging multiple open transactions and is still unsigned.
     Merged(H256),
-    /// Status "committed". The open transaction has been committed to the canonical chain.
+    /// Status "committed". The final transaction including the open transaction has been sent to the CKB node.
     Committed(H256),
+    /// Status "proposed". The final transaction has been proposed.
+    Proposed(H256),
+    /// Status "in block". The final transaction has been included in a block of the canonical chain.
+    InBlock(FinalTxInfo),
+    /// Status "confirmed". The block including the final transaction has reached the confirmation depth.
+    Confirmed(FinalTxInfo),
     // Status "rejected". The open transaction has been recently removed from the pool.
     /// Due to storage limitations, the pool can only hold the most recently removed transactions.
     Rejected(String),
 }
 
+/// The status without its payload, used to filter otxs by status.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum OpenTxStatusType {
+    Pending,
+    Merged,
+    Committed,
+    Proposed,
+    InBlock,
+    Confirmed,
+    Rejected,
+}
+
+impl OpenTxStatus {
+    pub fn status_type(&self) -> OpenTxStatusType {
+        match self {
+            OpenTxStatus::Pending => OpenTxStatusType::Pending,
+            OpenTxStatus::Merged(_) => OpenTxStatusType::Merged,
+            OpenTxStatus::Committed(_) => OpenTxStatusType::Committed,
+            OpenTxStatus::Proposed(_) => OpenTxStatusType::Proposed,
+            OpenTxStatus::InBlock(_) => OpenTxStatusType::InBlock,
+            OpenTxStatus::Confirmed(_) => OpenTxStatusType::Confirmed,
+            OpenTxStatus::Rejected(_) => OpenTxStatusType::Rejected,
+        }
+    }
+
+    /// Whether the status can move to the other one in the normal flow of an otx.
+    ///
+    /// An otx only moves forward: pending, merged, then the final tx goes through committed,
+    /// proposed, in block and confirmed, and may move back among them after a reorg.
+    /// Confirmed and rejected are final.
+    pub fn can_transit_to(&self, to: &OpenTxStatus) -> bool {
+        use OpenTxStatus::*;
+        match (self, to) {
+            (Pending, Merged(_) | Committed(_) | Rejected(_)) => true,
+            (Merged(_), Committed(_) | Rejected(_)) => true,
+            (Committed(_) | Proposed(_) | InBlock(_), Rejected(_)) => true,
+            (
+                Committed(_) | Proposed(_) | InBlock(_),
+                Committed(_) | Proposed(_) | InBlock(_) | Confirmed(_),
+            ) => self.get_final_tx_hash() == to.get_final_tx_hash(),
+            _ => false,
+        }
+    }
+
+    /// Whether the otx can return to pending after the node drops its final tx.
+    pub fn can_roll_back(&self) -> bool {
+        matches!(
+            self,
+            OpenTxStatus::Committed(_) | OpenTxStatus::Proposed(_) | OpenTxStatus::InBlock(_)
+        )
+    }
+
+    /// The hash of the final transaction which includes the open transaction.
+    pub fn get_final_tx_hash(&self) -> Option<&H256> {
+        match self {
+            OpenTxStatus::Committed(tx_hash) | OpenTxStatus::Proposed(tx_hash) => Some(tx_hash),
+            OpenTxStatus::InBlock(info) | OpenTxStatus::Confirmed(info) => Some(&info.tx_hash),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FinalTxInfo {
+    pub tx_hash: H256,
+    pub block_number: u64,
+    pub block_hash: H256,
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct OpenTxWithStatus {
     pub otx: OpenTransaction,
     pub status: OpenTxStatus,
+    /// Unix timestamp in seconds when the open transaction entered the pool.
+    #[serde(default)]
+    pub received_at: u64,
+    /// The number of blocks on top of the block including the final transaction, itself included.
+    /// 0 until the final transaction is in a block.
+    #[serde(default)]
+    pub confirmations: u64,
 }
 
 impl OpenTxWithStatus {
@@ -30,10 +113,162 @@ impl OpenTxWithStatus {
         OpenTxWithStatus {
             otx,
             status: OpenTxStatus::Pending,
+            received_at: unix_timestamp(),
+            confirmations: 0,
+        }
+    }
+}
+
+/// The current unix timestamp in seconds.
+pub fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+/// A change of the otx status, recorded in the status history of the otx.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StatusTransition {
+    /// `None` when the otx enters the pool.
+    pub from: Option<OpenTxStatus>,
+    pub to: OpenTxStatus,
+    /// Unix timestamp in seconds.
+    pub timestamp: u64,
+    /// The plugin or the pool component which made the change.
+    pub actor: String,
+    pub reason: Option<String>,
+}
+
+impl StatusTransition {
+    pub fn new(
+        from: Option<OpenTxStatus>,
+        to: OpenTxStatus,
+        actor: &str,
+        reason: Option<String>,
+    ) -> Self {
+        StatusTransition {
+            from,
+            to,
+            timestamp: unix_timestamp(),
+            actor: actor.to_owned(),
+            reason,
         }
     }
 }
 
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockInfo {
+    pub block_number: u64,
+    pub block_hash: H256,
+}
+
+impl BlockInfo {
+    pub fn new(block_number: u64, block_hash: H256) -> Self {
+        BlockInfo {
+            block_number,
+            block_hash,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OpenTxWithId {
+    pub id: H256,
+    #[serde(flatten)]
+    pub otx_with_status: OpenTxWithStatus,
+}
+
+/// An open transaction with the trace from its submission to the final transaction.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OpenTxDetail {
+    pub id: H256,
+    #[serde(flatten)]
+    pub otx_with_status: OpenTxWithStatus,
+    /// The ids of the merged otxs it went through, the outermost is at the end.
+    pub merged_otx_ids: Vec<H256>,
+    /// The hash of the final transaction sent to the CKB node.
+    pub final_tx_hash: Option<H256>,
+}
+
+/// The conditions to list otxs, all the given conditions must be satisfied.
+#[derive(Clone, Default, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OtxFilter {
+    pub status: Option<OpenTxStatusType>,
+    /// The lock script of an input cell.
+    pub input_lock: Option<Script>,
+    /// The lock script of an output cell.
+    pub output_lock: Option<Script>,
+    /// The type script of an input or output cell, e.g. an UDT.
+    pub type_script: Option<Script>,
+    /// The merged otx or the final tx which includes the otx.
+    pub tx_hash: Option<H256>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Order {
+    #[default]
+    Asc,
+    Desc,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Pagination<T> {
+    pub objects: Vec<T>,
+    /// Pass it as the `after` param to get the next page.
+    pub last_cursor: JsonBytes,
+}
+
+/// The number of otxs in the pool by status.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PoolStats {
+    pub pending_count: usize,
+    pub merged_count: usize,
+    /// The otxs whose final transaction has been sent to the CKB node but not confirmed yet.
+    pub sent_count: usize,
+    pub confirmed_count: usize,
+    /// The recently rejected otxs kept in the pool.
+    pub rejected_count: usize,
+    /// The max number of pending otxs, 0 means unlimited.
+    pub max_pool_size: usize,
+}
+
+/// A pool event recorded in the event log.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PoolEvent {
+    NewOtx(OpenTransaction),
+    OtxStatusChanged((H256, OpenTxStatus)),
+    CommitOtx(Vec<H256>),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventRecord {
+    /// The sequence number in the event log, increasing by one from 1.
+    pub seq: u64,
+    /// Unix timestamp in seconds.
+    pub timestamp: u64,
+    pub event: PoolEvent,
+}
+
+impl EventRecord {
+    pub fn new(seq: u64, event: PoolEvent) -> Self {
+        EventRecord {
+            seq,
+            timestamp: unix_timestamp(),
+            event,
+        }
+    }
+}
+
+/// A range of the event log.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EventPage {
+    pub events: Vec<EventRecord>,
+    /// The sequence number of the latest event in the log, 0 if there is none.
+    pub last_seq: u64,
+}
+
 #[derive(Debug)]
 pub struct PaymentAmount {
     pub capacity: i128,
@@ -66,4 +301,24 @@ mod test {
 
         assert_eq!(opentx.as_bytes(), opentx_rebuild.as_bytes());
     }
+
+    #[test]
+    fn test_status_transition() {
+        let tx_hash = H256::default();
+        let info = FinalTxInfo {
+            tx_hash: tx_hash.clone(),
+            block_number: 1,
+            block_hash: H256::default(),
+        };
+        let committed = OpenTxStatus::Committed(tx_hash.clone());
+        assert!(OpenTxStatus::Pending.can_transit_to(&OpenTxStatus::Merged(tx_hash.clone())));
+        assert!(OpenTxStatus::Merged(tx_hash.clone()).can_transit_to(&committed));
+        assert!(committed.can_transit_to(&OpenTxStatus::InBlock(info.clone())));
+        assert!(OpenTxStatus::InBlock(info.clone()).can_transit_to(&committed));
+        assert!(!committed.can_transit_to(&OpenTxStatus::Committed(H256([1; 32]))));
+        assert!(!committed.can_transit_to(&OpenTxStatus::Pending));
+        assert!(committed.can_roll_back());
+        assert!(!OpenTxStatus::Confirmed(info).can_transit_to(&committed));
+        assert!(!OpenTxStatus::Rejected("".to_owned()).can_transit_to(&OpenTxStatus::Pending));
+    }
 }
@@ -1,5 +1,7 @@
 /// NOTE: this example is for plugin integration tests
-use otx_pool_plugin_protocol::{MessageFromHost, MessageFromPlugin, PluginInfo};
+use otx_pool_plugin_protocol::{
+    EventType, MessageFromHost, MessageFromPlugin, PluginCapabilities, PluginInfo, PROTOCOL_VERSION,
+};
 
 use std::io::{self, Write};
 
@@ -26,18 +28,65 @@ fn main() {
 
 fn handle(msg: MessageFromHost) -> Option<MessageFromPlugin> {
     match msg {
-        MessageFromHost::GetPluginInfo => {
-            let info = PluginInfo {
-                name: String::from("plugin demo"),
-                description: String::from("It's a plugin demo"),
-                version: 0.to_string(),
+        MessageFromHost::GetPluginInfo => Some(MessageFromPlugin::PluginInfo(plugin_info())),
+        MessageFromHost::Handshake(_host_capabilities) => {
+            let capabilities = PluginCapabilities {
+                protocol_version: PROTOCOL_VERSION,
+                subscriptions: vec![
+                    EventType::NewOtx,
+                    EventType::NewInterval,
+                    EventType::CommitOtx,
+                ],
+                services: vec![],
+                rpc_namespace: Some(String::from("demo")),
             };
-            Some(MessageFromPlugin::PluginInfo(info))
+            Some(MessageFromPlugin::Handshake((plugin_info(), capabilities)))
         }
-        MessageFromHost::NewInterval(_) => {
-            log::info!("New interval");
+        MessageFromHost::Configure(config) | MessageFromHost::Reconfigure(config) => {
+            log::info!("Config: {}", config);
             Some(MessageFromPlugin::Ok)
         }
+        MessageFromHost::RpcCall((method, params)) => match method.as_str() {
+            "demo_echo" => Some(MessageFromPlugin::RpcResult(params)),
+            _ => Some(MessageFromPlugin::Error(format!(
+                "method {} not found",
+                method
+            ))),
+        },
+        MessageFromHost::Ping => Some(MessageFromPlugin::Pong),
+        // notifications expect no response
+        MessageFromHost::NewOtx((seq, otx)) => {
+            log::info!("New otx {}: {:?}", seq, otx.get_tx_hash());
+            None
+        }
+        MessageFromHost::NewInterval(elapsed) => {
+            log::info!("New interval: {}", elapsed);
+            None
+        }
+        MessageFromHost::CommitOtx((seq, otx_hashes)) => {
+            log::info!("Commit otxs {}: {:?}", seq, otx_hashes);
+            None
+        }
+        MessageFromHost::OtxPoolStart => {
+            log::info!("Otx pool started");
+            None
+        }
+        MessageFromHost::Timer((job_id, timestamp)) => {
+            log::info!("Job {} is due at {}", job_id, timestamp);
+            None
+        }
+        MessageFromHost::OtxPoolStop => {
+            log::info!("Otx pool stopping");
+            None
+        }
         _ => None,
     }
 }
+
+fn plugin_info() -> PluginInfo {
+    PluginInfo {
+        name: String::from("plugin demo"),
+        description: String::from("It's a plugin demo"),
+        version: 0.to_string(),
+    }
+}
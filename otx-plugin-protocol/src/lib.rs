@@ -1,28 +1,80 @@
 use otx_format::jsonrpc_types::OpenTransaction;
+use otx_format::types::{
+    BlockInfo, EventPage, OpenTxStatus, OpenTxWithId, OpenTxWithStatus, Order, OtxFilter,
+    Pagination, PoolStats,
+};
+
+use ckb_jsonrpc_types::JsonBytes;
 
 use ckb_types::core::service::Request;
 use ckb_types::H256;
 use crossbeam_channel::Sender;
 use serde_derive::{Deserialize, Serialize};
+use serde_json::Value;
 
 use std::path::PathBuf;
 
+/// The version of the messages between the host and the plugins,
+/// a plugin declaring another version is refused.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// The prefixes of the rpc methods served by the host and the built-in plugins.
+pub const RESERVED_RPC_NAMESPACES: [&str; 5] = ["admin", "get", "query", "rpc", "submit"];
+
 pub trait Plugin: Send + Sync {
     fn get_name(&self) -> String;
     fn get_meta(&self) -> PluginMeta;
     fn get_info(&self) -> PluginInfo;
-    fn on_new_otx(&self, _otx: OpenTransaction) {
+    fn get_capabilities(&self) -> PluginCapabilities {
+        // By default a plugin subscribes to all events and uses all host services.
+        PluginCapabilities::default()
+    }
+    /// Receive the `[plugins.<name>]` table of the config file, if there is one.
+    fn on_configure(&self, _config: Value) -> Result<(), String> {
+        // This is a default implementation that ignores the config.
+        Ok(())
+    }
+    /// Receive the updated table after the config file changes.
+    fn on_reconfigure(&self, _config: Value) -> Result<(), String> {
+        // This is a default implementation that ignores the config.
+        Ok(())
+    }
+    /// Called once the pool is started, or when the plugin is activated after that.
+    fn on_start(&self) {
+        // This is a default implementation that does nothing.
+    }
+    /// Called before the pool stops, the pool waits a bounded time for the callback and
+    /// the queued events to finish, e.g. a merge in flight.
+    fn on_stop(&self) {
+        // This is a default implementation that does nothing.
+    }
+    /// The new otx, the commit and the status change events carry their sequence number in
+    /// the event log, 0 if they are not recorded, e.g. the pending otxs replayed on start.
+    fn on_new_otx(&self, _seq: u64, _otx: OpenTransaction) {
         // This is a default implementation that does nothing.
     }
     fn on_new_intervel(&self, _interval: u64) {
         // This is a default implementation that does nothing.
     }
-    fn on_commit_otx(&self, _otxs: Vec<H256>) {
+    /// Called when a job scheduled by the plugin is due, with the unix timestamp in seconds.
+    fn on_timer(&self, _job_id: String, _timestamp: u64) {
+        // This is a default implementation that does nothing.
+    }
+    fn on_commit_otx(&self, _seq: u64, _otxs: Vec<H256>) {
+        // This is a default implementation that does nothing.
+    }
+    fn on_reject_otx(&self, _otxs: Vec<(H256, String)>) {
+        // This is a default implementation that does nothing.
+    }
+    fn on_otx_status_changed(&self, _seq: u64, _otx: (H256, OpenTxStatus)) {
+        // This is a default implementation that does nothing.
+    }
+    fn on_new_block(&self, _block: BlockInfo) {
         // This is a default implementation that does nothing.
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PluginMeta {
     /// The installation path of the plug-in, the built-in plugin binary_path is default value.
     pub binary_path: PathBuf,
@@ -42,7 +94,8 @@ impl PluginMeta {
     }
 }
 
-pub type HostServiceHandler = Sender<Request<MessageFromPlugin, MessageFromHost>>;
+/// The requests to the host service are tagged with the name of the calling plugin.
+pub type HostServiceHandler = Sender<Request<(String, MessageFromPlugin), MessageFromHost>>;
 
 pub enum MessageType {
     Request,
@@ -53,18 +106,37 @@ pub enum MessageType {
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum MessageFromHost {
     // Notify
-    NewOtx(OpenTransaction),
+    // the new otx, commit and status change events lead with their sequence number in the
+    // event log, 0 if not recorded
+    NewOtx((u64, OpenTransaction)),
     NewInterval(u64),
     OtxPoolStart,
     OtxPoolStop,
-    CommitOtx(Vec<H256>),
+    // the job id and the unix timestamp in seconds, sent only to the plugin scheduling the job
+    Timer((String, u64)),
+    CommitOtx((u64, Vec<H256>)),
+    RejectOtx(Vec<(H256, String)>),
+    OtxStatusChanged((u64, H256, OpenTxStatus)),
+    NewBlock(BlockInfo),
 
     // Request
     GetPluginInfo,
+    Handshake(HostCapabilities),
+    // the plugin config table, sent after the handshake and when it changes
+    Configure(Value),
+    Reconfigure(Value),
+    // the method in the rpc namespace of the plugin and its params
+    RpcCall((String, Value)),
+    Ping,
 
     // Response
     Ok,
     Error(String),
+    Otx(Option<OpenTxWithStatus>),
+    Otxs(Pagination<OpenTxWithId>),
+    PoolStats(PoolStats),
+    ChainTip(Option<BlockInfo>),
+    Events(EventPage),
 }
 
 impl MessageFromHost {
@@ -74,8 +146,24 @@ impl MessageFromHost {
             | Self::NewInterval(_)
             | Self::OtxPoolStart
             | Self::OtxPoolStop
-            | Self::CommitOtx(_) => MessageType::Notify,
-            Self::GetPluginInfo | Self::Ok | Self::Error(_) => MessageType::Request,
+            | Self::Timer(_)
+            | Self::CommitOtx(_)
+            | Self::RejectOtx(_)
+            | Self::OtxStatusChanged(_)
+            | Self::NewBlock(_) => MessageType::Notify,
+            Self::GetPluginInfo
+            | Self::Handshake(_)
+            | Self::Configure(_)
+            | Self::Reconfigure(_)
+            | Self::RpcCall(_)
+            | Self::Ping
+            | Self::Ok
+            | Self::Error(_)
+            | Self::Otx(_)
+            | Self::Otxs(_)
+            | Self::PoolStats(_)
+            | Self::ChainTip(_)
+            | Self::Events(_) => MessageType::Request,
         }
     }
 }
@@ -86,28 +174,270 @@ pub enum MessageFromPlugin {
     Ok,
     Error(String),
     PluginInfo(PluginInfo),
+    Handshake((PluginInfo, PluginCapabilities)),
+    RpcResult(Value),
+    Pong,
 
     // Request
     NewMergedOtx((OpenTransaction, Vec<H256>)),
-    DiscardOtx((H256, OpenTransaction)),
+    // the otx id and the reason
+    DiscardOtx((H256, String)),
+    // the otx id and the modified otx
     ModifyOtx((H256, OpenTransaction)),
     SentToCkb(H256),
     MergeOtxsAndSentToCkb((Vec<H256>, H256)),
+    // the read-only queries of the pool state
+    GetOtxById(H256),
+    // the filter, order, limit and the cursor to start after
+    QueryOtxs((OtxFilter, Order, u32, Option<JsonBytes>)),
+    GetPoolStats,
+    GetChainTip,
+    // the sequence number to start from and the max number of events
+    ReplayEvents((u64, u32)),
+    // the otx ids and the lease in seconds
+    ClaimOtxs((Vec<H256>, u64)),
+    ReleaseOtxs(Vec<H256>),
+    // add the job, or replace the job of the plugin with the same id
+    ScheduleJob(Job),
+    // the job id
+    CancelJob(String),
 }
 
 impl MessageFromPlugin {
     pub fn get_message_type(&self) -> MessageType {
         match self {
-            Self::Ok | Self::Error(_) | Self::PluginInfo(_) => MessageType::Response,
+            Self::Ok
+            | Self::Error(_)
+            | Self::PluginInfo(_)
+            | Self::Handshake(_)
+            | Self::RpcResult(_)
+            | Self::Pong => MessageType::Response,
             Self::NewMergedOtx(_)
             | Self::DiscardOtx(_)
             | Self::ModifyOtx(_)
             | Self::SentToCkb(_)
-            | Self::MergeOtxsAndSentToCkb(_) => MessageType::Request,
+            | Self::MergeOtxsAndSentToCkb(_)
+            | Self::GetOtxById(_)
+            | Self::QueryOtxs(_)
+            | Self::GetPoolStats
+            | Self::GetChainTip
+            | Self::ReplayEvents(_)
+            | Self::ClaimOtxs(_)
+            | Self::ReleaseOtxs(_)
+            | Self::ScheduleJob(_)
+            | Self::CancelJob(_) => MessageType::Request,
+        }
+    }
+
+    /// The host service a request calls, it must be declared by the plugin in the handshake.
+    pub fn get_host_service(&self) -> Option<HostService> {
+        match self {
+            Self::NewMergedOtx(_) => Some(HostService::MergeOtxs),
+            Self::DiscardOtx(_) => Some(HostService::DiscardOtx),
+            Self::ModifyOtx(_) => Some(HostService::ModifyOtx),
+            Self::SentToCkb(_) | Self::MergeOtxsAndSentToCkb(_) => Some(HostService::SendTx),
+            Self::GetOtxById(_)
+            | Self::QueryOtxs(_)
+            | Self::GetPoolStats
+            | Self::GetChainTip
+            | Self::ReplayEvents(_) => Some(HostService::QueryPool),
+            Self::ClaimOtxs(_) | Self::ReleaseOtxs(_) => Some(HostService::ClaimOtxs),
+            Self::ScheduleJob(_) | Self::CancelJob(_) => Some(HostService::Schedule),
+            Self::Ok
+            | Self::Error(_)
+            | Self::PluginInfo(_)
+            | Self::Handshake(_)
+            | Self::RpcResult(_)
+            | Self::Pong => None,
+        }
+    }
+}
+
+/// The events the host notifies the plugins of.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventType {
+    NewOtx,
+    NewInterval,
+    CommitOtx,
+    RejectOtx,
+    OtxStatusChanged,
+    NewBlock,
+}
+
+impl EventType {
+    pub const ALL: [EventType; 6] = [
+        EventType::NewOtx,
+        EventType::NewInterval,
+        EventType::CommitOtx,
+        EventType::RejectOtx,
+        EventType::OtxStatusChanged,
+        EventType::NewBlock,
+    ];
+}
+
+/// The groups of the requests a plugin can send to the host.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HostService {
+    /// `NewMergedOtx`
+    MergeOtxs,
+    /// `SentToCkb` and `MergeOtxsAndSentToCkb`
+    SendTx,
+    DiscardOtx,
+    ModifyOtx,
+    /// `GetOtxById`, `QueryOtxs`, `GetPoolStats`, `GetChainTip` and `ReplayEvents`
+    QueryPool,
+    /// `ClaimOtxs` and `ReleaseOtxs`
+    ClaimOtxs,
+    /// `ScheduleJob` and `CancelJob`
+    Schedule,
+}
+
+impl HostService {
+    pub const ALL: [HostService; 7] = [
+        HostService::MergeOtxs,
+        HostService::SendTx,
+        HostService::DiscardOtx,
+        HostService::ModifyOtx,
+        HostService::QueryPool,
+        HostService::ClaimOtxs,
+        HostService::Schedule,
+    ];
+}
+
+/// A job scheduled by a plugin, the host sends `Timer` to the plugin whenever it is due.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Job {
+    /// Unique among the jobs of the plugin.
+    pub id: String,
+    pub schedule: Schedule,
+}
+
+impl Job {
+    pub fn new(id: &str, schedule: Schedule) -> Self {
+        Job {
+            id: id.into(),
+            schedule,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Schedule {
+    /// Due once after the delay in seconds.
+    Once(u64),
+    /// Due every given seconds.
+    Every(u64),
+    /// Due at the minutes matching the cron expression `minute hour day month weekday` in UTC,
+    /// every field is `*`, a number, a range `a-b`, with an optional step `/n`, or a list of them.
+    Cron(String),
+}
+
+/// What the host offers, sent to the plugin at the start of the handshake.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HostCapabilities {
+    pub protocol_version: u32,
+    pub events: Vec<EventType>,
+    pub services: Vec<HostService>,
+    /// The prefixes of the rpc methods of the host, which plugins can not take as namespace.
+    pub reserved_rpc_namespaces: Vec<String>,
+}
+
+impl Default for HostCapabilities {
+    fn default() -> Self {
+        HostCapabilities {
+            protocol_version: PROTOCOL_VERSION,
+            events: EventType::ALL.to_vec(),
+            services: HostService::ALL.to_vec(),
+            reserved_rpc_namespaces: RESERVED_RPC_NAMESPACES
+                .iter()
+                .map(|namespace| namespace.to_string())
+                .collect(),
+        }
+    }
+}
+
+/// What the plugin declares in the handshake.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PluginCapabilities {
+    pub protocol_version: u32,
+    /// The events sent to the plugin, the others are not.
+    pub subscriptions: Vec<EventType>,
+    /// The host services the plugin calls, the requests of the others are refused.
+    pub services: Vec<HostService>,
+    /// The rpc methods named `<namespace>_*` are served by the plugin.
+    pub rpc_namespace: Option<String>,
+}
+
+impl Default for PluginCapabilities {
+    fn default() -> Self {
+        PluginCapabilities {
+            protocol_version: PROTOCOL_VERSION,
+            subscriptions: EventType::ALL.to_vec(),
+            services: HostService::ALL.to_vec(),
+            rpc_namespace: None,
         }
     }
 }
 
+impl PluginCapabilities {
+    /// Check whether the plugin can work with the host.
+    pub fn check_compatible(&self, host: &HostCapabilities) -> Result<(), String> {
+        if self.protocol_version != host.protocol_version {
+            return Err(format!(
+                "the plugin speaks protocol version {}, but the host speaks version {}",
+                self.protocol_version, host.protocol_version
+            ));
+        }
+        if let Some(service) = self
+            .services
+            .iter()
+            .find(|service| !host.services.contains(service))
+        {
+            return Err(format!("the host does not provide service {:?}", service));
+        }
+        if let Some(namespace) = &self.rpc_namespace {
+            if namespace.is_empty()
+                || !namespace
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit())
+            {
+                return Err(format!(
+                    "invalid rpc namespace {:?}, only lowercase letters and digits are allowed",
+                    namespace
+                ));
+            }
+            if host.reserved_rpc_namespaces.contains(namespace) {
+                return Err(format!(
+                    "rpc namespace {} is reserved by the host",
+                    namespace
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the rpc method is in the namespace of the plugin.
+    pub fn serves_rpc_method(&self, method: &str) -> bool {
+        match &self.rpc_namespace {
+            Some(namespace) => method
+                .strip_prefix(namespace.as_str())
+                .map_or(false, |rest| rest.starts_with('_')),
+            None => false,
+        }
+    }
+
+    pub fn is_subscribed(&self, event: EventType) -> bool {
+        self.subscriptions.contains(&event)
+    }
+
+    pub fn is_service_declared(&self, service: HostService) -> bool {
+        self.services.contains(&service)
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PluginInfo {
     pub name: String,
@@ -124,3 +454,70 @@ impl PluginInfo {
         }
     }
 }
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PluginDetail {
+    pub info: PluginInfo,
+    pub meta: PluginMeta,
+    pub capabilities: PluginCapabilities,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PluginLiveness {
+    /// The plugin is running, external plugins answer the health pings.
+    Alive,
+    /// The plugin process exited or stopped answering, and is waiting to be restarted.
+    Restarting,
+    /// The plugin used up its restart budget and is no longer restarted.
+    Dead,
+    /// The plugin process has been terminated by the host.
+    Stopped,
+    /// The plugin fell behind and no longer receives events, see the `disconnect` overflow policy.
+    Disconnected,
+}
+
+/// The delivery of events through the event queue of a plugin.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PluginEventStats {
+    /// The number of events waiting in the queue.
+    pub queued: u64,
+    pub delivered: u64,
+    /// The number of events dropped because the queue was full or the plugin disconnected.
+    pub dropped: u64,
+    /// The number of callbacks that ran longer than the callback timeout.
+    pub slow_callbacks: u64,
+    /// Unix timestamp in seconds when the running callback started,
+    /// if it has been running longer than the callback timeout.
+    #[serde(default)]
+    pub stuck_since: Option<u64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PluginHealth {
+    pub name: String,
+    pub liveness: PluginLiveness,
+    /// The process id of an external plugin.
+    pub pid: Option<u32>,
+    /// The number of times the plugin process has been restarted.
+    pub restarts: u32,
+    /// Unix timestamp in seconds of the last answered health ping.
+    pub last_seen_at: Option<u64>,
+    pub last_error: Option<String>,
+    #[serde(default)]
+    pub events: PluginEventStats,
+}
+
+impl PluginHealth {
+    pub fn new(name: &str, liveness: PluginLiveness) -> Self {
+        PluginHealth {
+            name: name.to_owned(),
+            liveness,
+            pid: None,
+            restarts: 0,
+            last_seen_at: None,
+            last_error: None,
+            events: PluginEventStats::default(),
+        }
+    }
+}
@@ -0,0 +1,286 @@
+use crate::notify::{NewBlock, NotifyController};
+use crate::pool::OtxPool;
+
+use otx_format::types::{BlockInfo, FinalTxInfo, OpenTxStatus};
+use otx_pool_config::{ChainWatcherConfig, CkbConfig};
+
+use anyhow::{anyhow, Result};
+use ckb_jsonrpc_types::Status;
+use ckb_sdk::CkbRpcClient;
+use ckb_types::H256;
+use crossbeam_channel::{bounded, select, Sender};
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+const ACTOR: &str = "chain_watcher";
+
+/// Follows the canonical chain of the CKB node.
+///
+/// The pending otxs whose inputs are consumed by other transactions are rejected.
+/// The sent txs are followed until they are confirmed, and rolled back if they are dropped by the node.
+pub struct ChainWatcher {
+    stop_handler: Sender<()>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ChainWatcher {
+    pub fn start(
+        config: ChainWatcherConfig,
+        ckb_config: CkbConfig,
+        otx_pool: Arc<OtxPool>,
+        notify_ctrl: NotifyController,
+    ) -> Result<ChainWatcher> {
+        let (stop_sender, stop_receiver) = bounded(1);
+        let poll_interval = Duration::from_secs(config.get_poll_interval_secs());
+        let mut state = ChainState::new(
+            CkbRpcClient::new(ckb_config.get_ckb_uri()),
+            config.get_reorg_window(),
+            config.get_confirmation_depth(),
+            otx_pool,
+            notify_ctrl,
+        );
+
+        let handle = thread::Builder::new()
+            .name("chain-watcher".to_owned())
+            .spawn(move || loop {
+                select! {
+                    recv(stop_receiver) -> _ => {
+                        log::info!("ChainWatcher received stop signal");
+                        break;
+                    }
+                    default(poll_interval) => {
+                        if let Err(err) = state.poll() {
+                            log::warn!("ChainWatcher poll error: {:?}", err);
+                        }
+                    }
+                }
+            })?;
+
+        Ok(ChainWatcher {
+            stop_handler: stop_sender,
+            thread: Some(handle),
+        })
+    }
+}
+
+impl Drop for ChainWatcher {
+    fn drop(&mut self) {
+        log::info!("ChainWatcher drop");
+        let _ = self.stop_handler.try_send(());
+        // the poll in progress finishes before the pool is flushed
+        if let Some(thread) = self.thread.take() {
+            if thread.join().is_err() {
+                log::error!("ChainWatcher thread panicked");
+            }
+        }
+    }
+}
+
+struct ChainState {
+    ckb_rpc_client: CkbRpcClient,
+    reorg_window: usize,
+    confirmation_depth: u64,
+    // the recent blocks of the canonical chain, the tip is at the back
+    recent_blocks: VecDeque<BlockInfo>,
+    otx_pool: Arc<OtxPool>,
+    notify_ctrl: NotifyController,
+}
+
+impl ChainState {
+    fn new(
+        ckb_rpc_client: CkbRpcClient,
+        reorg_window: usize,
+        confirmation_depth: u64,
+        otx_pool: Arc<OtxPool>,
+        notify_ctrl: NotifyController,
+    ) -> Self {
+        ChainState {
+            ckb_rpc_client,
+            reorg_window: reorg_window.max(1),
+            confirmation_depth: confirmation_depth.max(1),
+            recent_blocks: VecDeque::new(),
+            otx_pool,
+            notify_ctrl,
+        }
+    }
+
+    fn poll(&mut self) -> Result<()> {
+        let tip_number: u64 = self.ckb_rpc_client.get_tip_block_number()?.into();
+
+        // the watcher follows the chain from the tip it first sees
+        let start_number = match self.recent_blocks.back() {
+            Some(_) => self.detach_forked_blocks()? + 1,
+            None => tip_number,
+        };
+        for number in start_number..=tip_number {
+            self.attach_block(number)?;
+        }
+        self.track_sent_txs(tip_number)
+    }
+
+    /// Detach the recent blocks which are no longer in the canonical chain,
+    /// returns the number of the last block kept.
+    fn detach_forked_blocks(&mut self) -> Result<u64> {
+        while let Some(block) = self.recent_blocks.back() {
+            let canonical_hash = self
+                .ckb_rpc_client
+                .get_block_hash(block.block_number.into())?;
+            if canonical_hash.as_ref() == Some(&block.block_hash) {
+                break;
+            }
+            log::info!(
+                "block {} {:#x} is detached from the canonical chain",
+                block.block_number,
+                block.block_hash
+            );
+            self.recent_blocks.pop_back();
+        }
+
+        // the whole window is forked, restart from the previous block of the window
+        match self.recent_blocks.back() {
+            Some(block) => Ok(block.block_number),
+            None => {
+                let tip_number: u64 = self.ckb_rpc_client.get_tip_block_number()?.into();
+                Ok(tip_number.saturating_sub(self.reorg_window as u64))
+            }
+        }
+    }
+
+    /// Follow the sent txs until they are confirmed.
+    fn track_sent_txs(&mut self, tip_number: u64) -> Result<()> {
+        for (tx_hash, status) in self.otx_pool.get_unconfirmed_sent_txs() {
+            let new_status = match &status {
+                OpenTxStatus::InBlock(info) => {
+                    let canonical_hash = self
+                        .ckb_rpc_client
+                        .get_block_hash(info.block_number.into())?;
+                    if canonical_hash.as_ref() == Some(&info.block_hash) {
+                        Some(self.in_block_status(info.to_owned(), tip_number))
+                    } else {
+                        log::info!(
+                            "sent tx {:#x} is detached with block {} {:#x}",
+                            tx_hash,
+                            info.block_number,
+                            info.block_hash
+                        );
+                        self.query_sent_tx(&tx_hash, tip_number)?
+                    }
+                }
+                _ => self.query_sent_tx(&tx_hash, tip_number)?,
+            };
+            if let Some((new_status, confirmations)) = new_status {
+                self.otx_pool
+                    .update_sent_tx_status(&tx_hash, new_status, confirmations, ACTOR);
+            }
+        }
+        Ok(())
+    }
+
+    /// Query the status and the confirmations of a sent tx from the node,
+    /// the otxs in it are rolled back if the node has dropped the tx.
+    fn query_sent_tx(
+        &mut self,
+        tx_hash: &H256,
+        tip_number: u64,
+    ) -> Result<Option<(OpenTxStatus, u64)>> {
+        let tx_status = match self.ckb_rpc_client.get_transaction(tx_hash.to_owned())? {
+            Some(tx) => tx.tx_status,
+            None => {
+                self.otx_pool
+                    .rollback_sent_tx(tx_hash, ACTOR, "the tx is unknown to the node");
+                return Ok(None);
+            }
+        };
+        let status = match tx_status.status {
+            Status::Pending => (OpenTxStatus::Committed(tx_hash.to_owned()), 0),
+            Status::Proposed => (OpenTxStatus::Proposed(tx_hash.to_owned()), 0),
+            Status::Committed => {
+                let block_hash = tx_status
+                    .block_hash
+                    .ok_or_else(|| anyhow!("no block hash of committed tx {:#x}", tx_hash))?;
+                let header = self
+                    .ckb_rpc_client
+                    .get_header(block_hash.clone())?
+                    .ok_or_else(|| anyhow!("header {:#x} not found", block_hash))?;
+                let info = FinalTxInfo {
+                    tx_hash: tx_hash.to_owned(),
+                    block_number: header.inner.number.into(),
+                    block_hash,
+                };
+                self.in_block_status(info, tip_number)
+            }
+            Status::Unknown => {
+                self.otx_pool
+                    .rollback_sent_tx(tx_hash, ACTOR, "the tx is unknown to the node");
+                return Ok(None);
+            }
+            Status::Rejected => {
+                let reason = format!(
+                    "the tx is rejected by the node: {}",
+                    tx_status.reason.unwrap_or_default()
+                );
+                self.otx_pool.rollback_sent_tx(tx_hash, ACTOR, &reason);
+                return Ok(None);
+            }
+        };
+        Ok(Some(status))
+    }
+
+    fn in_block_status(&self, info: FinalTxInfo, tip_number: u64) -> (OpenTxStatus, u64) {
+        let confirmations = tip_number.saturating_sub(info.block_number) + 1;
+        if confirmations >= self.confirmation_depth {
+            (OpenTxStatus::Confirmed(info), self.confirmation_depth)
+        } else {
+            (OpenTxStatus::InBlock(info), confirmations)
+        }
+    }
+
+    fn attach_block(&mut self, number: u64) -> Result<()> {
+        let block = self
+            .ckb_rpc_client
+            .get_block_by_number(number.into())?
+            .ok_or_else(|| anyhow!("block {} not found", number))?;
+        let block_info = BlockInfo::new(number, block.header.hash.clone());
+
+        let mut dead_otxs: Vec<(H256, String)> = vec![];
+        for tx in block.transactions.iter() {
+            if self.otx_pool.get_sent_tx(&tx.hash).is_some() {
+                continue;
+            }
+            for input in tx.inner.inputs.iter() {
+                if let Some(id) = self
+                    .otx_pool
+                    .get_pending_otx_by_input(&input.previous_output)
+                {
+                    if id == tx.hash {
+                        continue;
+                    }
+                    dead_otxs.push((
+                        id,
+                        format!(
+                            "input {:#x}:{} is consumed by tx {:#x}",
+                            input.previous_output.tx_hash,
+                            input.previous_output.index.value(),
+                            tx.hash
+                        ),
+                    ));
+                }
+            }
+        }
+        dead_otxs.sort();
+        dead_otxs.dedup_by(|a, b| a.0 == b.0);
+        self.otx_pool.reject_otxs(dead_otxs, ACTOR);
+
+        self.recent_blocks.push_back(block_info.clone());
+        while self.recent_blocks.len() > self.reorg_window {
+            self.recent_blocks.pop_front();
+        }
+
+        self.otx_pool.set_chain_tip(block_info.clone());
+        self.notify_ctrl.notify::<NewBlock>(block_info);
+        Ok(())
+    }
+}
@@ -1,6 +1,7 @@
 use otx_format::error::{OtxError, OtxFormatError};
 
 use anyhow::Result;
+use ckb_types::H256;
 use derive_more::Display;
 use jsonrpc_core::{Error, ErrorCode};
 use molecule::error::VerificationError;
@@ -29,6 +30,12 @@ impl From<OtxFormatError> for OtxRpcError {
     }
 }
 
+impl From<OtxPoolError> for OtxRpcError {
+    fn from(err: OtxPoolError) -> Self {
+        OtxRpcError(Box::new(err))
+    }
+}
+
 impl From<VerificationError> for OtxRpcError {
     fn from(err: VerificationError) -> Self {
         OtxRpcError(Box::new(err))
@@ -39,12 +46,64 @@ impl From<VerificationError> for OtxRpcError {
 pub enum OtxPoolError {
     #[display(fmt = "Otx already exists")]
     OtxAlreadyExists,
+
+    #[display(fmt = "Storage error: {}", _0)]
+    StorageError(String),
+
+    #[display(fmt = "Otx pool is full")]
+    PoolIsFull,
+
+    #[display(fmt = "Otx inputs conflict with otx {:#x}", _0)]
+    InputConflict(H256),
+
+    #[display(fmt = "Otx fee is not higher than the conflicting otx {:#x}", _0)]
+    ReplacementFeeTooLow(H256),
+
+    #[display(fmt = "Malformed otx: {}", _0)]
+    MalformedOtx(String),
+
+    #[display(fmt = "Otx accounting meta mismatch: {}", _0)]
+    AccountingMismatch(String),
+
+    #[display(fmt = "Otx input is not live: {}", _0)]
+    InputNotLive(String),
+
+    #[display(fmt = "Otx script verification failed: {}", _0)]
+    ScriptVerificationFailed(String),
+
+    #[display(fmt = "CKB rpc error: {}", _0)]
+    CkbRpcError(String),
+
+    #[display(fmt = "Invalid params: {}", _0)]
+    InvalidParams(String),
+
+    #[display(fmt = "Invalid otx status transition: {}", _0)]
+    InvalidStatusTransition(String),
+
+    #[display(fmt = "Plugin error: {}", _0)]
+    PluginError(String),
+
+    #[display(fmt = "Otx {:#x} is claimed by another plugin", _0)]
+    OtxClaimed(H256),
 }
 
 impl OtxError for OtxPoolError {
     fn err_code(&self) -> i64 {
         match self {
             OtxPoolError::OtxAlreadyExists => -13100,
+            OtxPoolError::StorageError(_) => -13101,
+            OtxPoolError::PoolIsFull => -13102,
+            OtxPoolError::InputConflict(_) => -13103,
+            OtxPoolError::ReplacementFeeTooLow(_) => -13104,
+            OtxPoolError::MalformedOtx(_) => -13105,
+            OtxPoolError::AccountingMismatch(_) => -13106,
+            OtxPoolError::InputNotLive(_) => -13107,
+            OtxPoolError::ScriptVerificationFailed(_) => -13108,
+            OtxPoolError::CkbRpcError(_) => -13109,
+            OtxPoolError::InvalidParams(_) => -13110,
+            OtxPoolError::InvalidStatusTransition(_) => -13111,
+            OtxPoolError::PluginError(_) => -13112,
+            OtxPoolError::OtxClaimed(_) => -13113,
         }
     }
 
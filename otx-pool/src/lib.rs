@@ -1,50 +1,90 @@
+pub mod chain_watcher;
 pub mod error;
 pub mod logo;
 pub mod notify;
 pub mod plugin_extension;
 pub mod pool;
 pub mod rpc;
+pub mod scheduler;
+pub mod store;
+pub mod validator;
 
-use notify::{NotifyController, NotifyService};
-use otx_pool_config::NetworkConfig;
+use chain_watcher::ChainWatcher;
+use notify::{Interval, NotifyController};
+use otx_pool_config::{
+    ChainWatcherConfig, CkbConfig, NetworkConfig, NotifyConfig, PluginDispatchConfig,
+    PluginSupervisorConfig, PluginsConfig, PoolConfig, SchedulerConfig, ScriptConfig, StoreConfig,
+    ValidatorConfig,
+};
 use otx_pool_plugin_protocol::{HostServiceHandler, Plugin, PluginInfo, PluginMeta};
+use plugin_extension::config_watcher::PluginConfigWatcher;
 use plugin_extension::host_service::HostServiceProvider;
 use plugin_extension::manager::PluginManager;
-use pool::OtxPool;
-use rpc::{OtxPoolRpc, OtxPoolRpcImpl};
+use pool::{EventLog, OtxPool};
+use rpc::{
+    AdminRpc, AdminRpcImpl, OtxPoolRpc, OtxPoolRpcImpl, PluginRpc, PluginRpcImpl, PluginRpcRouter,
+};
+use scheduler::Scheduler;
+use store::open_store;
+use validator::OtxValidatorChain;
 
 use anyhow::{anyhow, Result};
 use ckb_async_runtime::{new_global_runtime, Handle, Runtime};
-use jsonrpc_core::{IoDelegate, IoHandler};
+use jsonrpc_core::{IoDelegate, MetaIoHandler};
 use jsonrpc_http_server::{Server, ServerBuilder};
 use jsonrpc_server_utils::cors::AccessControlAllowOrigin;
 use jsonrpc_server_utils::hosts::DomainsValidation;
 use tokio::task::JoinHandle;
 use tokio::time::{self, Duration, Instant};
 
-use std::{net::SocketAddr, path::Path, sync::Arc};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::{net::SocketAddr, path::Path};
 
 const RUNTIME_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
-const INTERVAL: Duration = Duration::from_secs(2);
+const PLUGIN_STOP_TIMEOUT: Duration = Duration::from_secs(10);
+// the resolution of the scheduled jobs
+const SCHEDULER_TICK: Duration = Duration::from_secs(1);
 const PLUGIN_ROOT: &str = "./free-space";
 
 pub struct OtxPoolService {
     runtime_handle: Handle,
     runtime: Runtime,
     network_config: NetworkConfig,
+    ckb_config: CkbConfig,
+    chain_watcher_config: ChainWatcherConfig,
     bind_addr: SocketAddr,
     notify_ctrl: NotifyController,
     otx_pool: Arc<OtxPool>,
+    scheduler: Arc<Scheduler>,
+    scheduler_config: SchedulerConfig,
+    validator_chain: Arc<OtxValidatorChain>,
     host_service_provider: HostServiceProvider,
-    plugin_manager: PluginManager,
+    plugin_manager: Arc<RwLock<PluginManager>>,
 
+    chain_watcher: Option<ChainWatcher>,
+    plugin_config_watcher: Option<PluginConfigWatcher>,
     interval_handler: Option<JoinHandle<()>>,
-    io_handler: Option<IoHandler>,
+    io_handler: Option<MetaIoHandler<(), PluginRpcRouter>>,
     rpc_server: Option<Server>,
 }
 
 impl OtxPoolService {
-    pub fn new(network_config: NetworkConfig) -> Result<Self> {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        network_config: NetworkConfig,
+        store_config: StoreConfig,
+        pool_config: PoolConfig,
+        ckb_config: CkbConfig,
+        chain_watcher_config: ChainWatcherConfig,
+        validator_config: ValidatorConfig,
+        notify_config: NotifyConfig,
+        scheduler_config: SchedulerConfig,
+        plugin_supervisor_config: PluginSupervisorConfig,
+        plugin_dispatch_config: PluginDispatchConfig,
+        plugins_config: PluginsConfig,
+        script_config: ScriptConfig,
+    ) -> Result<Self> {
         // runtime handle
         let (runtime_handle, runtime) = new_global_runtime();
 
@@ -52,40 +92,80 @@ impl OtxPoolService {
         let bind: Vec<&str> = network_config.get_listen_uri().split("//").collect();
         let bind_addr: SocketAddr = bind[1].parse()?;
 
-        // init notify service
-        let notify_service = NotifyService::new();
-        let notify_ctrl = notify_service.start(runtime_handle.clone());
+        // init event bus
+        let notify_ctrl = NotifyController::new(runtime_handle.clone(), notify_config);
 
         // otx pool
-        let otx_pool = Arc::new(OtxPool::new(notify_ctrl.clone()));
+        let store = open_store(&store_config)?;
+        let event_log = EventLog::open(store.clone(), store_config.get_max_events())?;
+        let otx_pool = Arc::new(OtxPool::new(
+            notify_ctrl.clone(),
+            store,
+            event_log,
+            pool_config,
+        )?);
+
+        // otx validators
+        let validator_chain = Arc::new(OtxValidatorChain::new(
+            &validator_config,
+            &ckb_config,
+            &script_config,
+        ));
+
+        // the jobs scheduled by the plugins
+        let scheduler = Arc::new(Scheduler::new(
+            scheduler_config.clone(),
+            notify_ctrl.clone(),
+        ));
 
         // init host service
-        let _service_provider = HostServiceProvider::start(notify_ctrl.clone(), otx_pool.clone())
+        let _service_provider = HostServiceProvider::start(otx_pool.clone(), scheduler.clone())
             .map_err(|err| anyhow!(err))?;
 
         // create plugin manager
-        let plugin_manager =
-            PluginManager::new(Path::new(PLUGIN_ROOT), _service_provider.handler());
+        let plugin_manager = Arc::new(RwLock::new(PluginManager::new(
+            Path::new(PLUGIN_ROOT),
+            runtime_handle.clone(),
+            _service_provider.handler(),
+            scheduler.clone(),
+            plugin_supervisor_config,
+            plugin_dispatch_config,
+            plugins_config,
+        )));
 
-        let io_handler = Some(IoHandler::new());
+        // the rpc calls in the namespaces of the external plugins are routed to the plugins
+        let io_handler = Some(MetaIoHandler::with_middleware(PluginRpcRouter::new(
+            plugin_manager.clone(),
+        )));
 
         Ok(OtxPoolService {
             runtime_handle,
             runtime,
             network_config,
+            ckb_config,
+            chain_watcher_config,
             bind_addr,
             notify_ctrl,
             otx_pool,
+            scheduler,
+            scheduler_config,
+            validator_chain,
             host_service_provider: _service_provider,
             plugin_manager,
+            chain_watcher: None,
+            plugin_config_watcher: None,
             interval_handler: None,
             rpc_server: None,
             io_handler,
         })
     }
 
-    pub fn add_plugin(&mut self, plugin: Box<Arc<dyn Plugin + Send>>) {
-        self.plugin_manager.register_built_in_plugins(plugin)
+    pub fn add_plugin(&mut self, plugin: Box<Arc<dyn Plugin + Send>>) -> Result<()> {
+        self.plugin_manager
+            .write()
+            .expect("write plugin manager")
+            .register_built_in_plugins(plugin)
+            .map_err(|e| anyhow!(e))
     }
 
     pub fn extended_rpc_with<T: Send + Sync>(&mut self, delegate: IoDelegate<T>) {
@@ -97,7 +177,9 @@ impl OtxPoolService {
 
     pub fn load_third_party_plugins(&mut self) -> Result<()> {
         self.plugin_manager
-            .load_third_party_plugins(&self.runtime_handle, &self.host_service_provider)
+            .write()
+            .expect("write plugin manager")
+            .load_third_party_plugins()
             .map_err(|e| anyhow!(e))
     }
 
@@ -105,33 +187,81 @@ impl OtxPoolService {
         self.host_service_provider.handler()
     }
 
-    pub fn get_plugin_configs(
-        &self,
-    ) -> &std::collections::HashMap<String, (PluginMeta, PluginInfo)> {
-        self.plugin_manager.plugin_configs()
+    pub fn get_plugin_configs(&self) -> HashMap<String, (PluginMeta, PluginInfo)> {
+        self.plugin_manager
+            .read()
+            .expect("read plugin manager")
+            .plugin_configs()
+            .to_owned()
+    }
+
+    /// Push the `[plugins.<name>]` tables to the plugins again when the config file changes.
+    pub fn watch_plugins_config(&mut self, config_path: &Path) -> Result<()> {
+        let watcher =
+            PluginConfigWatcher::start(config_path.to_owned(), self.plugin_manager.clone())?;
+        self.plugin_config_watcher = Some(watcher);
+        Ok(())
     }
 
     pub fn start(&mut self) {
-        // start interval loop
+        // start the scheduler loop, which also evicts the expired otxs every interval
         let notifier = self.notify_ctrl.clone();
+        let otx_pool = self.otx_pool.clone();
+        let scheduler = self.scheduler.clone();
+        let interval_secs = self.scheduler_config.get_interval_secs();
         self.interval_handler = Some(self.runtime_handle.spawn(async move {
-            let mut now = Instant::now().elapsed().as_secs();
-            let mut interval = time::interval(INTERVAL);
+            let started_at = Instant::now();
+            let mut next_interval = interval_secs;
+            let mut tick = time::interval(SCHEDULER_TICK);
             loop {
-                now += INTERVAL.as_secs();
-                interval.tick().await;
-                notifier.notify_interval(now);
+                tick.tick().await;
+                scheduler.fire_due_jobs();
+                let elapsed = started_at.elapsed().as_secs();
+                if elapsed >= next_interval {
+                    next_interval += interval_secs;
+                    otx_pool.evict_expired_otxs();
+                    notifier.notify::<Interval>(elapsed);
+                }
             }
         }));
 
         // subscribe events
         self.plugin_manager
+            .write()
+            .expect("write plugin manager")
             .subscribe_events(&self.notify_ctrl, &self.runtime_handle);
 
+        // start plugins before the pending otxs are replayed to them
+        self.plugin_manager
+            .write()
+            .expect("write plugin manager")
+            .start_plugins();
+
+        // replay the pending otxs reloaded from store
+        self.otx_pool.notify_pending_otxs();
+
+        // start chain watcher
+        if self.chain_watcher_config.is_enabled() {
+            let chain_watcher = ChainWatcher::start(
+                self.chain_watcher_config.clone(),
+                self.ckb_config.clone(),
+                self.otx_pool.clone(),
+                self.notify_ctrl.clone(),
+            )
+            .expect("start chain watcher");
+            self.chain_watcher = Some(chain_watcher);
+        }
+
         // init otx pool rpc
-        let rpc_impl = OtxPoolRpcImpl::new(self.otx_pool.clone());
+        let rpc_impl = OtxPoolRpcImpl::new(self.otx_pool.clone(), self.validator_chain.clone());
         let mut io_handler = self.io_handler.take().expect("io_handler");
         io_handler.extend_with(rpc_impl.to_delegate());
+        let plugin_rpc_impl = PluginRpcImpl::new(self.plugin_manager.clone());
+        io_handler.extend_with(plugin_rpc_impl.to_delegate());
+        if self.network_config.is_admin_rpc_enabled() {
+            let admin_rpc_impl = AdminRpcImpl::new(self.plugin_manager.clone());
+            io_handler.extend_with(admin_rpc_impl.to_delegate());
+        }
 
         // start rpc server
         let server = ServerBuilder::new(io_handler)
@@ -150,11 +280,22 @@ impl OtxPoolService {
     }
 
     pub fn stop(self) {
+        // stop accepting new otxs and events
+        if let Some(rpc_server) = self.rpc_server {
+            rpc_server.close();
+        }
         if let Some(interval_handler) = self.interval_handler {
             interval_handler.abort();
         }
-        if let Some(rpc_server) = self.rpc_server {
-            rpc_server.close();
+        drop(self.chain_watcher);
+        drop(self.plugin_config_watcher);
+
+        // let the plugins finish the merges in flight
+        PluginManager::stop(&self.plugin_manager, PLUGIN_STOP_TIMEOUT);
+
+        // the pool state is flushed before exit
+        if let Err(err) = self.otx_pool.flush() {
+            log::error!("flush otx store error: {:?}", err);
         }
         self.runtime.shutdown_timeout(RUNTIME_SHUTDOWN_TIMEOUT);
     }
@@ -1,314 +1,399 @@
 use ckb_async_runtime::Handle;
-use ckb_stop_handler::{SignalSender, StopHandler};
 use ckb_types::H256;
 use otx_format::jsonrpc_types::OpenTransaction;
-use tokio::sync::{
-    mpsc::{self, Receiver, Sender},
-    oneshot,
-};
+use otx_format::types::{BlockInfo, OpenTxStatus};
+use otx_pool_config::{NotifyConfig, OverflowPolicy};
+use tokio::sync::mpsc::{self, error::TrySendError, Receiver, Sender, UnboundedSender};
 
+use std::any::{Any, TypeId};
 use std::collections::HashMap;
+use std::fmt::Debug;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 
 pub type RuntimeHandle = Handle;
 
-/// Asynchronous request sent to the service.
-pub struct Request<A, R> {
-    /// Oneshot channel for the service to send back the response.
-    pub responder: oneshot::Sender<R>,
-    /// Request arguments.
-    pub arguments: A,
+/// A topic of the event bus, every topic carries the events of one type.
+pub trait Topic: 'static {
+    type Event: Clone + Debug + Send + 'static;
+    const NAME: &'static str;
 }
 
-impl<A, R> Request<A, R> {
-    /// Call the service with the arguments and wait for the response.
-    pub async fn call(sender: &Sender<Request<A, R>>, arguments: A) -> Option<R> {
-        let (responder, response) = oneshot::channel();
-        let _ = sender
-            .send(Request {
-                responder,
-                arguments,
-            })
-            .await;
-        response.await.ok()
-    }
+macro_rules! topics {
+    ($($(#[$attr:meta])* $topic:ident => $event:ty;)*) => {
+        $(
+            $(#[$attr])*
+            pub struct $topic;
+
+            impl Topic for $topic {
+                type Event = $event;
+                const NAME: &'static str = stringify!($topic);
+            }
+        )*
+    };
 }
 
-pub const SIGNAL_CHANNEL_SIZE: usize = 1;
-pub const REGISTER_CHANNEL_SIZE: usize = 2;
-pub const NOTIFY_CHANNEL_SIZE: usize = 128;
+// the events recorded in the event log carry their sequence number, 0 if not recorded
+topics! {
+    /// A new otx entered the pool.
+    NewOtx => (u64, OpenTransaction);
+    /// The otxs included in a final tx sent to the CKB node.
+    CommitOtx => (u64, Vec<H256>);
+    /// The rejected otxs and the reasons.
+    RejectOtx => Vec<(H256, String)>;
+    OtxStatusChanged => (u64, H256, OpenTxStatus);
+    /// A new tip block of the canonical chain.
+    NewBlock => BlockInfo;
+    /// The seconds elapsed since the pool started.
+    Interval => u64;
+    /// The plugin, the id of its job which is due and the unix timestamp in seconds.
+    Timer => (String, String, u64);
+}
 
-pub type NotifyRegister<M> = Sender<Request<String, Receiver<M>>>;
+pub type SubscriberId = u64;
 
-#[derive(Clone)]
-pub struct NotifyController {
-    stop: StopHandler<()>,
-    new_open_tx_register: NotifyRegister<OpenTransaction>,
-    new_open_tx_notifier: Sender<OpenTransaction>,
-    commit_open_tx_register: NotifyRegister<Vec<H256>>,
-    commit_open_tx_notifier: Sender<Vec<H256>>,
-    interval_register: NotifyRegister<u64>,
-    interval_notifier: Sender<u64>,
-    start_register: NotifyRegister<()>,
-    start_notifier: Sender<()>,
-    stop_register: NotifyRegister<()>,
-    stop_notifier: Sender<()>,
-    handle: Handle,
+/// The receiving end of a subscription, the subscriber is pruned once it is dropped.
+pub struct Subscription<E> {
+    id: SubscriberId,
+    receiver: Receiver<E>,
 }
 
-impl Drop for NotifyController {
-    fn drop(&mut self) {
-        self.stop.try_send(());
+impl<E> Subscription<E> {
+    pub fn id(&self) -> SubscriberId {
+        self.id
+    }
+
+    pub async fn recv(&mut self) -> Option<E> {
+        self.receiver.recv().await
     }
 }
 
-pub struct NotifyService {
-    new_open_tx_subscribers: HashMap<String, Sender<OpenTransaction>>,
-    commit_open_tx_subscribers: HashMap<String, Sender<Vec<H256>>>,
-    interval_subscribers: HashMap<String, Sender<u64>>,
-    start_subscribers: HashMap<String, Sender<()>>,
-    stop_subscribers: HashMap<String, Sender<()>>,
+/// The events a subscriber has missed because its buffer was full.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SubscriberStats {
+    pub topic: &'static str,
+    pub name: String,
+    pub dropped: u64,
 }
 
-impl Default for NotifyService {
-    fn default() -> Self {
-        Self::new()
-    }
+struct Subscriber<E> {
+    name: String,
+    sender: Sender<E>,
+    dropped: Arc<AtomicU64>,
 }
 
-impl NotifyService {
-    pub fn new() -> Self {
-        Self {
-            new_open_tx_subscribers: HashMap::default(),
-            commit_open_tx_subscribers: HashMap::default(),
-            interval_subscribers: HashMap::default(),
-            start_subscribers: HashMap::default(),
-            stop_subscribers: HashMap::default(),
+impl<E> Clone for Subscriber<E> {
+    fn clone(&self) -> Self {
+        Subscriber {
+            name: self.name.clone(),
+            sender: self.sender.clone(),
+            dropped: self.dropped.clone(),
         }
     }
+}
 
-    /// start background tokio spawned task.
-    pub fn start(mut self, handle: Handle) -> NotifyController {
-        let (signal_sender, mut signal_receiver) = oneshot::channel();
-
-        let (new_open_tx_register, mut new_open_tx_register_receiver) =
-            mpsc::channel(REGISTER_CHANNEL_SIZE);
-        let (new_open_tx_sender, mut new_open_tx_receiver) = mpsc::channel(NOTIFY_CHANNEL_SIZE);
-
-        let (commit_open_tx_register, mut commit_open_tx_register_receiver) =
-            mpsc::channel(REGISTER_CHANNEL_SIZE);
-        let (commit_open_tx_sender, mut commit_open_tx_receiver) =
-            mpsc::channel(NOTIFY_CHANNEL_SIZE);
-
-        let (interval_register, mut interval_register_receiver) =
-            mpsc::channel(REGISTER_CHANNEL_SIZE);
-        let (interval_sender, mut interval_receiver) = mpsc::channel(NOTIFY_CHANNEL_SIZE);
-
-        let (start_register, mut start_register_receiver) = mpsc::channel(REGISTER_CHANNEL_SIZE);
-        let (start_sender, mut start_receiver) = mpsc::channel(NOTIFY_CHANNEL_SIZE);
+type Subscribers<E> = Arc<Mutex<HashMap<SubscriberId, Subscriber<E>>>>;
+type DroppedCounters = Arc<Mutex<HashMap<SubscriberId, (&'static str, String, Arc<AtomicU64>)>>>;
 
-        let (stop_register, mut stop_register_receiver) = mpsc::channel(REGISTER_CHANNEL_SIZE);
-        let (stop_sender, mut stop_receiver) = mpsc::channel(NOTIFY_CHANNEL_SIZE);
+struct TopicChannel<E> {
+    publisher: UnboundedSender<E>,
+    subscribers: Subscribers<E>,
+}
 
-        handle.spawn(async move {
-            loop {
-                tokio::select! {
-                    _ = &mut signal_receiver => {
-                        break;
-                    }
-                    Some(msg) = new_open_tx_register_receiver.recv() => { self.handle_register_new_open_tx(msg) },
-                    Some(msg) = new_open_tx_receiver.recv() => { self.handle_notify_new_open_tx(msg).await },
-                    Some(msg) = commit_open_tx_register_receiver.recv() => { self.handle_register_commit_open_tx(msg) },
-                    Some(msg) = commit_open_tx_receiver.recv() => { self.handle_notify_commit_open_tx(msg).await },
-                    Some(msg) = interval_register_receiver.recv() => { self.handle_register_interval(msg) },
-                    Some(msg) = interval_receiver.recv() => { self.handle_notify_interval(msg).await },
-                    Some(msg) = start_register_receiver.recv() => { self.handle_register_start(msg) },
-                    Some(()) = start_receiver.recv() => { self.handle_notify_start().await },
-                    Some(msg) = stop_register_receiver.recv() => { self.handle_register_stop(msg) },
-                    Some(()) = stop_receiver.recv() => { self.handle_notify_stop().await },
-                    else => break,
-                }
-            }
-        });
+/// The event bus of the pool.
+///
+/// Every topic is served by its own task, which delivers the events to the subscribers
+/// in the order they are published. Publishing never waits, and every subscriber has its own
+/// buffer. When the buffer of a subscriber is full, the overflow policy decides whether the
+/// event is dropped for that subscriber, the topic waits for it, or it is unsubscribed.
+#[derive(Clone)]
+pub struct NotifyController {
+    topics: Arc<Mutex<HashMap<TypeId, Box<dyn Any + Send>>>>,
+    next_subscriber_id: Arc<AtomicU64>,
+    dropped_counters: DroppedCounters,
+    config: NotifyConfig,
+    handle: Handle,
+}
 
+impl NotifyController {
+    pub fn new(handle: Handle, config: NotifyConfig) -> Self {
         NotifyController {
-            new_open_tx_register,
-            new_open_tx_notifier: new_open_tx_sender,
-            commit_open_tx_register,
-            commit_open_tx_notifier: commit_open_tx_sender,
-            interval_register,
-            interval_notifier: interval_sender,
-            start_register,
-            start_notifier: start_sender,
-            stop_register,
-            stop_notifier: stop_sender,
-            stop: StopHandler::new(
-                SignalSender::Tokio(signal_sender),
-                None,
-                "notify".to_string(),
-            ),
+            topics: Arc::new(Mutex::new(HashMap::new())),
+            next_subscriber_id: Arc::new(AtomicU64::new(0)),
+            dropped_counters: Arc::new(Mutex::new(HashMap::new())),
+            config,
             handle,
         }
     }
 
-    fn handle_register_new_open_tx(&mut self, msg: Request<String, Receiver<OpenTransaction>>) {
-        let Request {
-            responder,
-            arguments: name,
-        } = msg;
-        log::debug!("Register new_open_tx {:?}", name);
-        let (sender, receiver) = mpsc::channel(NOTIFY_CHANNEL_SIZE);
-        self.new_open_tx_subscribers.insert(name, sender);
-        let _ = responder.send(receiver);
+    pub fn subscribe<T: Topic>(&self, name: &str) -> Subscription<T::Event> {
+        let id = self.next_subscriber_id.fetch_add(1, Ordering::Relaxed);
+        let name = name.to_owned();
+        log::debug!("Register {} {:?}", T::NAME, name);
+        let (sender, receiver) = mpsc::channel(self.config.get_channel_size());
+        let dropped = Arc::new(AtomicU64::new(0));
+        self.dropped_counters
+            .lock()
+            .expect("lock dropped counters")
+            .insert(id, (T::NAME, name.clone(), dropped.clone()));
+        self.with_topic::<T, _>(|channel| {
+            channel
+                .subscribers
+                .lock()
+                .expect("lock subscribers")
+                .insert(
+                    id,
+                    Subscriber {
+                        name,
+                        sender,
+                        dropped,
+                    },
+                );
+        });
+        Subscription { id, receiver }
     }
 
-    async fn handle_notify_new_open_tx(&mut self, otx: OpenTransaction) {
-        log::trace!("event new open tx {:?}", otx);
-        // notify all subscribers
-        for subscriber in self.new_open_tx_subscribers.values() {
-            let _ = subscriber.send(otx.clone()).await;
-        }
+    /// Returns false if the subscriber has already gone.
+    pub fn unsubscribe<T: Topic>(&self, id: SubscriberId) -> bool {
+        self.dropped_counters
+            .lock()
+            .expect("lock dropped counters")
+            .remove(&id);
+        self.with_topic::<T, _>(|channel| {
+            channel
+                .subscribers
+                .lock()
+                .expect("lock subscribers")
+                .remove(&id)
+                .is_some()
+        })
     }
 
-    fn handle_register_commit_open_tx(&mut self, msg: Request<String, Receiver<Vec<H256>>>) {
-        let Request {
-            responder,
-            arguments: name,
-        } = msg;
-        log::debug!("Register commit_open_tx {:?}", name);
-        let (sender, receiver) = mpsc::channel(NOTIFY_CHANNEL_SIZE);
-        self.commit_open_tx_subscribers.insert(name, sender);
-        let _ = responder.send(receiver);
+    /// The dropped events of the current subscribers.
+    pub fn get_subscriber_stats(&self) -> Vec<SubscriberStats> {
+        let mut stats: Vec<SubscriberStats> = self
+            .dropped_counters
+            .lock()
+            .expect("lock dropped counters")
+            .values()
+            .map(|(topic, name, dropped)| SubscriberStats {
+                topic: *topic,
+                name: name.to_owned(),
+                dropped: dropped.load(Ordering::Relaxed),
+            })
+            .collect();
+        stats.sort_by(|a, b| (a.topic, &a.name).cmp(&(b.topic, &b.name)));
+        stats
     }
 
-    async fn handle_notify_commit_open_tx(&mut self, otx_hashes: Vec<H256>) {
-        log::trace!("event commit open tx {:?}", otx_hashes);
-        // notify all subscribers
-        for subscriber in self.commit_open_tx_subscribers.values() {
-            let _ = subscriber.send(otx_hashes.clone()).await;
-        }
+    pub fn notify<T: Topic>(&self, event: T::Event) {
+        self.with_topic::<T, _>(|channel| {
+            let _ = channel.publisher.send(event);
+        });
     }
 
-    fn handle_register_interval(&mut self, msg: Request<String, Receiver<u64>>) {
-        let Request {
-            responder,
-            arguments: name,
-        } = msg;
-        log::debug!("Register interval event plugin: {:?}", name);
-        let (sender, receiver) = mpsc::channel(NOTIFY_CHANNEL_SIZE);
-        self.interval_subscribers.insert(name, sender);
-        let _ = responder.send(receiver);
+    fn with_topic<T: Topic, R>(&self, f: impl FnOnce(&TopicChannel<T::Event>) -> R) -> R {
+        let mut topics = self.topics.lock().expect("lock topics");
+        let channel = topics
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(self.open_topic::<T>()));
+        f(channel
+            .downcast_ref::<TopicChannel<T::Event>>()
+            .expect("topic channel"))
     }
 
-    async fn handle_notify_interval(&mut self, elapsed_secs: u64) {
-        log::trace!("event interval");
-        // notify all subscribers
-        for subscriber in self.interval_subscribers.values() {
-            let _ = subscriber.send(elapsed_secs).await;
-        }
-    }
+    fn open_topic<T: Topic>(&self) -> TopicChannel<T::Event> {
+        let (publisher, mut receiver) = mpsc::unbounded_channel::<T::Event>();
+        let subscribers: Subscribers<T::Event> = Arc::new(Mutex::new(HashMap::new()));
 
-    fn handle_register_start(&mut self, msg: Request<String, Receiver<()>>) {
-        let Request {
-            responder,
-            arguments: name,
-        } = msg;
-        log::debug!("Register start {:?}", name);
-        let (sender, receiver) = mpsc::channel(NOTIFY_CHANNEL_SIZE);
-        self.start_subscribers.insert(name, sender);
-        let _ = responder.send(receiver);
-    }
+        let task_subscribers = subscribers.clone();
+        let dropped_counters = self.dropped_counters.clone();
+        let overflow_policy = self.config.get_overflow_policy();
+        self.handle.spawn(async move {
+            while let Some(event) = receiver.recv().await {
+                log::trace!("event {} {:?}", T::NAME, event);
+                let snapshot: Vec<(SubscriberId, Subscriber<T::Event>)> = task_subscribers
+                    .lock()
+                    .expect("lock subscribers")
+                    .iter()
+                    .map(|(id, subscriber)| (*id, subscriber.clone()))
+                    .collect();
+                let mut gone = vec![];
+                for (id, subscriber) in snapshot {
+                    let event = match subscriber.sender.try_send(event.clone()) {
+                        Ok(()) => continue,
+                        Err(TrySendError::Closed(_)) => {
+                            log::debug!("Prune {} {:?}", T::NAME, subscriber.name);
+                            gone.push(id);
+                            continue;
+                        }
+                        Err(TrySendError::Full(event)) => event,
+                    };
+                    match overflow_policy {
+                        OverflowPolicy::Drop => {
+                            subscriber.dropped.fetch_add(1, Ordering::Relaxed);
+                            log::warn!(
+                                "{} subscriber {:?} is full, drop the event",
+                                T::NAME,
+                                subscriber.name
+                            );
+                        }
+                        OverflowPolicy::Block => {
+                            if subscriber.sender.send(event).await.is_err() {
+                                gone.push(id);
+                            }
+                        }
+                        OverflowPolicy::Disconnect => {
+                            subscriber.dropped.fetch_add(1, Ordering::Relaxed);
+                            log::error!(
+                                "{} subscriber {:?} is full, unsubscribe it",
+                                T::NAME,
+                                subscriber.name
+                            );
+                            gone.push(id);
+                        }
+                    }
+                }
+                if !gone.is_empty() {
+                    let mut subscribers = task_subscribers.lock().expect("lock subscribers");
+                    let mut dropped_counters =
+                        dropped_counters.lock().expect("lock dropped counters");
+                    for id in gone {
+                        subscribers.remove(&id);
+                        dropped_counters.remove(&id);
+                    }
+                }
+            }
+        });
 
-    async fn handle_notify_start(&mut self) {
-        log::trace!("event start");
-        // notify all subscribers
-        for subscriber in self.start_subscribers.values() {
-            let _ = subscriber.send(()).await;
+        TopicChannel {
+            publisher,
+            subscribers,
         }
     }
+}
 
-    fn handle_register_stop(&mut self, msg: Request<String, Receiver<()>>) {
-        let Request {
-            responder,
-            arguments: name,
-        } = msg;
-        log::debug!("Register stop {:?}", name);
-        let (sender, receiver) = mpsc::channel(NOTIFY_CHANNEL_SIZE);
-        self.stop_subscribers.insert(name, sender);
-        let _ = responder.send(receiver);
-    }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    async fn handle_notify_stop(&mut self) {
-        log::trace!("event stop");
-        // notify all subscribers
-        for subscriber in self.stop_subscribers.values() {
-            let _ = subscriber.send(()).await;
-        }
-    }
-}
+    use ckb_async_runtime::new_background_runtime;
 
-impl NotifyController {
-    pub async fn subscribe_new_open_tx<S: ToString>(&self, name: S) -> Receiver<OpenTransaction> {
-        Request::call(&self.new_open_tx_register, name.to_string())
-            .await
-            .expect("Subscribe new open tx should be OK")
-    }
+    use std::thread;
+    use std::time::{Duration, Instant};
 
-    pub fn notify_new_open_tx(&self, otx: OpenTransaction) {
-        let new_open_tx_notifier = self.new_open_tx_notifier.clone();
-        self.handle.spawn(async move {
-            let _ = new_open_tx_notifier.send(otx).await;
-        });
+    fn new_notify_ctrl(channel_size: usize, overflow_policy: OverflowPolicy) -> NotifyController {
+        NotifyController::new(
+            new_background_runtime(),
+            NotifyConfig::new(channel_size, overflow_policy),
+        )
     }
 
-    pub async fn subscribe_commit_open_tx<S: ToString>(&self, name: S) -> Receiver<Vec<H256>> {
-        Request::call(&self.commit_open_tx_register, name.to_string())
-            .await
-            .expect("Subscribe commit open tx should be OK")
+    fn recv<E>(subscription: &mut Subscription<E>) -> Option<E> {
+        subscription.receiver.blocking_recv()
     }
 
-    pub fn notify_commit_open_tx(&self, otx_hashes: Vec<H256>) {
-        let commit_open_tx_notifier = self.commit_open_tx_notifier.clone();
-        self.handle.spawn(async move {
-            let _ = commit_open_tx_notifier.send(otx_hashes).await;
-        });
+    // the events are delivered by the topic tasks in the background
+    fn wait_until(condition: impl Fn() -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !condition() {
+            assert!(Instant::now() < deadline, "condition not met in time");
+            thread::sleep(Duration::from_millis(10));
+        }
     }
 
-    pub async fn subscribe_interval<S: ToString>(&self, name: S) -> Receiver<u64> {
-        Request::call(&self.interval_register, name.to_string())
-            .await
-            .expect("Subscribe interval should be OK")
+    fn subscriber_names(notify_ctrl: &NotifyController) -> Vec<String> {
+        notify_ctrl
+            .get_subscriber_stats()
+            .into_iter()
+            .map(|stats| stats.name)
+            .collect()
     }
 
-    pub fn notify_interval(&self, elapsed_secs: u64) {
-        let interval_notifier = self.interval_notifier.clone();
-        self.handle.spawn(async move {
-            let _ = interval_notifier.send(elapsed_secs).await;
-        });
+    #[test]
+    fn test_publish_subscribe() {
+        let notify_ctrl = new_notify_ctrl(16, OverflowPolicy::Drop);
+        let mut first = notify_ctrl.subscribe::<Interval>("first");
+        let mut second = notify_ctrl.subscribe::<Interval>("second");
+        let mut blocks = notify_ctrl.subscribe::<NewBlock>("blocks");
+
+        let block = BlockInfo::new(1, H256([1; 32]));
+        notify_ctrl.notify::<Interval>(1);
+        notify_ctrl.notify::<Interval>(2);
+        notify_ctrl.notify::<NewBlock>(block.clone());
+
+        // every subscriber receives the events of its topic in order
+        assert_eq!(recv(&mut first), Some(1));
+        assert_eq!(recv(&mut first), Some(2));
+        assert_eq!(recv(&mut second), Some(1));
+        assert_eq!(recv(&mut second), Some(2));
+        assert_eq!(recv(&mut blocks), Some(block));
+        assert!(blocks.receiver.try_recv().is_err());
+        assert_eq!(
+            subscriber_names(&notify_ctrl),
+            vec!["blocks", "first", "second"]
+        );
     }
 
-    pub async fn subscribe_start<S: ToString>(&self, name: S) -> Receiver<()> {
-        Request::call(&self.start_register, name.to_string())
-            .await
-            .expect("Subscribe start should be OK")
+    #[test]
+    fn test_unsubscribe() {
+        let notify_ctrl = new_notify_ctrl(16, OverflowPolicy::Drop);
+        let mut kept = notify_ctrl.subscribe::<Interval>("kept");
+        let mut removed = notify_ctrl.subscribe::<Interval>("removed");
+        let dropped = notify_ctrl.subscribe::<Interval>("dropped");
+
+        assert!(notify_ctrl.unsubscribe::<Interval>(removed.id()));
+        assert!(!notify_ctrl.unsubscribe::<Interval>(removed.id()));
+        assert_eq!(recv(&mut removed), None);
+
+        // the dropped subscription is pruned on the next event
+        drop(dropped);
+        notify_ctrl.notify::<Interval>(1);
+        assert_eq!(recv(&mut kept), Some(1));
+        wait_until(|| subscriber_names(&notify_ctrl) == vec!["kept"]);
     }
 
-    pub fn notify_start(&self) {
-        let start_notifier = self.start_notifier.clone();
-        self.handle.spawn(async move {
-            let _ = start_notifier.send(()).await;
-        });
+    #[test]
+    fn test_overflow_drop() {
+        let notify_ctrl = new_notify_ctrl(1, OverflowPolicy::Drop);
+        let mut slow = notify_ctrl.subscribe::<Interval>("slow");
+        for elapsed in 1..=3 {
+            notify_ctrl.notify::<Interval>(elapsed);
+        }
+        wait_until(|| notify_ctrl.get_subscriber_stats()[0].dropped == 2);
+        assert_eq!(recv(&mut slow), Some(1));
+
+        // the subscriber receives the events again once it catches up
+        notify_ctrl.notify::<Interval>(4);
+        assert_eq!(recv(&mut slow), Some(4));
+        assert_eq!(notify_ctrl.get_subscriber_stats()[0].dropped, 2);
     }
 
-    pub async fn subscribe_stop<S: ToString>(&self, name: S) -> Receiver<()> {
-        Request::call(&self.stop_register, name.to_string())
-            .await
-            .expect("Subscribe stop should be OK")
+    #[test]
+    fn test_overflow_block() {
+        let notify_ctrl = new_notify_ctrl(1, OverflowPolicy::Block);
+        let mut slow = notify_ctrl.subscribe::<Interval>("slow");
+        for elapsed in 1..=3 {
+            notify_ctrl.notify::<Interval>(elapsed);
+        }
+        for elapsed in 1..=3 {
+            assert_eq!(recv(&mut slow), Some(elapsed));
+        }
+        assert_eq!(notify_ctrl.get_subscriber_stats()[0].dropped, 0);
     }
 
-    pub fn notify_stop(&self) {
-        let stop_notifier = self.stop_notifier.clone();
-        self.handle.spawn(async move {
-            let _ = stop_notifier.send(()).await;
-        });
+    #[test]
+    fn test_overflow_disconnect() {
+        let notify_ctrl = new_notify_ctrl(1, OverflowPolicy::Disconnect);
+        let mut slow = notify_ctrl.subscribe::<Interval>("slow");
+        for elapsed in 1..=2 {
+            notify_ctrl.notify::<Interval>(elapsed);
+        }
+
+        // the queued event is still received, then the subscription ends
+        wait_until(|| notify_ctrl.get_subscriber_stats().is_empty());
+        assert_eq!(recv(&mut slow), Some(1));
+        assert_eq!(recv(&mut slow), None);
+        assert!(!notify_ctrl.unsubscribe::<Interval>(slow.id()));
     }
 }
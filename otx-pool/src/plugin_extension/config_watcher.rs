@@ -0,0 +1,78 @@
+use super::manager::PluginManager;
+
+use otx_pool_config::{parse, ConfigFile};
+
+use anyhow::Result;
+use crossbeam_channel::{bounded, select, Sender};
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Polls the modification time of the config file,
+/// and pushes the changed `[plugins.<name>]` tables to the plugins.
+pub struct PluginConfigWatcher {
+    stop_handler: Sender<()>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl PluginConfigWatcher {
+    pub fn start(
+        config_path: PathBuf,
+        plugin_manager: Arc<RwLock<PluginManager>>,
+    ) -> Result<PluginConfigWatcher> {
+        let (stop_sender, stop_receiver) = bounded(1);
+        let mut last_modified = modified_time(&config_path);
+
+        let handle = thread::Builder::new()
+            .name("plugin-config-watcher".to_owned())
+            .spawn(move || loop {
+                select! {
+                    recv(stop_receiver) -> _ => {
+                        log::info!("PluginConfigWatcher received stop signal");
+                        break;
+                    }
+                    default(POLL_INTERVAL) => {
+                        let modified = modified_time(&config_path);
+                        if modified == last_modified {
+                            continue;
+                        }
+                        last_modified = modified;
+                        match parse::<ConfigFile>(&config_path) {
+                            Ok(config) => {
+                                PluginManager::reconfigure_plugins(&plugin_manager, config.plugins)
+                            }
+                            Err(err) => log::warn!("reload config {:?} error: {}", config_path, err),
+                        }
+                    }
+                }
+            })?;
+
+        Ok(PluginConfigWatcher {
+            stop_handler: stop_sender,
+            thread: Some(handle),
+        })
+    }
+}
+
+impl Drop for PluginConfigWatcher {
+    fn drop(&mut self) {
+        let _ = self.stop_handler.try_send(());
+        // the reconfiguration in progress finishes before the plugins are stopped
+        if let Some(thread) = self.thread.take() {
+            if thread.join().is_err() {
+                log::error!("PluginConfigWatcher thread panicked");
+            }
+        }
+    }
+}
+
+fn modified_time(config_path: &Path) -> Option<SystemTime> {
+    fs::metadata(config_path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
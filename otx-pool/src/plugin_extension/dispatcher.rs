@@ -0,0 +1,456 @@
+use otx_format::jsonrpc_types::OpenTransaction;
+use otx_format::types::{BlockInfo, OpenTxStatus};
+use otx_pool_config::{OverflowPolicy, PluginDispatchConfig};
+use otx_pool_plugin_protocol::{EventType, Plugin, PluginEventStats};
+
+use anyhow::Result;
+use ckb_types::H256;
+use crossbeam_channel::{bounded, Sender, TrySendError};
+use tokio::task::block_in_place;
+
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+const LIFECYCLE_EVENT_TIMEOUT: Duration = Duration::from_secs(5);
+const IDLE_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// An event delivered to the plugins.
+#[derive(Clone, Debug)]
+pub enum PluginEvent {
+    Start,
+    Stop,
+    // the plugin, the job id and the unix timestamp in seconds
+    Timer((String, String, u64)),
+    // the events recorded in the event log carry their sequence number
+    NewOtx((u64, OpenTransaction)),
+    NewInterval(u64),
+    CommitOtx((u64, Vec<H256>)),
+    RejectOtx(Vec<(H256, String)>),
+    OtxStatusChanged((u64, H256, OpenTxStatus)),
+    NewBlock(BlockInfo),
+}
+
+impl PluginEvent {
+    /// The lifecycle events and the timers have no event type, the former are delivered
+    /// to every plugin, and the latter only to the plugin scheduling the job.
+    pub fn event_type(&self) -> Option<EventType> {
+        match self {
+            PluginEvent::Start | PluginEvent::Stop | PluginEvent::Timer(_) => None,
+            PluginEvent::NewOtx(_) => Some(EventType::NewOtx),
+            PluginEvent::NewInterval(_) => Some(EventType::NewInterval),
+            PluginEvent::CommitOtx(_) => Some(EventType::CommitOtx),
+            PluginEvent::RejectOtx(_) => Some(EventType::RejectOtx),
+            PluginEvent::OtxStatusChanged(_) => Some(EventType::OtxStatusChanged),
+            PluginEvent::NewBlock(_) => Some(EventType::NewBlock),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            PluginEvent::Start => "start",
+            PluginEvent::Stop => "stop",
+            PluginEvent::Timer(_) => "timer",
+            PluginEvent::NewOtx(_) => "new_otx",
+            PluginEvent::NewInterval(_) => "new_interval",
+            PluginEvent::CommitOtx(_) => "commit_otx",
+            PluginEvent::RejectOtx(_) => "reject_otx",
+            PluginEvent::OtxStatusChanged(_) => "otx_status_changed",
+            PluginEvent::NewBlock(_) => "new_block",
+        }
+    }
+}
+
+#[derive(Default)]
+struct DispatchStats {
+    delivered: AtomicU64,
+    dropped: AtomicU64,
+    slow_callbacks: AtomicU64,
+    disconnected: AtomicBool,
+    // a callback is running
+    busy: AtomicBool,
+    // unix timestamp in milliseconds when the running callback started, 0 if there is none
+    callback_started_at: AtomicU64,
+}
+
+/// Delivers the events to a plugin through its own bounded queue and worker thread,
+/// so that a slow plugin does not hold up the events of the other plugins.
+///
+/// A callback running longer than the callback timeout is counted as slow,
+/// and reported as stuck while it is still running.
+/// When the queue is full, the new event is handled by the overflow policy,
+/// except for the lifecycle events which wait for room in the queue.
+pub struct PluginDispatcher {
+    plugin_name: String,
+    subscriptions: Vec<EventType>,
+    overflow_policy: OverflowPolicy,
+    callback_timeout: Duration,
+    sender: Sender<PluginEvent>,
+    stats: Arc<DispatchStats>,
+    _thread: Option<JoinHandle<()>>,
+}
+
+impl PluginDispatcher {
+    pub fn start(
+        plugin: Box<Arc<dyn Plugin + Send>>,
+        config: &PluginDispatchConfig,
+    ) -> Result<PluginDispatcher> {
+        let plugin_name = plugin.get_name();
+        let capabilities = plugin.get_capabilities();
+        let subscriptions = EventType::ALL
+            .into_iter()
+            .filter(|event| capabilities.is_subscribed(*event))
+            .collect();
+        let (sender, receiver) = bounded::<PluginEvent>(config.get_queue_size());
+        let stats = Arc::new(DispatchStats::default());
+        let callback_timeout = Duration::from_millis(config.get_callback_timeout_millis());
+
+        let worker_stats = stats.clone();
+        let handle = thread::Builder::new()
+            .name(format!("plugin-dispatcher-{}", plugin_name))
+            .spawn(move || {
+                // the loop ends once the dispatcher is dropped and the queue is drained
+                for event in receiver.iter() {
+                    if worker_stats.disconnected.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    let event_name = event.name();
+                    let started_at = Instant::now();
+                    worker_stats.busy.store(true, Ordering::Relaxed);
+                    worker_stats
+                        .callback_started_at
+                        .store(unix_timestamp_millis(), Ordering::Relaxed);
+                    deliver(&plugin, event);
+                    worker_stats.callback_started_at.store(0, Ordering::Relaxed);
+                    worker_stats.busy.store(false, Ordering::Relaxed);
+                    let elapsed = started_at.elapsed();
+                    worker_stats.delivered.fetch_add(1, Ordering::Relaxed);
+                    if elapsed > callback_timeout {
+                        worker_stats.slow_callbacks.fetch_add(1, Ordering::Relaxed);
+                        log::warn!(
+                            "plugin {} took {} ms to handle {}",
+                            plugin.get_name(),
+                            elapsed.as_millis(),
+                            event_name
+                        );
+                    }
+                }
+            })?;
+
+        Ok(PluginDispatcher {
+            plugin_name,
+            subscriptions,
+            overflow_policy: config.get_overflow_policy(),
+            callback_timeout,
+            sender,
+            stats,
+            _thread: Some(handle),
+        })
+    }
+
+    pub fn get_plugin_name(&self) -> &str {
+        &self.plugin_name
+    }
+
+    pub fn is_subscribed(&self, event: &PluginEvent) -> bool {
+        match event {
+            PluginEvent::Timer((plugin_name, _, _)) => *plugin_name == self.plugin_name,
+            event => event
+                .event_type()
+                .map_or(true, |event_type| self.subscriptions.contains(&event_type)),
+        }
+    }
+
+    pub fn is_disconnected(&self) -> bool {
+        self.stats.disconnected.load(Ordering::Relaxed)
+    }
+
+    /// Put the event into the queue of the plugin.
+    pub fn dispatch(&self, event: PluginEvent) {
+        if self.is_disconnected() {
+            self.stats.dropped.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        if matches!(event, PluginEvent::Start | PluginEvent::Stop) {
+            if let Err(err) = self.sender.send_timeout(event, LIFECYCLE_EVENT_TIMEOUT) {
+                self.stats.dropped.fetch_add(1, Ordering::Relaxed);
+                log::warn!(
+                    "failed to send the {} event to plugin {}",
+                    err.into_inner().name(),
+                    self.plugin_name
+                );
+            }
+            return;
+        }
+        let event = match self.sender.try_send(event) {
+            Ok(()) => return,
+            Err(TrySendError::Full(event)) => event,
+            Err(TrySendError::Disconnected(_)) => {
+                self.stats.dropped.fetch_add(1, Ordering::Relaxed);
+                return;
+            }
+        };
+        match self.overflow_policy {
+            OverflowPolicy::Drop => {
+                self.stats.dropped.fetch_add(1, Ordering::Relaxed);
+                log::warn!(
+                    "event queue of plugin {} is full, drop {}",
+                    self.plugin_name,
+                    event.name()
+                );
+            }
+            OverflowPolicy::Block => {
+                if block_in_place(|| self.sender.send(event)).is_err() {
+                    self.stats.dropped.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+            OverflowPolicy::Disconnect => {
+                self.stats.disconnected.store(true, Ordering::Relaxed);
+                self.stats
+                    .dropped
+                    .fetch_add(self.sender.len() as u64 + 1, Ordering::Relaxed);
+                log::error!(
+                    "event queue of plugin {} is full, the plugin is disconnected",
+                    self.plugin_name
+                );
+            }
+        }
+    }
+
+    /// Wait until the queued events are handled, returns false if the deadline passes first.
+    pub fn wait_idle(&self, deadline: Instant) -> bool {
+        loop {
+            if self.is_disconnected()
+                || (self.sender.is_empty() && !self.stats.busy.load(Ordering::Relaxed))
+            {
+                return true;
+            }
+            if Instant::now() >= deadline {
+                return false;
+            }
+            thread::sleep(IDLE_POLL_INTERVAL);
+        }
+    }
+
+    pub fn get_stats(&self) -> PluginEventStats {
+        PluginEventStats {
+            queued: if self.is_disconnected() {
+                0
+            } else {
+                self.sender.len() as u64
+            },
+            delivered: self.stats.delivered.load(Ordering::Relaxed),
+            dropped: self.stats.dropped.load(Ordering::Relaxed),
+            slow_callbacks: self.stats.slow_callbacks.load(Ordering::Relaxed),
+            stuck_since: self.stuck_since(),
+        }
+    }
+
+    /// Unix timestamp in seconds when the running callback started,
+    /// if it has been running longer than the callback timeout.
+    fn stuck_since(&self) -> Option<u64> {
+        let started_at = self.stats.callback_started_at.load(Ordering::Relaxed);
+        let elapsed = unix_timestamp_millis().saturating_sub(started_at);
+        if started_at != 0 && elapsed > self.callback_timeout.as_millis() as u64 {
+            Some(started_at / 1000)
+        } else {
+            None
+        }
+    }
+}
+
+fn unix_timestamp_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+fn deliver(plugin: &Arc<dyn Plugin + Send>, event: PluginEvent) {
+    match event {
+        PluginEvent::Start => plugin.on_start(),
+        PluginEvent::Stop => plugin.on_stop(),
+        PluginEvent::Timer((_, job_id, timestamp)) => plugin.on_timer(job_id, timestamp),
+        PluginEvent::NewOtx((seq, otx)) => plugin.on_new_otx(seq, otx),
+        PluginEvent::NewInterval(elapsed) => plugin.on_new_intervel(elapsed),
+        PluginEvent::CommitOtx((seq, otxs)) => plugin.on_commit_otx(seq, otxs),
+        PluginEvent::RejectOtx(otxs) => plugin.on_reject_otx(otxs),
+        PluginEvent::OtxStatusChanged((seq, id, status)) => {
+            plugin.on_otx_status_changed(seq, (id, status))
+        }
+        PluginEvent::NewBlock(block) => plugin.on_new_block(block),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use otx_pool_plugin_protocol::{PluginCapabilities, PluginInfo, PluginMeta};
+
+    use crossbeam_channel::{unbounded, Receiver};
+
+    use std::path::PathBuf;
+    use std::sync::Mutex;
+
+    // handles an interval once the test lets it through the gate
+    struct SlowPlugin {
+        capabilities: PluginCapabilities,
+        gate: Receiver<()>,
+        intervals: Mutex<Vec<u64>>,
+    }
+
+    impl Plugin for SlowPlugin {
+        fn get_name(&self) -> String {
+            "slow".to_owned()
+        }
+
+        fn get_meta(&self) -> PluginMeta {
+            PluginMeta::new(PathBuf::default(), true, true)
+        }
+
+        fn get_info(&self) -> PluginInfo {
+            PluginInfo::new("slow", "", "")
+        }
+
+        fn get_capabilities(&self) -> PluginCapabilities {
+            self.capabilities.clone()
+        }
+
+        fn on_new_intervel(&self, interval: u64) {
+            let _ = self.gate.recv();
+            self.intervals.lock().unwrap().push(interval);
+        }
+    }
+
+    fn start_dispatcher(
+        queue_size: usize,
+        overflow_policy: OverflowPolicy,
+    ) -> (PluginDispatcher, Arc<SlowPlugin>, Sender<()>) {
+        start_dispatcher_with(PluginDispatchConfig::new(
+            queue_size,
+            10_000,
+            overflow_policy,
+        ))
+    }
+
+    fn start_dispatcher_with(
+        config: PluginDispatchConfig,
+    ) -> (PluginDispatcher, Arc<SlowPlugin>, Sender<()>) {
+        let (gate_sender, gate) = unbounded();
+        let plugin = Arc::new(SlowPlugin {
+            capabilities: PluginCapabilities {
+                subscriptions: vec![EventType::NewInterval],
+                ..Default::default()
+            },
+            gate,
+            intervals: Mutex::new(vec![]),
+        });
+        let dispatcher =
+            PluginDispatcher::start(Box::new(plugin.clone() as Arc<dyn Plugin + Send>), &config)
+                .unwrap();
+        (dispatcher, plugin, gate_sender)
+    }
+
+    fn wait_until(condition: impl Fn() -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !condition() {
+            assert!(Instant::now() < deadline, "condition not met in time");
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    // the worker is stuck in the first callback and the queue is full
+    fn fill_queue(dispatcher: &PluginDispatcher) {
+        dispatcher.dispatch(PluginEvent::NewInterval(1));
+        wait_until(|| dispatcher.stats.busy.load(Ordering::Relaxed));
+        dispatcher.dispatch(PluginEvent::NewInterval(2));
+    }
+
+    #[test]
+    fn test_subscriptions() {
+        let (dispatcher, _plugin, _gate) = start_dispatcher(1, OverflowPolicy::Drop);
+        assert!(dispatcher.is_subscribed(&PluginEvent::NewInterval(1)));
+        assert!(!dispatcher.is_subscribed(&PluginEvent::CommitOtx((0, vec![]))));
+        assert!(dispatcher.is_subscribed(&PluginEvent::Start));
+        assert!(dispatcher.is_subscribed(&PluginEvent::Timer((
+            "slow".to_owned(),
+            "job".to_owned(),
+            0
+        ))));
+        assert!(!dispatcher.is_subscribed(&PluginEvent::Timer((
+            "other".to_owned(),
+            "job".to_owned(),
+            0
+        ))));
+    }
+
+    #[test]
+    fn test_overflow_drop() {
+        let (dispatcher, plugin, gate) = start_dispatcher(1, OverflowPolicy::Drop);
+        fill_queue(&dispatcher);
+        dispatcher.dispatch(PluginEvent::NewInterval(3));
+        assert_eq!(dispatcher.get_stats().dropped, 1);
+        assert_eq!(dispatcher.get_stats().queued, 1);
+
+        gate.send(()).unwrap();
+        gate.send(()).unwrap();
+        wait_until(|| dispatcher.get_stats().delivered == 2);
+        assert_eq!(*plugin.intervals.lock().unwrap(), vec![1, 2]);
+        let stats = dispatcher.get_stats();
+        assert_eq!((stats.delivered, stats.dropped), (2, 1));
+        assert!(!dispatcher.is_disconnected());
+    }
+
+    #[test]
+    fn test_overflow_block() {
+        let (dispatcher, plugin, gate) = start_dispatcher(1, OverflowPolicy::Block);
+        let dispatcher = Arc::new(dispatcher);
+        fill_queue(&dispatcher);
+        let blocked_dispatcher = dispatcher.clone();
+        let blocked = thread::spawn(move || {
+            blocked_dispatcher.dispatch(PluginEvent::NewInterval(3));
+        });
+
+        for _ in 0..3 {
+            gate.send(()).unwrap();
+        }
+        blocked.join().unwrap();
+        wait_until(|| dispatcher.get_stats().delivered == 3);
+        assert_eq!(*plugin.intervals.lock().unwrap(), vec![1, 2, 3]);
+        let stats = dispatcher.get_stats();
+        assert_eq!((stats.delivered, stats.dropped), (3, 0));
+    }
+
+    #[test]
+    fn test_overflow_disconnect() {
+        let (dispatcher, plugin, gate) = start_dispatcher(1, OverflowPolicy::Disconnect);
+        fill_queue(&dispatcher);
+        dispatcher.dispatch(PluginEvent::NewInterval(3));
+        assert!(dispatcher.is_disconnected());
+        // the queued event and the new one are dropped, as well as the later events
+        assert_eq!(dispatcher.get_stats().dropped, 2);
+        dispatcher.dispatch(PluginEvent::NewInterval(4));
+        assert_eq!(dispatcher.get_stats().dropped, 3);
+        assert_eq!(dispatcher.get_stats().queued, 0);
+
+        gate.send(()).unwrap();
+        wait_until(|| dispatcher.get_stats().delivered == 1);
+        assert_eq!(*plugin.intervals.lock().unwrap(), vec![1]);
+    }
+
+    #[test]
+    fn test_stuck_callback() {
+        let (dispatcher, _plugin, gate) =
+            start_dispatcher_with(PluginDispatchConfig::new(1, 100, OverflowPolicy::Drop));
+        dispatcher.dispatch(PluginEvent::NewInterval(1));
+        // the callback is reported before it returns
+        wait_until(|| dispatcher.get_stats().stuck_since.is_some());
+        assert_eq!(dispatcher.get_stats().slow_callbacks, 0);
+
+        gate.send(()).unwrap();
+        wait_until(|| dispatcher.get_stats().slow_callbacks == 1);
+        assert_eq!(dispatcher.get_stats().stuck_since, None);
+    }
+}
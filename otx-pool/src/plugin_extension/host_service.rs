@@ -1,11 +1,12 @@
-use crate::notify::NotifyController;
 use crate::pool::OtxPool;
+use crate::scheduler::Scheduler;
 
 use otx_format::jsonrpc_types::OpenTransaction;
-use otx_format::types::OpenTxStatus;
-use otx_pool_plugin_protocol::{HostServiceHandler, MessageFromHost, MessageFromPlugin};
+use otx_format::types::{OpenTxStatus, Order, OtxFilter};
+use otx_pool_plugin_protocol::{HostServiceHandler, Job, MessageFromHost, MessageFromPlugin};
 
 use anyhow::{anyhow, Result};
+use ckb_jsonrpc_types::JsonBytes;
 use ckb_types::core::service::Request;
 use ckb_types::H256;
 use crossbeam_channel::{bounded, select, Sender};
@@ -22,8 +23,8 @@ pub struct HostServiceProvider {
 
 impl HostServiceProvider {
     pub fn start(
-        notify_ctrl: NotifyController,
         otx_pool: Arc<OtxPool>,
+        scheduler: Arc<Scheduler>,
     ) -> Result<HostServiceProvider, String> {
         let (sender, receiver) = bounded(5);
         let (stop_sender, stop_receiver) = bounded(1);
@@ -38,44 +39,84 @@ impl HostServiceProvider {
                         }
                         Ok(Request {
                             responder,
-                            arguments,
+                            arguments: (plugin_name, message),
                         }) => {
-                            log::debug!("ServiceProvider received a request: {:?}", arguments);
-                            match arguments {
-                                MessageFromPlugin::DiscardOtx(_id) => {
-                                    let _ = responder.send(MessageFromHost::Ok);
-                                }
-                                MessageFromPlugin::SentToCkb(otx_hash) => {
-                                    Self::handle_sent_ckb_tx(
-                                        otx_hash,
-                                        notify_ctrl.clone(),
-                                        otx_pool.clone(),
-                                    );
-                                    let _ = responder.send(MessageFromHost::Ok);
-                                }
+                            log::debug!("ServiceProvider received a request from {}: {:?}", plugin_name, message);
+                            let result = match message {
+                                MessageFromPlugin::DiscardOtx((id, reason)) => Self::handle_discard_otx(
+                                    id,
+                                    reason,
+                                    &plugin_name,
+                                    otx_pool.clone(),
+                                ),
+                                MessageFromPlugin::ModifyOtx((id, otx)) => Self::handle_modify_otx(
+                                    id,
+                                    otx,
+                                    &plugin_name,
+                                    otx_pool.clone(),
+                                ),
+                                MessageFromPlugin::SentToCkb(otx_hash) => Self::handle_sent_ckb_tx(
+                                    otx_hash,
+                                    &plugin_name,
+                                    otx_pool.clone(),
+                                ),
                                 MessageFromPlugin::MergeOtxsAndSentToCkb((otx_hashes, tx_hash)) => {
                                     Self::handle_merge_otxs_and_sent(
                                         otx_hashes,
                                         tx_hash,
-                                        notify_ctrl.clone(),
+                                        &plugin_name,
                                         otx_pool.clone(),
-                                    );
-                                    let _ = responder.send(MessageFromHost::Ok);
+                                    )
                                 }
                                 MessageFromPlugin::NewMergedOtx((merged_otx, otx_hashes)) => {
-                                    match Self::handle_new_merged_otx(
+                                    Self::handle_new_merged_otx(
                                         merged_otx,
                                         otx_hashes,
-                                        otx_pool.clone()
-                                    ) {
-                                        Ok(_) => {let _ = responder.send(MessageFromHost::Ok);}
-                                        Err(err) => {
-                                            log::warn!("handle new merged otx error: {:?}", err);
-                                            let _ = responder.send(MessageFromHost::Error(err.to_string()));
-                                        }
-                                    }
+                                        &plugin_name,
+                                        otx_pool.clone(),
+                                    )
+                                }
+                                MessageFromPlugin::GetOtxById(id) => {
+                                    Ok(MessageFromHost::Otx(otx_pool.get_otx_by_id(id)))
+                                }
+                                MessageFromPlugin::QueryOtxs((filter, order, limit, after)) => {
+                                    Self::handle_query_otxs(filter, order, limit, after, otx_pool.clone())
+                                }
+                                MessageFromPlugin::GetPoolStats => {
+                                    Ok(MessageFromHost::PoolStats(otx_pool.get_stats()))
+                                }
+                                MessageFromPlugin::GetChainTip => {
+                                    Ok(MessageFromHost::ChainTip(otx_pool.get_chain_tip()))
+                                }
+                                MessageFromPlugin::ReplayEvents((from_seq, limit)) => otx_pool
+                                    .get_events(from_seq, limit as usize)
+                                    .map(MessageFromHost::Events)
+                                    .map_err(|err| anyhow!(err.to_string())),
+                                MessageFromPlugin::ClaimOtxs((ids, lease_secs)) => Self::handle_claim_otxs(
+                                    ids,
+                                    lease_secs,
+                                    &plugin_name,
+                                    otx_pool.clone(),
+                                ),
+                                MessageFromPlugin::ReleaseOtxs(ids) => {
+                                    otx_pool.release_otxs(&ids, &plugin_name);
+                                    Ok(MessageFromHost::Ok)
+                                }
+                                MessageFromPlugin::ScheduleJob(job) => {
+                                    Self::handle_schedule_job(job, &plugin_name, scheduler.clone())
+                                }
+                                MessageFromPlugin::CancelJob(job_id) => {
+                                    scheduler.cancel(&plugin_name, &job_id);
+                                    Ok(MessageFromHost::Ok)
+                                }
+                                message => Err(anyhow!("unsupported message: {:?}", message)),
+                            };
+                            match result {
+                                Ok(response) => {let _ = responder.send(response);}
+                                Err(err) => {
+                                    log::warn!("handle request from {} error: {:?}", plugin_name, err);
+                                    let _ = responder.send(MessageFromHost::Error(err.to_string()));
                                 }
-                                _ => unreachable!(),
                             }
                         }
                     }
@@ -109,8 +150,9 @@ impl HostServiceProvider {
     fn handle_new_merged_otx(
         new_merged_otx: OpenTransaction,
         included_otx_hashes: Vec<H256>,
+        plugin_name: &str,
         otx_pool: Arc<OtxPool>,
-    ) -> Result<()> {
+    ) -> Result<MessageFromHost> {
         let merged_otx_hash = if let Ok(hash) = new_merged_otx.get_tx_hash() {
             hash
         } else {
@@ -124,18 +166,94 @@ impl HostServiceProvider {
                 .map(|hash| hash.to_string())
                 .collect::<Vec<String>>()
         );
-        for otx_hash in included_otx_hashes.iter() {
-            otx_pool.update_otx_status(otx_hash, OpenTxStatus::Merged(merged_otx_hash.clone()));
-        }
-        otx_pool.insert(new_merged_otx).expect("insert merged otx");
-        Ok(())
+        otx_pool
+            .merge_otxs(new_merged_otx, &included_otx_hashes, plugin_name)
+            .map_err(|err| anyhow!(err.to_string()))?;
+        Ok(MessageFromHost::Ok)
+    }
+
+    fn handle_discard_otx(
+        id: H256,
+        reason: String,
+        plugin_name: &str,
+        otx_pool: Arc<OtxPool>,
+    ) -> Result<MessageFromHost> {
+        log::info!("handle discard otx: {:#x}, reason: {}", id, reason);
+        otx_pool
+            .discard_otx(&id, reason, plugin_name)
+            .map_err(|err| anyhow!(err.to_string()))?;
+        Ok(MessageFromHost::Ok)
+    }
+
+    fn handle_modify_otx(
+        id: H256,
+        otx: OpenTransaction,
+        plugin_name: &str,
+        otx_pool: Arc<OtxPool>,
+    ) -> Result<MessageFromHost> {
+        let new_id = otx_pool
+            .modify_otx(&id, otx, plugin_name)
+            .map_err(|err| anyhow!(err.to_string()))?;
+        log::info!("handle modify otx: {:#x}, modified otx: {:#x}", id, new_id);
+        Ok(MessageFromHost::Ok)
+    }
+
+    fn handle_claim_otxs(
+        ids: Vec<H256>,
+        lease_secs: u64,
+        plugin_name: &str,
+        otx_pool: Arc<OtxPool>,
+    ) -> Result<MessageFromHost> {
+        let expires_at = otx_pool
+            .claim_otxs(&ids, plugin_name, lease_secs)
+            .map_err(|err| anyhow!(err.to_string()))?;
+        log::debug!(
+            "{} claimed otxs {:?} until {}",
+            plugin_name,
+            ids.iter()
+                .map(|hash| hash.to_string())
+                .collect::<Vec<String>>(),
+            expires_at
+        );
+        Ok(MessageFromHost::Ok)
+    }
+
+    fn handle_schedule_job(
+        job: Job,
+        plugin_name: &str,
+        scheduler: Arc<Scheduler>,
+    ) -> Result<MessageFromHost> {
+        let job_id = job.id.clone();
+        let next_at = scheduler
+            .schedule(plugin_name, job)
+            .map_err(|err| anyhow!(err))?;
+        log::debug!(
+            "{} scheduled job {}, due at {}",
+            plugin_name,
+            job_id,
+            next_at
+        );
+        Ok(MessageFromHost::Ok)
+    }
+
+    fn handle_query_otxs(
+        filter: OtxFilter,
+        order: Order,
+        limit: u32,
+        after: Option<JsonBytes>,
+        otx_pool: Arc<OtxPool>,
+    ) -> Result<MessageFromHost> {
+        let otxs = otx_pool
+            .get_otxs(&filter, order, limit as usize, after)
+            .map_err(|err| anyhow!(err.to_string()))?;
+        Ok(MessageFromHost::Otxs(otxs))
     }
 
     fn handle_sent_ckb_tx(
         final_otx_hash: H256,
-        notify_ctrl: NotifyController,
+        plugin_name: &str,
         otx_pool: Arc<OtxPool>,
-    ) {
+    ) -> Result<MessageFromHost> {
         let otx_hashes: Vec<H256> = otx_pool
             .get_otxs_by_merged_otx_id(&final_otx_hash)
             .iter_mut()
@@ -150,23 +268,26 @@ impl HostServiceProvider {
                 .collect::<Vec<String>>()
         );
 
-        for otx_hash in otx_hashes.iter() {
-            otx_pool.update_otx_status(otx_hash, OpenTxStatus::Committed(final_otx_hash.clone()));
-        }
-        otx_pool.update_otx_status(
-            &final_otx_hash,
-            OpenTxStatus::Committed(final_otx_hash.clone()),
-        );
-        notify_ctrl.notify_commit_open_tx(otx_hashes.clone());
-        otx_pool.insert_sent_tx(final_otx_hash, otx_hashes);
+        let mut updated_otx_hashes = otx_hashes.clone();
+        updated_otx_hashes.push(final_otx_hash.clone());
+        otx_pool
+            .update_otxs_status(
+                &updated_otx_hashes,
+                OpenTxStatus::Committed(final_otx_hash.clone()),
+                plugin_name,
+                Some("the final tx is sent to the CKB node".to_owned()),
+            )
+            .map_err(|err| anyhow!(err.to_string()))?;
+        otx_pool.commit_otxs(final_otx_hash, otx_hashes);
+        Ok(MessageFromHost::Ok)
     }
 
     fn handle_merge_otxs_and_sent(
         otx_hashes: Vec<H256>,
         tx_hash: H256,
-        notify_ctrl: NotifyController,
+        plugin_name: &str,
         otx_pool: Arc<OtxPool>,
-    ) {
+    ) -> Result<MessageFromHost> {
         log::info!(
             "handle sent ckb tx: {:?}, includes otxs: {:?}",
             tx_hash.to_string(),
@@ -176,11 +297,16 @@ impl HostServiceProvider {
                 .collect::<Vec<String>>()
         );
 
-        for otx_hash in otx_hashes.iter() {
-            otx_pool.update_otx_status(otx_hash, OpenTxStatus::Committed(tx_hash.clone()));
-        }
-        notify_ctrl.notify_commit_open_tx(otx_hashes.clone());
-        otx_pool.insert_sent_tx(tx_hash, otx_hashes);
+        otx_pool
+            .update_otxs_status(
+                &otx_hashes,
+                OpenTxStatus::Committed(tx_hash.clone()),
+                plugin_name,
+                Some("the final tx is sent to the CKB node".to_owned()),
+            )
+            .map_err(|err| anyhow!(err.to_string()))?;
+        otx_pool.commit_otxs(tx_hash, otx_hashes);
+        Ok(MessageFromHost::Ok)
     }
 }
 
@@ -1,158 +1,641 @@
-use crate::notify::{NotifyController, RuntimeHandle};
-use crate::plugin_extension::host_service::HostServiceProvider;
+use crate::notify::{
+    CommitOtx, Interval, NewBlock, NewOtx, NotifyController, OtxStatusChanged, RejectOtx,
+    RuntimeHandle, Timer,
+};
+use crate::plugin_extension::dispatcher::{PluginDispatcher, PluginEvent};
 use crate::plugin_extension::plugin_proxy::PluginProxy;
+use crate::scheduler::Scheduler;
 
 use anyhow::Result;
 use ckb_async_runtime::Handle;
-use otx_pool_plugin_protocol::{HostServiceHandler, Plugin, PluginInfo, PluginMeta};
-use tokio::task::{block_in_place, JoinHandle};
+use otx_pool_config::{PluginDispatchConfig, PluginSupervisorConfig, PluginsConfig};
+use otx_pool_plugin_protocol::{
+    HostServiceHandler, Plugin, PluginCapabilities, PluginDetail, PluginHealth, PluginInfo,
+    PluginLiveness, PluginMeta,
+};
+use serde_json::Value;
+use tokio::task::JoinHandle;
 
 use std::collections::HashMap;
 use std::fs;
 use std::io;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
 
 pub const PLUGINS_DIRNAME: &str = "plugins";
 pub const INACTIVE_DIRNAME: &str = "plugins_inactive";
 
-type PluginList = Vec<(String, Box<Arc<dyn Plugin + Send>>)>;
+// how long a deactivated plugin has to handle its queued events
+const DEACTIVATE_TIMEOUT: Duration = Duration::from_secs(10);
+
+type PluginMap = HashMap<String, Box<Arc<dyn Plugin + Send>>>;
+type DispatcherMap = HashMap<String, Arc<PluginDispatcher>>;
 
 pub struct PluginManager {
-    _plugin_dir: PathBuf,
+    plugin_dir: PathBuf,
     inactive_plugin_dir: PathBuf,
 
     // information about all plugins, including inactive ones
     plugin_configs: HashMap<String, (PluginMeta, PluginInfo)>,
 
-    // proxies for activated plugin processes
-    plugins: HashMap<String, Box<Arc<dyn Plugin + Send>>>,
+    // the capabilities declared by all plugins in the handshake
+    plugin_capabilities: HashMap<String, PluginCapabilities>,
+
+    // activated plugins, including the proxies of external plugin processes,
+    // shared with the event thread so that plugins can be activated at runtime
+    plugins: Arc<RwLock<PluginMap>>,
+
+    // the event queues of the activated plugins, shared with the event thread
+    dispatchers: Arc<RwLock<DispatcherMap>>,
+
+    // proxies for activated external plugin processes
+    proxies: HashMap<String, Arc<PluginProxy>>,
 
+    runtime_handle: RuntimeHandle,
     service_provider: HostServiceHandler,
+    // the jobs of a plugin are cancelled when it is deactivated
+    scheduler: Arc<Scheduler>,
+    supervisor_config: PluginSupervisorConfig,
+    dispatch_config: PluginDispatchConfig,
+    plugins_config: PluginsConfig,
+    // whether the pool has started, the plugins activated later are started right away
+    started: bool,
     _event_thread: Option<JoinHandle<()>>,
 }
 
 impl PluginManager {
-    pub fn new(host_dir: &Path, service_provider: HostServiceHandler) -> Self {
+    pub fn new(
+        host_dir: &Path,
+        runtime_handle: RuntimeHandle,
+        service_provider: HostServiceHandler,
+        scheduler: Arc<Scheduler>,
+        supervisor_config: PluginSupervisorConfig,
+        dispatch_config: PluginDispatchConfig,
+        plugins_config: PluginsConfig,
+    ) -> Self {
         let plugin_configs: HashMap<String, (PluginMeta, PluginInfo)> = HashMap::new();
-        let plugins: HashMap<String, Box<Arc<dyn Plugin + Send>>> = HashMap::new();
+        let plugins = Arc::new(RwLock::new(PluginMap::new()));
 
         PluginManager {
-            _plugin_dir: host_dir.join(PLUGINS_DIRNAME),
+            plugin_dir: host_dir.join(PLUGINS_DIRNAME),
             inactive_plugin_dir: host_dir.join(INACTIVE_DIRNAME),
             plugin_configs,
+            plugin_capabilities: HashMap::new(),
             plugins,
+            dispatchers: Arc::new(RwLock::new(DispatcherMap::new())),
+            proxies: HashMap::new(),
+            runtime_handle,
             service_provider,
+            scheduler,
+            supervisor_config,
+            dispatch_config,
+            plugins_config,
+            started: false,
             _event_thread: None,
         }
     }
 
-    pub fn register_built_in_plugins(&mut self, plugin: Box<Arc<dyn Plugin + Send>>) {
-        let plugin_info = plugin.get_info();
-        let plugin_state = plugin.get_meta();
+    /// Configure and activate the built-in plugin, which is refused if it refuses its config.
+    pub fn register_built_in_plugins(
+        &mut self,
+        plugin: Box<Arc<dyn Plugin + Send>>,
+    ) -> Result<(), String> {
+        let plugin_name = plugin.get_name();
+        if let Some(config) = self.plugins_config.get_plugin_config(&plugin_name) {
+            plugin
+                .on_configure(config)
+                .map_err(|err| format!("configure plugin {} error: {}", plugin_name, err))?;
+        }
+        self.add_plugin(plugin_name.clone(), plugin.clone())?;
         self.plugin_configs
-            .insert(plugin.get_name(), (plugin_state, plugin_info));
-        let plugin = plugin;
-        self.plugins.insert(plugin.get_name(), plugin.clone());
+            .insert(plugin_name.clone(), (plugin.get_meta(), plugin.get_info()));
+        self.plugin_capabilities
+            .insert(plugin_name, plugin.get_capabilities());
+        Ok(())
     }
 
-    pub fn load_third_party_plugins(
-        &mut self,
-        runtime_handle: &RuntimeHandle,
-        service_provider: &HostServiceProvider,
-    ) -> Result<(), String> {
+    pub fn load_third_party_plugins(&mut self) -> Result<(), String> {
         // load plugins
         log::info!("load third-party plugins");
-        for (plugin_name, (plugin_state, plugin_info)) in
+        for (plugin_name, (plugin_state, plugin_info, plugin_capabilities)) in
             self.load_plugin_configs().map_err(|err| err.to_string())?
         {
             self.plugin_configs.insert(
                 plugin_name.clone(),
                 (plugin_state.to_owned(), plugin_info.to_owned()),
             );
+            self.plugin_capabilities
+                .insert(plugin_name.clone(), plugin_capabilities);
             if plugin_state.is_active {
-                let plugin_proxy = PluginProxy::start_process(
-                    runtime_handle.clone(),
-                    plugin_state,
-                    plugin_info,
-                    service_provider.handler(),
-                )?;
-                self.plugins
-                    .insert(plugin_name.to_owned(), Box::new(Arc::new(plugin_proxy)));
+                self.start_plugin(plugin_state, plugin_info)?;
             }
         }
 
         Ok(())
     }
 
-    pub fn subscribe_events(&mut self, notify_ctrl: &NotifyController, runtime_handle: &Handle) {
-        let plugins: PluginList = self
-            .plugins
-            .iter()
-            .map(|(name, p)| (name.to_owned(), p.clone()))
+    /// List all plugins, including the inactive ones.
+    pub fn list_plugins(&self) -> Vec<PluginDetail> {
+        let mut plugins: Vec<PluginDetail> = self
+            .plugin_configs
+            .values()
+            .map(|(meta, info)| PluginDetail {
+                info: info.to_owned(),
+                meta: meta.to_owned(),
+                capabilities: self
+                    .plugin_capabilities
+                    .get(&info.name)
+                    .cloned()
+                    .unwrap_or_default(),
+            })
             .collect();
+        plugins.sort_by(|a, b| a.info.name.cmp(&b.info.name));
+        plugins
+    }
+
+    /// Copy a plugin binary into the plugin directory, and start it if `activate` is set.
+    /// The handshake and the plugin process run without holding the manager lock,
+    /// which is only taken to check the plugin and to insert it.
+    pub fn install_plugin(
+        manager: &RwLock<PluginManager>,
+        binary_path: &Path,
+        activate: bool,
+    ) -> Result<PluginInfo, String> {
+        let (plugin_info, plugin_capabilities) = PluginProxy::handshake(binary_path.to_owned())?;
+        let file_name = binary_path
+            .file_name()
+            .ok_or_else(|| format!("invalid plugin binary path {:?}", binary_path))?;
+        let (dir, starter) = {
+            let manager = manager.read().expect("read plugin manager");
+            manager.check_new_plugin(&plugin_info.name, &plugin_capabilities, activate)?;
+            let dir = if activate {
+                manager.plugin_dir.clone()
+            } else {
+                manager.inactive_plugin_dir.clone()
+            };
+            (dir, manager.starter())
+        };
+        let installed_path = dir.join(file_name);
+        if installed_path.exists() {
+            return Err(format!("plugin binary {:?} already exists", installed_path));
+        }
+        fs::create_dir_all(&dir).map_err(|err| err.to_string())?;
+        fs::copy(binary_path, &installed_path).map_err(|err| err.to_string())?;
+
+        let plugin_state = PluginMeta::new(installed_path.clone(), activate, false);
+        let plugin_proxy = if activate {
+            match starter.start(plugin_state.clone(), plugin_info.clone()) {
+                Ok(plugin_proxy) => Some(plugin_proxy),
+                Err(err) => {
+                    let _ = fs::remove_file(&installed_path);
+                    return Err(err);
+                }
+            }
+        } else {
+            None
+        };
+
+        // another plugin of the same name or rpc namespace may be installed meanwhile
+        let mut manager_guard = manager.write().expect("write plugin manager");
+        if let Err(err) =
+            manager_guard.check_new_plugin(&plugin_info.name, &plugin_capabilities, activate)
+        {
+            drop(manager_guard);
+            drop(plugin_proxy);
+            let _ = fs::remove_file(&installed_path);
+            return Err(err);
+        }
+        log::info!(
+            "installed plugin {} to {:?}",
+            plugin_info.name,
+            installed_path
+        );
+        manager_guard.plugin_configs.insert(
+            plugin_info.name.clone(),
+            (plugin_state, plugin_info.clone()),
+        );
+        manager_guard
+            .plugin_capabilities
+            .insert(plugin_info.name.clone(), plugin_capabilities);
+        if let Some(plugin_proxy) = plugin_proxy {
+            manager_guard.add_proxy(plugin_proxy)?;
+        }
+        Ok(plugin_info)
+    }
+
+    /// Move the plugin binary into the plugin directory and start its process,
+    /// the process is started without holding the manager lock.
+    pub fn activate_plugin(
+        manager: &RwLock<PluginManager>,
+        plugin_name: &str,
+    ) -> Result<(), String> {
+        let (plugin_state, plugin_info, inactive_plugin_dir, starter) = {
+            let manager = manager.read().expect("read plugin manager");
+            let (plugin_state, plugin_info) = manager.get_third_party_plugin(plugin_name)?;
+            if plugin_state.is_active {
+                return Err(format!("plugin {} is already active", plugin_name));
+            }
+            manager.check_rpc_namespace(plugin_name, &manager.get_capabilities(plugin_name))?;
+            let binary_path = move_binary(&plugin_state.binary_path, &manager.plugin_dir)?;
+            (
+                PluginMeta::new(binary_path, true, false),
+                plugin_info,
+                manager.inactive_plugin_dir.clone(),
+                manager.starter(),
+            )
+        };
+        log::info!("activate plugin {}", plugin_name);
+        let plugin_proxy = match starter.start(plugin_state.clone(), plugin_info.clone()) {
+            Ok(plugin_proxy) => plugin_proxy,
+            Err(err) => {
+                let _ = move_binary(&plugin_state.binary_path, &inactive_plugin_dir);
+                return Err(err);
+            }
+        };
+
+        // another plugin of the same rpc namespace may be activated meanwhile
+        let mut manager_guard = manager.write().expect("write plugin manager");
+        if let Err(err) = manager_guard
+            .check_rpc_namespace(plugin_name, &manager_guard.get_capabilities(plugin_name))
+        {
+            drop(manager_guard);
+            drop(plugin_proxy);
+            let _ = move_binary(&plugin_state.binary_path, &inactive_plugin_dir);
+            return Err(err);
+        }
+        manager_guard
+            .plugin_configs
+            .insert(plugin_name.to_owned(), (plugin_state, plugin_info));
+        manager_guard.add_proxy(plugin_proxy)
+    }
+
+    /// Stop the plugin process and move the plugin binary into the inactive plugin directory.
+    /// The plugin is taken out of the manager first, and receives `on_stop` and finishes
+    /// its queued events without the manager lock held. It is marked inactive once its
+    /// binary is moved, otherwise it is started again.
+    pub fn deactivate_plugin(
+        manager: &RwLock<PluginManager>,
+        plugin_name: &str,
+    ) -> Result<(), String> {
+        let (plugin_state, plugin_info, dispatcher, proxy, inactive_plugin_dir, starter) = {
+            let mut manager = manager.write().expect("write plugin manager");
+            let (plugin_state, plugin_info) = manager.get_third_party_plugin(plugin_name)?;
+            if !plugin_state.is_active {
+                return Err(format!("plugin {} is already inactive", plugin_name));
+            }
+            // the proxy is missing while the plugin is being deactivated
+            let proxy = manager
+                .proxies
+                .remove(plugin_name)
+                .ok_or_else(|| format!("plugin {} is not running", plugin_name))?;
+            log::info!("deactivate plugin {}", plugin_name);
+            manager
+                .plugins
+                .write()
+                .expect("write plugins")
+                .remove(plugin_name);
+            let dispatcher = manager
+                .dispatchers
+                .write()
+                .expect("write dispatchers")
+                .remove(plugin_name);
+            manager.scheduler.cancel_all(plugin_name);
+            (
+                plugin_state,
+                plugin_info,
+                dispatcher,
+                proxy,
+                manager.inactive_plugin_dir.clone(),
+                manager.starter(),
+            )
+        };
+        if let Some(dispatcher) = dispatcher {
+            dispatcher.dispatch(PluginEvent::Stop);
+            if !dispatcher.wait_idle(Instant::now() + DEACTIVATE_TIMEOUT) {
+                log::warn!(
+                    "plugin {} did not finish its events in {} seconds",
+                    plugin_name,
+                    DEACTIVATE_TIMEOUT.as_secs()
+                );
+            }
+        }
+        proxy.stop();
+
+        match move_binary(&plugin_state.binary_path, &inactive_plugin_dir) {
+            Ok(binary_path) => {
+                manager
+                    .write()
+                    .expect("write plugin manager")
+                    .plugin_configs
+                    .insert(
+                        plugin_name.to_owned(),
+                        (PluginMeta::new(binary_path, false, false), plugin_info),
+                    );
+                Ok(())
+            }
+            Err(err) => {
+                log::error!(
+                    "move the binary of plugin {} error: {}, start it again",
+                    plugin_name,
+                    err
+                );
+                let plugin_proxy = starter.start(plugin_state, plugin_info)?;
+                manager
+                    .write()
+                    .expect("write plugin manager")
+                    .add_proxy(plugin_proxy)?;
+                Err(err)
+            }
+        }
+    }
+
+    fn get_third_party_plugin(
+        &self,
+        plugin_name: &str,
+    ) -> Result<(PluginMeta, PluginInfo), String> {
+        match self.plugin_configs.get(plugin_name) {
+            Some((plugin_state, _)) if plugin_state.is_built_in => Err(format!(
+                "built-in plugin {} can not be managed",
+                plugin_name
+            )),
+            Some(config) => Ok(config.to_owned()),
+            None => Err(format!("plugin {} not found", plugin_name)),
+        }
+    }
+
+    fn start_plugin(
+        &mut self,
+        plugin_state: PluginMeta,
+        plugin_info: PluginInfo,
+    ) -> Result<(), String> {
+        self.check_rpc_namespace(&plugin_info.name, &self.get_capabilities(&plugin_info.name))?;
+        let plugin_proxy = self.starter().start(plugin_state, plugin_info)?;
+        self.add_proxy(plugin_proxy)
+    }
+
+    /// The plugin name is not taken, nor the rpc namespace if the plugin is started.
+    fn check_new_plugin(
+        &self,
+        plugin_name: &str,
+        plugin_capabilities: &PluginCapabilities,
+        activate: bool,
+    ) -> Result<(), String> {
+        if self.plugin_configs.contains_key(plugin_name) {
+            return Err(format!("plugin {} already exists", plugin_name));
+        }
+        if activate {
+            self.check_rpc_namespace(plugin_name, plugin_capabilities)?;
+        }
+        Ok(())
+    }
 
-        let mut interval_event_receiver =
-            runtime_handle.block_on(notify_ctrl.subscribe_interval("plugin manager"));
-        let mut new_otx_event_receiver =
-            runtime_handle.block_on(notify_ctrl.subscribe_new_open_tx("plugin manager"));
-        let mut commit_otx_event_receiver =
-            runtime_handle.block_on(notify_ctrl.subscribe_commit_open_tx("plugin manager"));
+    /// The rpc namespace of the plugin is not taken by an activated plugin.
+    fn check_rpc_namespace(
+        &self,
+        plugin_name: &str,
+        plugin_capabilities: &PluginCapabilities,
+    ) -> Result<(), String> {
+        let namespace = match plugin_capabilities.rpc_namespace.as_ref() {
+            Some(namespace) => namespace,
+            None => return Ok(()),
+        };
+        match self
+            .proxies
+            .values()
+            .find(|proxy| proxy.get_capabilities().rpc_namespace.as_ref() == Some(namespace))
+        {
+            Some(proxy) => Err(format!(
+                "rpc namespace {} of plugin {} is taken by plugin {}",
+                namespace,
+                plugin_name,
+                proxy.get_name()
+            )),
+            None => Ok(()),
+        }
+    }
+
+    fn get_capabilities(&self, plugin_name: &str) -> PluginCapabilities {
+        self.plugin_capabilities
+            .get(plugin_name)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn starter(&self) -> PluginStarter {
+        PluginStarter {
+            runtime_handle: self.runtime_handle.clone(),
+            service_provider: self.service_provider.clone(),
+            supervisor_config: self.supervisor_config.clone(),
+            plugins_config: self.plugins_config.clone(),
+        }
+    }
+
+    fn add_proxy(&mut self, plugin_proxy: Arc<PluginProxy>) -> Result<(), String> {
+        let plugin_name = plugin_proxy.get_name();
+        self.proxies
+            .insert(plugin_name.clone(), plugin_proxy.clone());
+        self.add_plugin(plugin_name, Box::new(plugin_proxy))
+    }
+
+    /// Activate the plugin and start the worker delivering events to it.
+    fn add_plugin(
+        &mut self,
+        plugin_name: String,
+        plugin: Box<Arc<dyn Plugin + Send>>,
+    ) -> Result<(), String> {
+        let dispatcher = PluginDispatcher::start(plugin.clone(), &self.dispatch_config)
+            .map_err(|err| err.to_string())?;
+        if self.started {
+            dispatcher.dispatch(PluginEvent::Start);
+        }
+        self.dispatchers
+            .write()
+            .expect("write dispatchers")
+            .insert(plugin_name.clone(), Arc::new(dispatcher));
+        self.plugins
+            .write()
+            .expect("write plugins")
+            .insert(plugin_name, plugin);
+        Ok(())
+    }
+
+    pub fn subscribe_events(&mut self, notify_ctrl: &NotifyController, runtime_handle: &Handle) {
+        let dispatchers = self.dispatchers.clone();
+
+        let mut interval_event_receiver = notify_ctrl.subscribe::<Interval>("plugin manager");
+        let mut timer_event_receiver = notify_ctrl.subscribe::<Timer>("plugin manager");
+        let mut new_otx_event_receiver = notify_ctrl.subscribe::<NewOtx>("plugin manager");
+        let mut commit_otx_event_receiver = notify_ctrl.subscribe::<CommitOtx>("plugin manager");
+        let mut reject_otx_event_receiver = notify_ctrl.subscribe::<RejectOtx>("plugin manager");
+        let mut otx_status_changed_event_receiver =
+            notify_ctrl.subscribe::<OtxStatusChanged>("plugin manager");
+        let mut new_block_event_receiver = notify_ctrl.subscribe::<NewBlock>("plugin manager");
         let event_listening_thread = runtime_handle.spawn(async move {
             loop {
-                tokio::select! {
-                    Some(elapsed) = interval_event_receiver.recv() => {
-                        plugins.iter().for_each(| (_, plugin) | {
-                            block_in_place(|| plugin.on_new_intervel(elapsed));
-                        })
-                    }
-                    Some(open_tx) = new_otx_event_receiver.recv() => {
-                        plugins.iter().for_each(|(_, plugin) | {
-                            block_in_place(|| plugin.on_new_otx(open_tx.clone()));
-                        })
-                    }
-                    Some(otx_hash) = commit_otx_event_receiver.recv() => {
-                        plugins.iter().for_each(|(_, plugin) | {
-                            block_in_place(|| plugin.on_commit_otx(otx_hash.clone()));
-                        })
-                    }
-                }
+                let event = tokio::select! {
+                    Some(elapsed) = interval_event_receiver.recv() => PluginEvent::NewInterval(elapsed),
+                    Some(timer) = timer_event_receiver.recv() => PluginEvent::Timer(timer),
+                    Some(otx) = new_otx_event_receiver.recv() => PluginEvent::NewOtx(otx),
+                    Some(otxs) = commit_otx_event_receiver.recv() => PluginEvent::CommitOtx(otxs),
+                    Some(otxs) = reject_otx_event_receiver.recv() => PluginEvent::RejectOtx(otxs),
+                    Some(otx) = otx_status_changed_event_receiver.recv() => PluginEvent::OtxStatusChanged(otx),
+                    Some(block) = new_block_event_receiver.recv() => PluginEvent::NewBlock(block),
+                    else => break,
+                };
+                subscribers(&dispatchers, &event)
+                    .iter()
+                    .for_each(|dispatcher| dispatcher.dispatch(event.clone()));
             }
         });
         self._event_thread = Some(event_listening_thread);
     }
 
+    /// Push the changed `[plugins.<name>]` tables to the activated plugins,
+    /// the inactive plugins receive the new table when they are activated.
+    /// The plugins are reconfigured after the manager lock is released.
+    pub fn reconfigure_plugins(manager: &RwLock<PluginManager>, plugins_config: PluginsConfig) {
+        let changed: Vec<(String, Box<Arc<dyn Plugin + Send>>, Value)> = {
+            let mut manager = manager.write().expect("write plugin manager");
+            if plugins_config == manager.plugins_config {
+                return;
+            }
+            let changed = manager
+                .plugins
+                .read()
+                .expect("read plugins")
+                .iter()
+                .filter_map(|(plugin_name, plugin)| {
+                    let config = plugins_config.get_plugin_config(plugin_name);
+                    if config == manager.plugins_config.get_plugin_config(plugin_name) {
+                        return None;
+                    }
+                    Some((
+                        plugin_name.to_owned(),
+                        plugin.clone(),
+                        config.unwrap_or_default(),
+                    ))
+                })
+                .collect();
+            manager.plugins_config = plugins_config;
+            changed
+        };
+        for (plugin_name, plugin, config) in changed {
+            log::info!("reconfigure plugin {}", plugin_name);
+            if let Err(err) = plugin.on_reconfigure(config) {
+                log::error!("reconfigure plugin {} error: {}", plugin_name, err);
+            }
+        }
+    }
+
     pub fn plugin_configs(&self) -> &HashMap<String, (PluginMeta, PluginInfo)> {
         &self.plugin_configs
     }
 
+    /// The liveness and event delivery of the activated plugins,
+    /// built-in plugins are alive unless they are disconnected.
+    pub fn get_plugins_health(&self) -> Vec<PluginHealth> {
+        let dispatchers = self.dispatchers.read().expect("read dispatchers");
+        let mut plugins_health: Vec<PluginHealth> = self
+            .plugins
+            .read()
+            .expect("read plugins")
+            .keys()
+            .map(|name| {
+                let mut health = match self.proxies.get(name) {
+                    Some(proxy) => proxy.get_health(),
+                    None => PluginHealth::new(name, PluginLiveness::Alive),
+                };
+                if let Some(dispatcher) = dispatchers.get(name) {
+                    if dispatcher.is_disconnected() {
+                        health.liveness = PluginLiveness::Disconnected;
+                    }
+                    health.events = dispatcher.get_stats();
+                }
+                health
+            })
+            .collect();
+        plugins_health.sort_by(|a, b| a.name.cmp(&b.name));
+        plugins_health
+    }
+
+    /// The external plugin serving the rpc method in its namespace.
+    pub fn get_rpc_plugin(&self, method: &str) -> Option<Arc<PluginProxy>> {
+        self.proxies
+            .values()
+            .find(|proxy| proxy.get_capabilities().serves_rpc_method(method))
+            .cloned()
+    }
+
+    /// Call `on_start` of the activated plugins, before any pool event is delivered.
+    pub fn start_plugins(&mut self) {
+        self.started = true;
+        self.dispatchers
+            .read()
+            .expect("read dispatchers")
+            .values()
+            .for_each(|dispatcher| dispatcher.dispatch(PluginEvent::Start));
+    }
+
+    /// Call `on_stop` of the plugins and wait up to the timeout for them to handle
+    /// the queued events, e.g. merges in flight, then terminate the external plugin processes.
+    /// The plugins are taken out of the manager first, so its lock is not held while waiting.
+    pub fn stop(manager: &RwLock<PluginManager>, timeout: Duration) {
+        let deadline = Instant::now() + timeout;
+        let (dispatchers, proxies): (Vec<Arc<PluginDispatcher>>, Vec<Arc<PluginProxy>>) = {
+            let mut manager = manager.write().expect("write plugin manager");
+            let dispatchers = manager
+                .dispatchers
+                .write()
+                .expect("write dispatchers")
+                .drain()
+                .map(|(_, dispatcher)| dispatcher)
+                .collect();
+            let proxies = manager.proxies.drain().map(|(_, proxy)| proxy).collect();
+            (dispatchers, proxies)
+        };
+        dispatchers
+            .iter()
+            .for_each(|dispatcher| dispatcher.dispatch(PluginEvent::Stop));
+        for dispatcher in dispatchers.iter() {
+            if !dispatcher.wait_idle(deadline) {
+                log::warn!(
+                    "plugin {} did not finish its events in {} seconds",
+                    dispatcher.get_plugin_name(),
+                    timeout.as_secs()
+                );
+            }
+        }
+        proxies.iter().for_each(|proxy| proxy.stop());
+    }
+
     pub fn service_handler(&self) -> HostServiceHandler {
         self.service_provider.clone()
     }
 
-    fn load_plugin_configs(&self) -> Result<HashMap<String, (PluginMeta, PluginInfo)>, io::Error> {
-        if !self._plugin_dir.exists() {
-            fs::create_dir_all(&self._plugin_dir)?;
+    #[allow(clippy::type_complexity)]
+    fn load_plugin_configs(
+        &self,
+    ) -> Result<HashMap<String, (PluginMeta, PluginInfo, PluginCapabilities)>, io::Error> {
+        if !self.plugin_dir.exists() {
+            fs::create_dir_all(&self.plugin_dir)?;
         }
         if !self.inactive_plugin_dir.exists() {
             fs::create_dir_all(&self.inactive_plugin_dir)?;
         }
 
         let mut plugin_configs = HashMap::new();
-        for (dir, is_active) in &[
-            (&self._plugin_dir, true),
-            (&self.inactive_plugin_dir, false),
-        ] {
+        for (dir, is_active) in &[(&self.plugin_dir, true), (&self.inactive_plugin_dir, false)] {
             for entry in fs::read_dir(dir)? {
                 let path = entry?.path();
                 if path.is_file() {
                     let plugin_state = PluginMeta::new(path.clone(), *is_active, false);
-                    match PluginProxy::load_plugin_info(path.clone()) {
-                        Ok(plugin_info) => {
+                    match PluginProxy::handshake(path.clone()) {
+                        Ok((plugin_info, plugin_capabilities)) => {
                             log::info!("Loaded plugin: {}", plugin_info.name);
                             plugin_configs.insert(
                                 plugin_info.clone().name,
-                                (plugin_state, plugin_info.clone()),
+                                (plugin_state, plugin_info.clone(), plugin_capabilities),
                             );
                         }
                         Err(err) => {
@@ -165,3 +648,56 @@ impl PluginManager {
         Ok(plugin_configs)
     }
 }
+
+/// What is needed to start the plugin processes, taken out of the manager
+/// so that the processes are spawned without holding the manager lock.
+struct PluginStarter {
+    runtime_handle: RuntimeHandle,
+    service_provider: HostServiceHandler,
+    supervisor_config: PluginSupervisorConfig,
+    plugins_config: PluginsConfig,
+}
+
+impl PluginStarter {
+    fn start(
+        &self,
+        plugin_state: PluginMeta,
+        plugin_info: PluginInfo,
+    ) -> Result<Arc<PluginProxy>, String> {
+        let config = self.plugins_config.get_plugin_config(&plugin_info.name);
+        let plugin_proxy = PluginProxy::start_process(
+            self.runtime_handle.clone(),
+            plugin_state,
+            plugin_info,
+            self.service_provider.clone(),
+            self.supervisor_config.clone(),
+            config,
+        )?;
+        Ok(Arc::new(plugin_proxy))
+    }
+}
+
+/// The event queues of the plugins subscribing to the event,
+/// only the events asked for in the handshake are sent, and a timer only to its plugin.
+fn subscribers(
+    dispatchers: &RwLock<DispatcherMap>,
+    event: &PluginEvent,
+) -> Vec<Arc<PluginDispatcher>> {
+    dispatchers
+        .read()
+        .expect("read dispatchers")
+        .values()
+        .filter(|dispatcher| dispatcher.is_subscribed(event))
+        .cloned()
+        .collect()
+}
+
+fn move_binary(binary_path: &Path, dir: &Path) -> Result<PathBuf, String> {
+    let file_name = binary_path
+        .file_name()
+        .ok_or_else(|| format!("invalid plugin binary path {:?}", binary_path))?;
+    let new_path = dir.join(file_name);
+    fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+    fs::rename(binary_path, &new_path).map_err(|err| err.to_string())?;
+    Ok(new_path)
+}
@@ -1,6 +1,9 @@
+pub mod config_watcher;
+pub mod dispatcher;
 pub mod host_service;
 pub mod manager;
 pub mod plugin_proxy;
+pub mod supervisor;
 
 use otx_format::jsonrpc_types::OpenTransaction;
 use otx_pool_plugin_protocol::{PluginInfo, PluginMeta};
@@ -1,114 +1,67 @@
+use super::supervisor::PluginSupervisor;
 use crate::notify::RuntimeHandle;
 
+use otx_format::jsonrpc_types::OpenTransaction;
+use otx_format::types::{BlockInfo, OpenTxStatus};
+use otx_pool_config::PluginSupervisorConfig;
 use otx_pool_plugin_protocol::{
-    HostServiceHandler, MessageFromHost, MessageFromPlugin, MessageType, Plugin, PluginInfo,
-    PluginMeta,
+    HostCapabilities, HostServiceHandler, MessageFromHost, MessageFromPlugin, MessageType, Plugin,
+    PluginCapabilities, PluginHealth, PluginInfo, PluginLiveness, PluginMeta,
 };
 
 use ckb_types::core::service::Request;
+use ckb_types::H256;
 use crossbeam_channel::{bounded, select, unbounded, Sender};
+use serde_json::Value;
 use tokio::task::JoinHandle;
 
 use std::io::{BufRead, BufReader, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+const CONFIGURE_TIMEOUT: Duration = Duration::from_secs(10);
+const RPC_TIMEOUT: Duration = Duration::from_secs(30);
+
+// the notifications carry the id 0
+static NEXT_REQUEST_ID: AtomicU64 = AtomicU64::new(1);
 
 pub type RequestHandler = Sender<Request<(u64, MessageFromHost), (u64, MessageFromPlugin)>>;
 pub type MsgHandler = Sender<(u64, MessageFromHost)>;
 
 pub struct PluginProcess {
-    _plugin_process: Child,
+    pub(crate) child: Child,
     _stdin_thread: JoinHandle<()>,
     _stdout_thread: JoinHandle<()>,
-}
-
-pub struct PluginProxy {
-    state: PluginMeta,
-    info: PluginInfo,
-    _process: PluginProcess,
 
     /// Send request to stdin thread, and expect a response from stdout thread.
     request_handler: RequestHandler,
 
     /// Send notifaction/response to stdin thread.
     msg_handler: MsgHandler,
-}
 
-impl Plugin for PluginProxy {
-    fn get_name(&self) -> String {
-        self.info.name.clone()
-    }
-
-    fn get_info(&self) -> PluginInfo {
-        self.info.clone()
-    }
+    /// Declared by the plugin in the handshake.
+    capabilities: PluginCapabilities,
 
-    fn get_meta(&self) -> PluginMeta {
-        self.state.clone()
-    }
+    /// Waiting for the response to a request other than a ping.
+    busy: Arc<AtomicBool>,
 }
 
-impl PluginProxy {
-    pub fn msg_handler(&self) -> MsgHandler {
-        self.msg_handler.clone()
-    }
-
-    pub fn request_handler(&self) -> RequestHandler {
-        self.request_handler.clone()
-    }
-
-    /// This function will create a temporary plugin process to fetch plugin information.
-    pub fn load_plugin_info(binary_path: PathBuf) -> Result<PluginInfo, String> {
-        let mut child = Command::new(&binary_path)
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .spawn()
-            .map_err(|err| err.to_string())?;
-        let mut stdin = child
-            .stdin
-            .take()
-            .ok_or_else(|| String::from("Get stdin failed"))?;
-        let stdout = child
-            .stdout
-            .take()
-            .ok_or_else(|| String::from("Get stdout failed"))?;
-
-        // request from host to plugin
-        let request = (0u64, MessageFromHost::GetPluginInfo);
-        let request_string = serde_json::to_string(&request).expect("Serialize request error");
-        log::debug!("Send request to plugin: {}", request_string);
-        stdin
-            .write_all(format!("{}\n", request_string).as_bytes())
-            .map_err(|err| err.to_string())?;
-        stdin.flush().map_err(|err| err.to_string())?;
-
-        // get response from plugin
-        let mut buf_reader = BufReader::new(stdout);
-        let mut response_string = String::new();
-        buf_reader
-            .read_line(&mut response_string)
-            .map_err(|err| err.to_string())?;
-        log::debug!("Receive response from plugin: {}", response_string.trim());
-        let (id, response): (u64, MessageFromPlugin) =
-            serde_json::from_str(&response_string).map_err(|err| err.to_string())?;
-
-        if let (0u64, MessageFromPlugin::PluginInfo(plugin_info)) = (id, response) {
-            Ok(plugin_info)
-        } else {
-            Err(format!(
-                "Invalid response for get_info call to plugin {:?}, response: {}",
-                binary_path, response_string
-            ))
-        }
-    }
-
-    pub fn start_process(
-        runtime: RuntimeHandle,
-        plugin_state: PluginMeta,
-        plugin_info: PluginInfo,
+impl PluginProcess {
+    /// Start the plugin process, complete the handshake and send the plugin config if any,
+    /// the process is killed if the plugin is incompatible or refuses the config.
+    pub fn spawn(
+        runtime: &RuntimeHandle,
+        binary_path: &Path,
+        plugin_name: &str,
         service_handler: HostServiceHandler,
-    ) -> Result<PluginProxy, String> {
-        let mut child = Command::new(plugin_state.binary_path.clone())
+        config: Option<Value>,
+    ) -> Result<PluginProcess, String> {
+        let mut child = Command::new(binary_path)
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .spawn()
@@ -132,7 +85,9 @@ impl PluginProxy {
         // the channel sends notifications or responses from the host to plugin
         let (host_msg_sender, host_msg_receiver) = unbounded();
 
-        let plugin_name = plugin_info.name.clone();
+        let busy = Arc::new(AtomicBool::new(false));
+        let stdin_busy = busy.clone();
+        let stdin_plugin_name = plugin_name.to_owned();
         // this thread processes stdin information from host to plugin
         let stdin_thread = runtime.spawn(async move  {
             let handle_host_msg =
@@ -153,18 +108,28 @@ impl PluginProxy {
                     recv(host_request_receiver) -> msg => {
                         match msg {
                             Ok(Request { responder, arguments }) => {
+                                let request_id = arguments.0;
                                 let request_string = serde_json::to_string(&arguments).expect("Serialize request error");
                                 log::debug!("Send request to plugin: {}", request_string);
                                 stdin.write_all(format!("{}\n", request_string).as_bytes()).map_err(|err| err.to_string())?;
                                 stdin.flush().map_err(|err| err.to_string())?;
+                                if !matches!(arguments.1, MessageFromHost::Ping) {
+                                    stdin_busy.store(true, Ordering::Relaxed);
+                                }
+                                // no caller waits longer than the rpc timeout for the response
+                                let deadline = Instant::now() + RPC_TIMEOUT;
                                 loop {
                                     select!{
                                         recv(plugin_response_receiver) -> msg => {
                                             match msg {
-                                                Ok(response) => {
-                                                    responder.send(response).map_err(|err| err.to_string())?;
+                                                Ok((id, response)) if id == request_id => {
+                                                    // the caller may have given up waiting
+                                                    let _ = responder.send((id, response));
                                                     return Ok(false);
                                                 }
+                                                Ok((id, _)) => {
+                                                    log::debug!("Ignore the late response to request {}", id);
+                                                }
                                                 Err(err) => {
                                                     return Err(err.to_string());
                                                 }
@@ -179,6 +144,10 @@ impl PluginProxy {
                                                     return Err(err.to_string());
                                                 }
                                             }
+                                        },
+                                        default(deadline.saturating_duration_since(Instant::now())) => {
+                                            log::warn!("plugin {} does not answer request {} in time", stdin_plugin_name, request_id);
+                                            return Ok(false);
                                         }
                                     }
                                 }
@@ -204,20 +173,26 @@ impl PluginProxy {
                 }
             };
             loop {
-                match do_select() {
+                let result = do_select();
+                // the request sent, if any, is answered or given up
+                stdin_busy.store(false, Ordering::Relaxed);
+                match result {
                     Ok(true) => {
                         break;
                     }
                     Ok(false) => (),
                     Err(err) => {
-                        log::error!("plugin {} stdin error: {}", plugin_name, err);
+                        log::error!("plugin {} stdin error: {}", stdin_plugin_name, err);
                         break;
                     }
                 }
             }
         });
 
-        let plugin_name = plugin_info.name.clone();
+        // the requests from the plugin are refused until the handshake completes
+        let capabilities: Arc<RwLock<Option<PluginCapabilities>>> = Arc::new(RwLock::new(None));
+        let declared_capabilities = capabilities.clone();
+        let stdout_plugin_name = plugin_name.to_owned();
         let msg_sender = host_msg_sender.clone();
         let mut buf_reader = BufReader::new(stdout);
         let stdout_thread = runtime.spawn(async move {
@@ -245,11 +220,29 @@ impl PluginProxy {
                     MessageType::Request => {
                         // Handle request from plugin
                         log::debug!("Receive request from plugin: {}", content.trim());
-                        log::debug!("Sending request to ServiceProvider");
-                        let message_from_host =
-                            Request::call(&service_handler, message_from_plugin).ok_or_else(
-                                || String::from("Send request to ServiceProvider failed"),
-                            )?;
+                        let message_from_host = match check_host_service(
+                            &declared_capabilities,
+                            &message_from_plugin,
+                        ) {
+                            Ok(_) => {
+                                log::debug!("Sending request to ServiceProvider");
+                                Request::call(
+                                    &service_handler,
+                                    (stdout_plugin_name.clone(), message_from_plugin),
+                                )
+                                .ok_or_else(|| {
+                                    String::from("Send request to ServiceProvider failed")
+                                })?
+                            }
+                            Err(err) => {
+                                log::warn!(
+                                    "plugin {} request refused: {}",
+                                    stdout_plugin_name,
+                                    err
+                                );
+                                MessageFromHost::Error(err)
+                            }
+                        };
                         log::debug!(
                             "Received response from ServiceProvider: {:?}",
                             message_from_host
@@ -268,36 +261,470 @@ impl PluginProxy {
             loop {
                 match do_recv() {
                     Ok(true) => {
-                        log::info!("plugin {} quit", plugin_name);
+                        log::info!("plugin {} quit", stdout_plugin_name);
                         break;
                     }
                     Ok(false) => {}
                     Err(err) => {
-                        log::warn!("plugin {} stdout error: {}", plugin_name, err);
+                        log::warn!("plugin {} stdout error: {}", stdout_plugin_name, err);
                         break;
                     }
                 }
             }
         });
 
-        let process = PluginProcess {
-            _plugin_process: child,
+        let mut process = PluginProcess {
+            child,
             _stdin_thread: stdin_thread,
             _stdout_thread: stdout_thread,
+            request_handler: host_request_sender,
+            msg_handler: host_msg_sender,
+            capabilities: PluginCapabilities::default(),
+            busy,
         };
+        let started = process
+            .handshake()
+            .and_then(|(plugin_info, plugin_capabilities)| {
+                if plugin_info.name != plugin_name {
+                    return Err(format!(
+                        "plugin {} is renamed to {} in the handshake",
+                        plugin_name, plugin_info.name
+                    ));
+                }
+                *capabilities.write().expect("write plugin capabilities") =
+                    Some(plugin_capabilities.clone());
+                process.capabilities = plugin_capabilities;
+                match config {
+                    Some(config) => process.configure(MessageFromHost::Configure(config)),
+                    None => Ok(()),
+                }
+            });
+        match started {
+            Ok(_) => Ok(process),
+            Err(err) => {
+                let _ = process.child.kill();
+                let _ = process.child.wait();
+                Err(err)
+            }
+        }
+    }
+
+    fn handshake(&self) -> Result<(PluginInfo, PluginCapabilities), String> {
+        let host_capabilities = HostCapabilities::default();
+        let response = call(
+            &self.request_handler,
+            MessageFromHost::Handshake(host_capabilities.clone()),
+            HANDSHAKE_TIMEOUT,
+        )?;
+        check_handshake(response, &host_capabilities)
+    }
+
+    fn configure(&self, msg: MessageFromHost) -> Result<(), String> {
+        configure(&self.request_handler, msg)
+    }
+
+    pub fn capabilities(&self) -> PluginCapabilities {
+        self.capabilities.clone()
+    }
+
+    pub fn msg_handler(&self) -> MsgHandler {
+        self.msg_handler.clone()
+    }
+
+    pub fn request_handler(&self) -> RequestHandler {
+        self.request_handler.clone()
+    }
+
+    pub fn busy(&self) -> Arc<AtomicBool> {
+        self.busy.clone()
+    }
+}
+
+/// The host side of an external plugin, the plugin process is supervised and restarted
+/// when it crashes or hangs.
+pub struct PluginProxy {
+    state: PluginMeta,
+    info: PluginInfo,
+    capabilities: PluginCapabilities,
+
+    // the plugin config table, also sent to the restarted processes
+    config: Arc<RwLock<Option<Value>>>,
+
+    // the running process, `None` while the plugin is restarting or stopped
+    process: Arc<Mutex<Option<PluginProcess>>>,
+    health: Arc<RwLock<PluginHealth>>,
+    supervisor: Mutex<Option<PluginSupervisor>>,
+}
+
+impl Plugin for PluginProxy {
+    fn get_name(&self) -> String {
+        self.info.name.clone()
+    }
+
+    fn get_info(&self) -> PluginInfo {
+        self.info.clone()
+    }
+
+    fn get_meta(&self) -> PluginMeta {
+        self.state.clone()
+    }
+
+    fn get_capabilities(&self) -> PluginCapabilities {
+        self.capabilities.clone()
+    }
+
+    fn on_reconfigure(&self, config: Value) -> Result<(), String> {
+        *self.config.write().expect("write plugin config") = Some(config.clone());
+        match self.request_handler() {
+            Some(request_handler) => {
+                configure(&request_handler, MessageFromHost::Reconfigure(config))
+            }
+            // the restarted process receives the new config
+            None => Ok(()),
+        }
+    }
+
+    fn on_start(&self) {
+        self.notify(MessageFromHost::OtxPoolStart);
+    }
+
+    fn on_stop(&self) {
+        self.notify(MessageFromHost::OtxPoolStop);
+    }
+
+    fn on_new_otx(&self, seq: u64, otx: OpenTransaction) {
+        self.notify(MessageFromHost::NewOtx((seq, otx)));
+    }
+
+    fn on_new_intervel(&self, interval: u64) {
+        self.notify(MessageFromHost::NewInterval(interval));
+    }
+
+    fn on_timer(&self, job_id: String, timestamp: u64) {
+        self.notify(MessageFromHost::Timer((job_id, timestamp)));
+    }
+
+    fn on_commit_otx(&self, seq: u64, otxs: Vec<H256>) {
+        self.notify(MessageFromHost::CommitOtx((seq, otxs)));
+    }
+
+    fn on_reject_otx(&self, otxs: Vec<(H256, String)>) {
+        self.notify(MessageFromHost::RejectOtx(otxs));
+    }
+
+    fn on_otx_status_changed(&self, seq: u64, (id, status): (H256, OpenTxStatus)) {
+        self.notify(MessageFromHost::OtxStatusChanged((seq, id, status)));
+    }
+
+    fn on_new_block(&self, block: BlockInfo) {
+        self.notify(MessageFromHost::NewBlock(block));
+    }
+}
+
+impl PluginProxy {
+    /// Send a notification to the plugin process, notifications carry no request id.
+    /// The notifications are dropped while the plugin is restarting.
+    fn notify(&self, msg: MessageFromHost) {
+        let msg_handler = match self.msg_handler() {
+            Some(msg_handler) => msg_handler,
+            None => {
+                log::debug!("plugin {} is not running, drop {:?}", self.info.name, msg);
+                return;
+            }
+        };
+        if let Err(err) = msg_handler.send((0, msg)) {
+            log::warn!("notify plugin {} error: {}", self.info.name, err);
+        }
+    }
+
+    pub fn msg_handler(&self) -> Option<MsgHandler> {
+        self.process
+            .lock()
+            .expect("lock plugin process")
+            .as_ref()
+            .map(|process| process.msg_handler())
+    }
+
+    pub fn request_handler(&self) -> Option<RequestHandler> {
+        self.process
+            .lock()
+            .expect("lock plugin process")
+            .as_ref()
+            .map(|process| process.request_handler())
+    }
+
+    /// Forward a call of the rpc method in the namespace of the plugin.
+    pub fn call_rpc(&self, method: &str, params: Value) -> Result<Value, String> {
+        let request_handler = self
+            .request_handler()
+            .ok_or_else(|| format!("plugin {} is not running", self.info.name))?;
+        let request = MessageFromHost::RpcCall((method.to_owned(), params));
+        match call(&request_handler, request, RPC_TIMEOUT)? {
+            MessageFromPlugin::RpcResult(result) => Ok(result),
+            MessageFromPlugin::Error(err) => Err(err),
+            response => Err(format!("unexpected response to rpc call: {:?}", response)),
+        }
+    }
+
+    pub fn get_health(&self) -> PluginHealth {
+        self.health.read().expect("read plugin health").clone()
+    }
+
+    /// Stop supervising the plugin and terminate its process.
+    pub fn stop(&self) {
+        if let Some(supervisor) = self.supervisor.lock().expect("lock supervisor").take() {
+            supervisor.stop();
+        }
+    }
+
+    /// This function will create a temporary plugin process to complete the handshake,
+    /// which fetches the plugin information and capabilities.
+    /// The process is killed if it does not answer in time.
+    pub fn handshake(binary_path: PathBuf) -> Result<(PluginInfo, PluginCapabilities), String> {
+        let mut child = Command::new(&binary_path)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|err| err.to_string())?;
+        let result = Self::handshake_with(&mut child);
+        let _ = child.kill();
+        let _ = child.wait();
+        result.map_err(|err| format!("handshake with plugin {:?} error: {}", binary_path, err))
+    }
+
+    fn handshake_with(child: &mut Child) -> Result<(PluginInfo, PluginCapabilities), String> {
+        let mut stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| String::from("Get stdin failed"))?;
+        let stdout = child
+            .stdout
+            .take()
+            .ok_or_else(|| String::from("Get stdout failed"))?;
+
+        // request from host to plugin
+        let host_capabilities = HostCapabilities::default();
+        let request = (0u64, MessageFromHost::Handshake(host_capabilities.clone()));
+        let request_string = serde_json::to_string(&request).expect("Serialize request error");
+        log::debug!("Send request to plugin: {}", request_string);
+        stdin
+            .write_all(format!("{}\n", request_string).as_bytes())
+            .map_err(|err| err.to_string())?;
+        stdin.flush().map_err(|err| err.to_string())?;
+
+        // get response from plugin, the line is read on a thread so that a plugin
+        // that never answers is killed after the timeout, which also ends the thread
+        let (response_sender, response_receiver) = bounded(1);
+        thread::spawn(move || {
+            let mut buf_reader = BufReader::new(stdout);
+            let mut response_string = String::new();
+            let result = buf_reader
+                .read_line(&mut response_string)
+                .map(|_| response_string)
+                .map_err(|err| err.to_string());
+            let _ = response_sender.send(result);
+        });
+        let response_string = response_receiver
+            .recv_timeout(HANDSHAKE_TIMEOUT)
+            .map_err(|_| format!("no response in {} seconds", HANDSHAKE_TIMEOUT.as_secs()))??;
+        log::debug!("Receive response from plugin: {}", response_string.trim());
+        let (_, response): (u64, MessageFromPlugin) =
+            serde_json::from_str(&response_string).map_err(|err| err.to_string())?;
+        check_handshake(response, &host_capabilities)
+    }
+
+    pub fn start_process(
+        runtime: RuntimeHandle,
+        plugin_state: PluginMeta,
+        plugin_info: PluginInfo,
+        service_handler: HostServiceHandler,
+        supervisor_config: PluginSupervisorConfig,
+        config: Option<Value>,
+    ) -> Result<PluginProxy, String> {
+        let process = PluginProcess::spawn(
+            &runtime,
+            &plugin_state.binary_path,
+            &plugin_info.name,
+            service_handler.clone(),
+            config.clone(),
+        )?;
+        let capabilities = process.capabilities();
+        let mut health = PluginHealth::new(&plugin_info.name, PluginLiveness::Alive);
+        health.pid = Some(process.child.id());
+
+        let config = Arc::new(RwLock::new(config));
+        let process = Arc::new(Mutex::new(Some(process)));
+        let health = Arc::new(RwLock::new(health));
+        let supervisor = PluginSupervisor::start(
+            supervisor_config,
+            runtime,
+            plugin_state.binary_path.clone(),
+            plugin_info.name.clone(),
+            service_handler,
+            config.clone(),
+            capabilities.clone(),
+            process.clone(),
+            health.clone(),
+        )
+        .map_err(|err| err.to_string())?;
 
         Ok(PluginProxy {
             state: plugin_state,
             info: plugin_info,
-            _process: process,
-            request_handler: host_request_sender,
-            msg_handler: host_msg_sender,
+            capabilities,
+            config,
+            process,
+            health,
+            supervisor: Mutex::new(Some(supervisor)),
         })
     }
 }
 
 impl Drop for PluginProxy {
     fn drop(&mut self) {
-        // TODO: send term signal to the process
+        self.stop();
+    }
+}
+
+/// Send a request to the plugin and wait for the response.
+/// Every request has its own id, so that a late response is not taken for the next one.
+pub(super) fn call(
+    request_handler: &RequestHandler,
+    msg: MessageFromHost,
+    timeout: Duration,
+) -> Result<MessageFromPlugin, String> {
+    let (responder, response) = bounded(1);
+    let id = NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed);
+    request_handler
+        .send_timeout(
+            Request {
+                responder,
+                arguments: (id, msg),
+            },
+            timeout,
+        )
+        .map_err(|_| "the plugin is busy with another request".to_owned())?;
+    response
+        .recv_timeout(timeout)
+        .map(|(_, response)| response)
+        .map_err(|_| format!("no response in {} seconds", timeout.as_secs()))
+}
+
+/// Send the `Configure` or `Reconfigure` message, the plugin answers `Ok` if it accepts the config.
+fn configure(request_handler: &RequestHandler, msg: MessageFromHost) -> Result<(), String> {
+    match call(request_handler, msg, CONFIGURE_TIMEOUT)? {
+        MessageFromPlugin::Ok => Ok(()),
+        MessageFromPlugin::Error(err) => Err(format!("the plugin refuses the config: {}", err)),
+        response => Err(format!("unexpected response to the config: {:?}", response)),
+    }
+}
+
+fn check_handshake(
+    response: MessageFromPlugin,
+    host_capabilities: &HostCapabilities,
+) -> Result<(PluginInfo, PluginCapabilities), String> {
+    match response {
+        MessageFromPlugin::Handshake((plugin_info, plugin_capabilities)) => {
+            plugin_capabilities
+                .check_compatible(host_capabilities)
+                .map_err(|err| format!("incompatible plugin {}: {}", plugin_info.name, err))?;
+            Ok((plugin_info, plugin_capabilities))
+        }
+        response => Err(format!(
+            "the plugin does not support the handshake of protocol version {}, response: {:?}",
+            host_capabilities.protocol_version, response
+        )),
+    }
+}
+
+fn check_host_service(
+    capabilities: &RwLock<Option<PluginCapabilities>>,
+    message: &MessageFromPlugin,
+) -> Result<(), String> {
+    let service = match message.get_host_service() {
+        Some(service) => service,
+        None => return Ok(()),
+    };
+    match capabilities
+        .read()
+        .expect("read plugin capabilities")
+        .as_ref()
+    {
+        Some(capabilities) if capabilities.is_service_declared(service) => Ok(()),
+        Some(_) => Err(format!(
+            "host service {:?} is not declared in the handshake",
+            service
+        )),
+        None => Err("the handshake is not completed".to_owned()),
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    use ckb_async_runtime::new_background_runtime;
+
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+
+    // a plugin answering the rpc calls with the method name, one second late for "slow"
+    fn write_slow_plugin(path: &Path) {
+        let handshake = serde_json::to_string(&MessageFromPlugin::Handshake((
+            PluginInfo::new("slow", "", ""),
+            PluginCapabilities::default(),
+        )))
+        .unwrap();
+        let script = format!(
+            r#"#!/bin/sh
+while read -r line; do
+    id=${{line#[}}
+    id=${{id%%,*}}
+    case "$line" in
+        *Handshake*) printf '%s\n' "[$id,"'{}'"]" ;;
+        *'"slow"'*) sleep 1; printf '%s\n' "[$id,{{\"RpcResult\":\"slow\"}}]" ;;
+        *RpcCall*) printf '%s\n' "[$id,{{\"RpcResult\":\"fast\"}}]" ;;
+    esac
+done
+"#,
+            handshake
+        );
+        fs::write(path, script).unwrap();
+        fs::set_permissions(path, fs::Permissions::from_mode(0o755)).unwrap();
+    }
+
+    fn rpc_call(method: &str) -> MessageFromHost {
+        MessageFromHost::RpcCall((method.to_owned(), Value::Null))
+    }
+
+    #[test]
+    fn test_late_response() {
+        let path = std::env::temp_dir().join(format!("slow-plugin-{}", std::process::id()));
+        write_slow_plugin(&path);
+        let (service_handler, _service_receiver) = bounded(1);
+        let mut process = PluginProcess::spawn(
+            &new_background_runtime(),
+            &path,
+            "slow",
+            service_handler,
+            None,
+        )
+        .unwrap();
+        let request_handler = process.request_handler();
+
+        let timeout = Duration::from_millis(200);
+        assert!(call(&request_handler, rpc_call("slow"), timeout).is_err());
+        // the late response is dropped, and the plugin still serves the next requests
+        for _ in 0..2 {
+            let response = call(&request_handler, rpc_call("fast"), Duration::from_secs(5));
+            assert!(matches!(
+                response,
+                Ok(MessageFromPlugin::RpcResult(Value::String(result))) if result == "fast"
+            ));
+        }
+
+        let _ = process.child.kill();
+        let _ = process.child.wait();
+        let _ = fs::remove_file(path);
     }
 }
@@ -0,0 +1,325 @@
+use super::plugin_proxy::{call, PluginProcess};
+use crate::notify::RuntimeHandle;
+
+use otx_format::types::unix_timestamp;
+use otx_pool_config::PluginSupervisorConfig;
+use otx_pool_plugin_protocol::{
+    HostServiceHandler, MessageFromHost, MessageFromPlugin, PluginCapabilities, PluginHealth,
+    PluginLiveness,
+};
+
+use anyhow::Result;
+use crossbeam_channel::{bounded, select, Sender};
+use serde_json::Value;
+
+use std::path::PathBuf;
+use std::process::Child;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+const SUPERVISE_TICK: Duration = Duration::from_secs(1);
+const TERMINATE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Watches an external plugin process.
+///
+/// The process is pinged periodically, and restarted with exponential backoff if it exits
+/// or stops answering, until the restart budget is used up. A process busy with a request
+/// is alive, as the request is given up after its own timeout.
+/// A restarted process receives the start event again, and is given up if it declares
+/// other subscriptions or rpc namespace than the registered ones.
+/// On stop, the process receives SIGTERM, and SIGKILL if it does not exit in time.
+pub struct PluginSupervisor {
+    stop_handler: Sender<()>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl PluginSupervisor {
+    #[allow(clippy::too_many_arguments)]
+    pub fn start(
+        config: PluginSupervisorConfig,
+        runtime: RuntimeHandle,
+        binary_path: PathBuf,
+        plugin_name: String,
+        service_handler: HostServiceHandler,
+        plugin_config: Arc<RwLock<Option<Value>>>,
+        capabilities: PluginCapabilities,
+        process: Arc<Mutex<Option<PluginProcess>>>,
+        health: Arc<RwLock<PluginHealth>>,
+    ) -> Result<PluginSupervisor> {
+        let (stop_sender, stop_receiver) = bounded(1);
+        let mut state = SupervisorState {
+            config,
+            runtime,
+            binary_path,
+            plugin_name: plugin_name.clone(),
+            service_handler,
+            plugin_config,
+            capabilities,
+            process,
+            health,
+            started_at: Instant::now(),
+            last_ping_at: Instant::now(),
+            consecutive_restarts: 0,
+            next_restart_at: None,
+        };
+
+        let handle = thread::Builder::new()
+            .name(format!("plugin-supervisor-{}", plugin_name))
+            .spawn(move || {
+                loop {
+                    select! {
+                        recv(stop_receiver) -> _ => {
+                            log::info!("PluginSupervisor of {} received stop signal", state.plugin_name);
+                            break;
+                        }
+                        default(SUPERVISE_TICK) => state.supervise(),
+                    }
+                }
+                state.terminate();
+            })?;
+
+        Ok(PluginSupervisor {
+            stop_handler: stop_sender,
+            thread: Some(handle),
+        })
+    }
+
+    /// Stop supervising and wait until the plugin process is terminated.
+    pub fn stop(mut self) {
+        let _ = self.stop_handler.try_send(());
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for PluginSupervisor {
+    fn drop(&mut self) {
+        let _ = self.stop_handler.try_send(());
+    }
+}
+
+struct SupervisorState {
+    config: PluginSupervisorConfig,
+    runtime: RuntimeHandle,
+    binary_path: PathBuf,
+    plugin_name: String,
+    service_handler: HostServiceHandler,
+    // the plugin config table sent to the restarted processes
+    plugin_config: Arc<RwLock<Option<Value>>>,
+    // declared in the first handshake, the events and rpc calls are routed by them
+    capabilities: PluginCapabilities,
+    process: Arc<Mutex<Option<PluginProcess>>>,
+    health: Arc<RwLock<PluginHealth>>,
+    // when the current process was started
+    started_at: Instant,
+    last_ping_at: Instant,
+    // reset once the plugin stays alive longer than the max backoff
+    consecutive_restarts: u32,
+    next_restart_at: Option<Instant>,
+}
+
+impl SupervisorState {
+    fn supervise(&mut self) {
+        match self.liveness() {
+            PluginLiveness::Alive => self.check(),
+            PluginLiveness::Restarting => {
+                if self
+                    .next_restart_at
+                    .map_or(true, |restart_at| Instant::now() >= restart_at)
+                {
+                    self.restart();
+                }
+            }
+            PluginLiveness::Dead | PluginLiveness::Stopped => {}
+        }
+    }
+
+    fn check(&mut self) {
+        let exited = {
+            let mut process = self.process.lock().expect("lock plugin process");
+            match process.as_mut().map(|process| process.child.try_wait()) {
+                Some(Ok(Some(exit_status))) => Some(format!("exited with {}", exit_status)),
+                Some(Ok(None)) => None,
+                Some(Err(err)) => Some(format!("wait error: {}", err)),
+                None => Some("no process".to_owned()),
+            }
+        };
+        if let Some(reason) = exited {
+            self.fail(reason);
+            return;
+        }
+
+        if self.last_ping_at.elapsed() < Duration::from_secs(self.config.get_ping_interval_secs()) {
+            return;
+        }
+        self.last_ping_at = Instant::now();
+        match self.ping() {
+            Ok(false) => log::debug!("plugin {} is busy with a request", self.plugin_name),
+            Ok(true) => {
+                let mut health = self.health.write().expect("write plugin health");
+                health.last_seen_at = Some(unix_timestamp());
+                if self.started_at.elapsed()
+                    >= Duration::from_secs(self.config.get_backoff_max_secs())
+                {
+                    self.consecutive_restarts = 0;
+                }
+            }
+            Err(reason) => self.fail(reason),
+        }
+    }
+
+    /// Returns false if the ping waits behind a request instead of being answered.
+    fn ping(&self) -> Result<bool, String> {
+        let (request_handler, busy) = self
+            .process
+            .lock()
+            .expect("lock plugin process")
+            .as_ref()
+            .map(|process| (process.request_handler(), process.busy()))
+            .ok_or_else(|| "no process".to_owned())?;
+        let timeout = Duration::from_secs(self.config.get_ping_timeout_secs());
+        match call(&request_handler, MessageFromHost::Ping, timeout) {
+            Ok(MessageFromPlugin::Pong) => Ok(true),
+            Ok(msg) => Err(format!("unexpected response to ping: {:?}", msg)),
+            Err(_) if busy.load(Ordering::Relaxed) => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Kill the failed process, and schedule a restart if the budget allows.
+    fn fail(&mut self, reason: String) {
+        log::warn!("plugin {} failed: {}", self.plugin_name, reason);
+        if let Some(process) = self.process.lock().expect("lock plugin process").take() {
+            kill(process.child);
+        }
+
+        let mut health = self.health.write().expect("write plugin health");
+        health.pid = None;
+        health.last_error = Some(reason);
+        if self.consecutive_restarts >= self.config.get_max_restarts() {
+            log::error!(
+                "plugin {} used up its {} restarts, give it up",
+                self.plugin_name,
+                self.config.get_max_restarts()
+            );
+            health.liveness = PluginLiveness::Dead;
+            self.next_restart_at = None;
+        } else {
+            let backoff = self.backoff();
+            log::info!(
+                "restart plugin {} in {} seconds",
+                self.plugin_name,
+                backoff.as_secs()
+            );
+            health.liveness = PluginLiveness::Restarting;
+            self.next_restart_at = Some(Instant::now() + backoff);
+        }
+    }
+
+    fn restart(&mut self) {
+        self.consecutive_restarts += 1;
+        self.health.write().expect("write plugin health").restarts += 1;
+        match PluginProcess::spawn(
+            &self.runtime,
+            &self.binary_path,
+            &self.plugin_name,
+            self.service_handler.clone(),
+            self.plugin_config
+                .read()
+                .expect("read plugin config")
+                .clone(),
+        ) {
+            Ok(process) => {
+                let capabilities = process.capabilities();
+                if capabilities.subscriptions != self.capabilities.subscriptions
+                    || capabilities.rpc_namespace != self.capabilities.rpc_namespace
+                {
+                    kill(process.child);
+                    self.give_up(format!(
+                        "the restarted process declares other capabilities: {:?}",
+                        capabilities
+                    ));
+                    return;
+                }
+                log::info!("plugin {} restarted", self.plugin_name);
+                if let Err(err) = process
+                    .msg_handler()
+                    .send((0, MessageFromHost::OtxPoolStart))
+                {
+                    log::warn!("notify plugin {} error: {}", self.plugin_name, err);
+                }
+                let mut health = self.health.write().expect("write plugin health");
+                health.liveness = PluginLiveness::Alive;
+                health.pid = Some(process.child.id());
+                *self.process.lock().expect("lock plugin process") = Some(process);
+                self.started_at = Instant::now();
+                self.last_ping_at = Instant::now();
+                self.next_restart_at = None;
+            }
+            Err(err) => self.fail(format!("restart error: {}", err)),
+        }
+    }
+
+    /// Keep the plugin dead, it is not restarted anymore.
+    fn give_up(&mut self, reason: String) {
+        log::error!("plugin {} is given up: {}", self.plugin_name, reason);
+        let mut health = self.health.write().expect("write plugin health");
+        health.liveness = PluginLiveness::Dead;
+        health.pid = None;
+        health.last_error = Some(reason);
+        self.next_restart_at = None;
+    }
+
+    /// The delay before the next restart, doubles after every consecutive restart.
+    fn backoff(&self) -> Duration {
+        let base = self.config.get_backoff_base_secs();
+        let secs = base
+            .checked_shl(self.consecutive_restarts)
+            .unwrap_or(u64::MAX)
+            .min(self.config.get_backoff_max_secs());
+        Duration::from_secs(secs)
+    }
+
+    fn terminate(&mut self) {
+        if let Some(mut process) = self.process.lock().expect("lock plugin process").take() {
+            log::info!("terminate plugin {}", self.plugin_name);
+            terminate(
+                &mut process.child,
+                Duration::from_secs(self.config.get_terminate_timeout_secs()),
+            );
+        }
+        let mut health = self.health.write().expect("write plugin health");
+        health.liveness = PluginLiveness::Stopped;
+        health.pid = None;
+    }
+
+    fn liveness(&self) -> PluginLiveness {
+        self.health.read().expect("read plugin health").liveness
+    }
+}
+
+/// Send SIGTERM to the process, and SIGKILL if it does not exit before the timeout.
+fn terminate(child: &mut Child, timeout: Duration) {
+    #[cfg(unix)]
+    unsafe {
+        libc::kill(child.id() as libc::pid_t, libc::SIGTERM);
+    }
+    let deadline = Instant::now() + timeout;
+    while Instant::now() < deadline {
+        match child.try_wait() {
+            Ok(Some(_)) => return,
+            Ok(None) => thread::sleep(TERMINATE_POLL_INTERVAL),
+            Err(_) => break,
+        }
+    }
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+fn kill(mut child: Child) {
+    let _ = child.kill();
+    let _ = child.wait();
+}
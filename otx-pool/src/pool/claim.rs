@@ -0,0 +1,69 @@
+use ckb_types::H256;
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+struct Claim {
+    owner: String,
+    // unix timestamp in seconds
+    expires_at: u64,
+}
+
+/// The exclusive leases of the plugins on the pending otxs,
+/// an otx claimed by a plugin can not be consumed by the others until the lease ends.
+#[derive(Default)]
+pub struct OtxClaims {
+    claims: Mutex<HashMap<H256, Claim>>,
+}
+
+impl OtxClaims {
+    /// Claim all the otxs for the owner or none of them, the leases already held by the owner
+    /// are extended. Returns the otx claimed by another owner if there is one.
+    pub fn claim(&self, ids: &[H256], owner: &str, now: u64, expires_at: u64) -> Result<(), H256> {
+        let mut claims = self.claims.lock().expect("lock claims");
+        claims.retain(|_, claim| claim.expires_at > now);
+        if let Some(id) = ids
+            .iter()
+            .find(|id| claims.get(*id).map_or(false, |claim| claim.owner != owner))
+        {
+            return Err(id.to_owned());
+        }
+        for id in ids {
+            claims.insert(
+                id.to_owned(),
+                Claim {
+                    owner: owner.to_owned(),
+                    expires_at,
+                },
+            );
+        }
+        Ok(())
+    }
+
+    /// Release the otxs claimed by the owner, the otxs claimed by others are left alone.
+    pub fn release(&self, ids: &[H256], owner: &str) {
+        let mut claims = self.claims.lock().expect("lock claims");
+        for id in ids {
+            if claims.get(id).map_or(false, |claim| claim.owner == owner) {
+                claims.remove(id);
+            }
+        }
+    }
+
+    /// Release the otx whoever claimed it, e.g. once it is no longer pending.
+    pub fn remove(&self, id: &H256) {
+        self.claims.lock().expect("lock claims").remove(id);
+    }
+
+    /// The first otx held by another owner with an unexpired lease.
+    pub fn find_claimed_by_others(&self, ids: &[H256], owner: &str, now: u64) -> Option<H256> {
+        let claims = self.claims.lock().expect("lock claims");
+        ids.iter()
+            .find(|id| {
+                claims.get(*id).map_or(false, |claim| {
+                    claim.owner != owner && claim.expires_at > now
+                })
+            })
+            .cloned()
+    }
+}
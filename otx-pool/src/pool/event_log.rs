@@ -0,0 +1,119 @@
+use crate::store::OtxStore;
+
+use otx_format::types::{EventPage, EventRecord, PoolEvent};
+
+use anyhow::Result;
+
+use std::sync::{Arc, Mutex};
+
+/// The append-only log of the pool events, numbered by increasing sequence numbers,
+/// from which the plugins missing events catch up.
+///
+/// Only the latest `max_events` events are kept, 0 keeps them all.
+pub struct EventLog {
+    store: Arc<dyn OtxStore>,
+    // the sequence number of the latest event, also serializes the appends
+    last_seq: Mutex<u64>,
+    max_events: u64,
+}
+
+impl EventLog {
+    pub fn open(store: Arc<dyn OtxStore>, max_events: u64) -> Result<Self> {
+        let last_seq = store.get_last_event_seq()?;
+        log::info!("event log opened at sequence number {}", last_seq);
+        let event_log = EventLog {
+            store,
+            last_seq: Mutex::new(last_seq),
+            max_events,
+        };
+        // the max events may be lowered since the last run
+        event_log.trim(last_seq);
+        Ok(event_log)
+    }
+
+    /// Append the event and publish it with its sequence number, 0 if it is not recorded.
+    /// The event is published before the next one is appended, so that the live events
+    /// come in the order of the log.
+    pub fn append(&self, event: PoolEvent, publish: impl FnOnce(u64)) {
+        let mut last_seq = self.last_seq.lock().expect("lock event log");
+        let seq = *last_seq + 1;
+        if let Err(err) = self.store.append_event(&EventRecord::new(seq, event)) {
+            log::error!("append event {} error: {:?}", seq, err);
+            publish(0);
+            return;
+        }
+        *last_seq = seq;
+        publish(seq);
+        self.trim(seq);
+    }
+
+    /// Up to `limit` events from the sequence number on. The events older than the log
+    /// are skipped, so the first event returned may come after the one asked for.
+    pub fn get_events(&self, from_seq: u64, limit: usize) -> Result<EventPage> {
+        // no event is appended in between, so that no event comes after the last one
+        let last_seq = self.last_seq.lock().expect("lock event log");
+        let events = self.store.get_events(from_seq, limit)?;
+        Ok(EventPage {
+            events,
+            last_seq: *last_seq,
+        })
+    }
+
+    /// Delete the events older than the latest `max_events` ones.
+    fn trim(&self, last_seq: u64) {
+        if self.max_events == 0 || last_seq <= self.max_events {
+            return;
+        }
+        let first_seq = last_seq - self.max_events + 1;
+        if let Err(err) = self.store.delete_events_before(first_seq) {
+            log::error!("delete events before {} error: {:?}", first_seq, err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::MemoryStore;
+
+    use ckb_types::H256;
+
+    // the sequence numbers the events are published with
+    fn append_commits(event_log: &EventLog, count: u8) -> Vec<u64> {
+        let mut seqs = vec![];
+        for n in 0..count {
+            event_log.append(PoolEvent::CommitOtx(vec![H256([n; 32])]), |seq| {
+                seqs.push(seq)
+            });
+        }
+        seqs
+    }
+
+    fn get_seqs(event_log: &EventLog) -> Vec<u64> {
+        let page = event_log.get_events(0, 100).unwrap();
+        page.events.iter().map(|record| record.seq).collect()
+    }
+
+    #[test]
+    fn test_max_events() {
+        let store: Arc<dyn OtxStore> = Arc::new(MemoryStore::default());
+        let event_log = EventLog::open(store.clone(), 3).unwrap();
+        assert_eq!(append_commits(&event_log, 5), vec![1, 2, 3, 4, 5]);
+        assert_eq!(get_seqs(&event_log), vec![3, 4, 5]);
+        assert_eq!(event_log.get_events(0, 100).unwrap().last_seq, 5);
+    }
+
+    #[test]
+    fn test_lower_max_events_on_reopen() {
+        let store: Arc<dyn OtxStore> = Arc::new(MemoryStore::default());
+        let event_log = EventLog::open(store.clone(), 0).unwrap();
+        append_commits(&event_log, 5);
+        assert_eq!(get_seqs(&event_log), vec![1, 2, 3, 4, 5]);
+
+        // the events beyond the new max are trimmed at once, and the numbering goes on
+        let event_log = EventLog::open(store, 2).unwrap();
+        assert_eq!(get_seqs(&event_log), vec![4, 5]);
+        assert_eq!(append_commits(&event_log, 1), vec![6]);
+        assert_eq!(get_seqs(&event_log), vec![5, 6]);
+    }
+}
@@ -0,0 +1,102 @@
+use otx_pool_config::EvictionPolicy;
+
+use ckb_types::H256;
+
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Mutex;
+
+// the order of the otxs to evict, the first one is evicted first
+type EvictionKey = (u64, u64, H256);
+
+#[derive(Default)]
+struct Queue {
+    keys: BTreeSet<EvictionKey>,
+    // the eviction key and the fee of every otx in the queue
+    entries: HashMap<H256, (EvictionKey, u64)>,
+}
+
+/// The pending otxs in the order they are evicted when the pool is full,
+/// so that the otxs to evict are found without scanning the pool.
+pub struct EvictionQueue {
+    policy: EvictionPolicy,
+    queue: Mutex<Queue>,
+}
+
+impl EvictionQueue {
+    pub fn new(policy: EvictionPolicy) -> Self {
+        EvictionQueue {
+            policy,
+            queue: Mutex::new(Queue::default()),
+        }
+    }
+
+    pub fn insert(&self, id: &H256, received_at: u64, fee: u64) {
+        let key = match self.policy {
+            EvictionPolicy::OldestFirst => (received_at, 0, id.to_owned()),
+            EvictionPolicy::LowestFeeFirst => (fee, received_at, id.to_owned()),
+        };
+        let mut queue = self.queue.lock().expect("lock eviction queue");
+        if let Some((old_key, _)) = queue.entries.insert(id.to_owned(), (key.clone(), fee)) {
+            queue.keys.remove(&old_key);
+        }
+        queue.keys.insert(key);
+    }
+
+    pub fn remove(&self, id: &H256) {
+        let mut queue = self.queue.lock().expect("lock eviction queue");
+        if let Some((key, _)) = queue.entries.remove(id) {
+            queue.keys.remove(&key);
+        }
+    }
+
+    /// The otxs to evict so that one more otx fits in the max size, along with their fees.
+    pub fn select_evicted(&self, max_size: usize) -> Vec<(H256, u64)> {
+        let queue = self.queue.lock().expect("lock eviction queue");
+        if queue.keys.len() < max_size {
+            return vec![];
+        }
+        queue
+            .keys
+            .iter()
+            .take(queue.keys.len() + 1 - max_size)
+            .map(|(_, _, id)| (id.to_owned(), queue.entries[id].1))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(n: u8) -> H256 {
+        H256([n; 32])
+    }
+
+    #[test]
+    fn test_oldest_first() {
+        let queue = EvictionQueue::new(EvictionPolicy::OldestFirst);
+        queue.insert(&id(1), 30, 100);
+        queue.insert(&id(2), 10, 300);
+        queue.insert(&id(3), 20, 200);
+        assert!(queue.select_evicted(4).is_empty());
+        assert_eq!(queue.select_evicted(3), vec![(id(2), 300)]);
+        assert_eq!(queue.select_evicted(2), vec![(id(2), 300), (id(3), 200)]);
+
+        queue.remove(&id(2));
+        assert_eq!(queue.select_evicted(2), vec![(id(3), 200)]);
+    }
+
+    #[test]
+    fn test_lowest_fee_first() {
+        let queue = EvictionQueue::new(EvictionPolicy::LowestFeeFirst);
+        queue.insert(&id(1), 30, 100);
+        queue.insert(&id(2), 10, 300);
+        queue.insert(&id(3), 20, 100);
+        // the older otx goes first among the same fee
+        assert_eq!(queue.select_evicted(2), vec![(id(3), 100), (id(1), 100)]);
+
+        // the fee of a replaced otx is updated
+        queue.insert(&id(3), 20, 400);
+        assert_eq!(queue.select_evicted(2), vec![(id(1), 100), (id(2), 300)]);
+    }
+}
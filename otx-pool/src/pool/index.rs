@@ -0,0 +1,179 @@
+use otx_format::types::{OpenTxStatus, OpenTxStatusType, OpenTxWithStatus, OtxFilter};
+
+use ckb_types::{packed, prelude::*, H256};
+use dashmap::DashMap;
+
+use std::collections::HashSet;
+
+/// The secondary indexes of the otxs in the pool, every index maps a key to the otx ids.
+#[derive(Default)]
+pub struct OtxIndexes {
+    status: DashMap<OpenTxStatusType, HashSet<H256>>,
+    input_lock: DashMap<H256, HashSet<H256>>,
+    output_lock: DashMap<H256, HashSet<H256>>,
+    type_script: DashMap<H256, HashSet<H256>>,
+    // the merged otx or the final tx including the otx
+    tx_hash: DashMap<H256, HashSet<H256>>,
+}
+
+impl OtxIndexes {
+    pub fn insert(&self, id: &H256, otx: &OpenTxWithStatus) {
+        add(&self.status, otx.status.status_type(), id);
+        if let Some(tx_hash) = get_tx_hash(&otx.status) {
+            add(&self.tx_hash, tx_hash, id);
+        }
+        let scripts = ScriptHashes::new(otx);
+        scripts
+            .input_locks
+            .into_iter()
+            .for_each(|hash| add(&self.input_lock, hash, id));
+        scripts
+            .output_locks
+            .into_iter()
+            .for_each(|hash| add(&self.output_lock, hash, id));
+        scripts
+            .type_scripts
+            .into_iter()
+            .for_each(|hash| add(&self.type_script, hash, id));
+    }
+
+    pub fn remove(&self, id: &H256, otx: &OpenTxWithStatus) {
+        remove(&self.status, &otx.status.status_type(), id);
+        if let Some(tx_hash) = get_tx_hash(&otx.status) {
+            remove(&self.tx_hash, &tx_hash, id);
+        }
+        let scripts = ScriptHashes::new(otx);
+        scripts
+            .input_locks
+            .iter()
+            .for_each(|hash| remove(&self.input_lock, hash, id));
+        scripts
+            .output_locks
+            .iter()
+            .for_each(|hash| remove(&self.output_lock, hash, id));
+        scripts
+            .type_scripts
+            .iter()
+            .for_each(|hash| remove(&self.type_script, hash, id));
+    }
+
+    pub fn update_status(&self, id: &H256, old: &OpenTxStatus, new: &OpenTxStatus) {
+        remove(&self.status, &old.status_type(), id);
+        add(&self.status, new.status_type(), id);
+        if let Some(tx_hash) = get_tx_hash(old) {
+            remove(&self.tx_hash, &tx_hash, id);
+        }
+        if let Some(tx_hash) = get_tx_hash(new) {
+            add(&self.tx_hash, tx_hash, id);
+        }
+    }
+
+    pub fn count_by_status(&self, status: &OpenTxStatusType) -> usize {
+        self.status
+            .get(status)
+            .map(|ids| ids.len())
+            .unwrap_or_default()
+    }
+
+    pub fn get_by_tx_hash(&self, tx_hash: &H256) -> HashSet<H256> {
+        get(&self.tx_hash, tx_hash)
+    }
+
+    /// Get the ids of the otxs matching the filter, `None` means the filter has no condition.
+    pub fn query(&self, filter: &OtxFilter) -> Option<HashSet<H256>> {
+        let mut matched: Vec<HashSet<H256>> = vec![];
+        if let Some(status) = &filter.status {
+            matched.push(get(&self.status, status));
+        }
+        if let Some(lock) = &filter.input_lock {
+            matched.push(get(&self.input_lock, &script_hash(lock)));
+        }
+        if let Some(lock) = &filter.output_lock {
+            matched.push(get(&self.output_lock, &script_hash(lock)));
+        }
+        if let Some(type_script) = &filter.type_script {
+            matched.push(get(&self.type_script, &script_hash(type_script)));
+        }
+        if let Some(tx_hash) = &filter.tx_hash {
+            matched.push(get(&self.tx_hash, tx_hash));
+        }
+        matched.into_iter().reduce(|ids, other| &ids & &other)
+    }
+}
+
+struct ScriptHashes {
+    input_locks: HashSet<H256>,
+    output_locks: HashSet<H256>,
+    type_scripts: HashSet<H256>,
+}
+
+impl ScriptHashes {
+    fn new(otx: &OpenTxWithStatus) -> Self {
+        let input_locks = otx
+            .otx
+            .get_input_lock_scripts()
+            .unwrap_or_default()
+            .iter()
+            .flatten()
+            .map(script_hash)
+            .collect();
+
+        let outputs = otx.otx.get_outputs().unwrap_or_default();
+        let output_locks = outputs
+            .iter()
+            .map(|(output, _)| script_hash(&output.lock))
+            .collect();
+        let mut type_scripts: HashSet<H256> = outputs
+            .iter()
+            .filter_map(|(output, _)| output.type_.as_ref())
+            .map(script_hash)
+            .collect();
+        // the udt scripts of the inputs are recorded in the accounting meta
+        if let Ok(payment) = otx.otx.get_payment_amount() {
+            type_scripts.extend(
+                payment
+                    .x_udt_amount
+                    .keys()
+                    .chain(payment.s_udt_amount.keys())
+                    .map(script_hash),
+            );
+        }
+
+        ScriptHashes {
+            input_locks,
+            output_locks,
+            type_scripts,
+        }
+    }
+}
+
+fn get_tx_hash(status: &OpenTxStatus) -> Option<H256> {
+    match status {
+        OpenTxStatus::Merged(tx_hash) => Some(tx_hash.to_owned()),
+        _ => status.get_final_tx_hash().cloned(),
+    }
+}
+
+fn script_hash(script: &ckb_jsonrpc_types::Script) -> H256 {
+    packed::Script::from(script.to_owned())
+        .calc_script_hash()
+        .unpack()
+}
+
+fn add<K: std::hash::Hash + Eq>(index: &DashMap<K, HashSet<H256>>, key: K, id: &H256) {
+    index.entry(key).or_default().insert(id.to_owned());
+}
+
+fn remove<K: std::hash::Hash + Eq>(index: &DashMap<K, HashSet<H256>>, key: &K, id: &H256) {
+    if let Some(mut ids) = index.get_mut(key) {
+        ids.remove(id);
+    }
+    index.remove_if(key, |_, ids| ids.is_empty());
+}
+
+fn get<K: std::hash::Hash + Eq>(index: &DashMap<K, HashSet<H256>>, key: &K) -> HashSet<H256> {
+    index
+        .get(key)
+        .map(|ids| ids.value().clone())
+        .unwrap_or_default()
+}
@@ -1,75 +1,1398 @@
-use crate::error::InnerResult;
-use crate::notify::NotifyController;
+mod claim;
+mod event_log;
+mod eviction;
+mod index;
+
+pub use event_log::EventLog;
+
+use crate::error::{InnerResult, OtxPoolError};
+use crate::notify::{CommitOtx, NewOtx, NotifyController, OtxStatusChanged, RejectOtx};
+use crate::store::OtxStore;
 
 use otx_format::{
     jsonrpc_types::OpenTransaction,
-    types::{packed, OpenTxStatus, OpenTxWithStatus},
+    types::{
+        packed, unix_timestamp, BlockInfo, EventPage, OpenTxDetail, OpenTxStatus, OpenTxStatusType,
+        OpenTxWithId, OpenTxWithStatus, Order, OtxFilter, Pagination, PoolEvent, PoolStats,
+        StatusTransition,
+    },
 };
+use otx_pool_config::{EvictionPolicy, PoolConfig};
 
-use ckb_jsonrpc_types::JsonBytes;
+use anyhow::Result;
+use ckb_jsonrpc_types::{JsonBytes, OutPoint};
 use ckb_types::{prelude::Entity, H256};
+use claim::OtxClaims;
 use dashmap::mapref::entry::Entry;
 use dashmap::DashMap;
+use eviction::EvictionQueue;
+use index::OtxIndexes;
+
+use std::collections::{HashSet, VecDeque};
+use std::sync::{Arc, Mutex, RwLock};
+
+const MAX_PAGE_SIZE: usize = 1000;
+const MAX_CLAIM_LEASE_SECS: u64 = 600;
+
+/// The actor of the status changes made by the pool itself, e.g. expiry and eviction.
+pub const POOL_ACTOR: &str = "otx_pool";
 
 pub struct OtxPool {
     raw_otxs: DashMap<H256, OpenTxWithStatus>,
     sent_txs: DashMap<H256, Vec<H256>>,
+    // the inputs spent by the pending otxs
+    input_index: DashMap<OutPoint, H256>,
+    indexes: OtxIndexes,
+    // the pending otxs in the order they are evicted
+    eviction_queue: EvictionQueue,
+    // the pending otxs claimed by the plugins
+    claims: OtxClaims,
+    // serializes the admission of new otxs, the claims and the status changes,
+    // so that the checks of an operation still hold when it changes the otxs
+    status_lock: Mutex<()>,
+    // the recently rejected otxs, the oldest is at the front
+    rejected_otxs: Mutex<VecDeque<H256>>,
+    // the tip of the canonical chain seen by the chain watcher
+    chain_tip: RwLock<Option<BlockInfo>>,
+    config: PoolConfig,
+    store: Arc<dyn OtxStore>,
+    // the new otx, status change and commit events, for the plugins to catch up
+    event_log: EventLog,
     notify_ctrl: NotifyController,
 }
 
 impl OtxPool {
-    pub fn new(notify_ctrl: NotifyController) -> Self {
-        let raw_otxs = DashMap::new();
-        let sent_txs = DashMap::new();
-        OtxPool {
+    pub fn new(
+        notify_ctrl: NotifyController,
+        store: Arc<dyn OtxStore>,
+        event_log: EventLog,
+        config: PoolConfig,
+    ) -> Result<Self> {
+        let raw_otxs: DashMap<H256, OpenTxWithStatus> = store.get_otxs()?.into_iter().collect();
+        let sent_txs: DashMap<H256, Vec<H256>> = store.get_sent_txs()?.into_iter().collect();
+        log::info!(
+            "otx pool reloaded {} otxs and {} sent txs from store",
+            raw_otxs.len(),
+            sent_txs.len()
+        );
+
+        let mut rejected_otxs: Vec<(u64, H256)> = raw_otxs
+            .iter()
+            .filter(|pair| matches!(pair.value().status, OpenTxStatus::Rejected(_)))
+            .map(|pair| (pair.value().received_at, pair.key().to_owned()))
+            .collect();
+        rejected_otxs.sort();
+        let rejected_otxs = rejected_otxs.into_iter().map(|(_, id)| id).collect();
+
+        let pool = OtxPool {
             raw_otxs,
             sent_txs,
+            input_index: DashMap::new(),
+            indexes: OtxIndexes::default(),
+            eviction_queue: EvictionQueue::new(config.get_eviction_policy()),
+            claims: OtxClaims::default(),
+            status_lock: Mutex::new(()),
+            rejected_otxs: Mutex::new(rejected_otxs),
+            chain_tip: RwLock::new(None),
+            config,
+            store,
+            event_log,
             notify_ctrl,
-        }
+        };
+        pool.prune_rejected_otxs();
+        pool.raw_otxs
+            .iter()
+            .for_each(|pair| pool.indexes.insert(pair.key(), pair.value()));
+        pool.raw_otxs
+            .iter()
+            .filter(|pair| pair.value().status == OpenTxStatus::Pending)
+            .for_each(|pair| {
+                pool.index_inputs(pair.key(), &pair.value().otx);
+                pool.enqueue_eviction(pair.key(), pair.value());
+            });
+        Ok(pool)
     }
 
-    pub fn insert(&self, mut otx: OpenTransaction) -> InnerResult<H256> {
+    /// Submitting a pending otx again is a no-op, while an otx kept in the pool with another
+    /// status, e.g. evicted, expired or rejected, is refused.
+    pub fn insert(&self, mut otx: OpenTransaction, actor: &str) -> InnerResult<H256> {
         let tx_hash = otx.get_or_insert_otx_id()?;
-        match self.raw_otxs.entry(tx_hash.clone()) {
-            Entry::Vacant(entry) => {
-                entry.insert(OpenTxWithStatus::new(otx.clone()));
-                self.notify_ctrl.notify_new_open_tx(otx)
+        let _guard = self.status_lock.lock().expect("lock otx status");
+        if let Some(existing) = self.raw_otxs.get(&tx_hash) {
+            if existing.status == OpenTxStatus::Pending {
+                return Ok(tx_hash);
             }
-            Entry::Occupied(_) => {}
-        };
+            return Err(OtxPoolError::OtxAlreadyExists.into());
+        }
+        self.resolve_conflicts(&tx_hash, &otx)?;
+        self.make_room_for(&otx)?;
+        self.admit(&tx_hash, &otx, actor, None)?;
+        self.publish_new_otx(otx);
         Ok(tx_hash)
     }
 
+    /// Add the merged otx of a plugin, and move the otxs it includes to merged.
+    ///
+    /// The merged otx is checked and inserted before the otxs included change,
+    /// so nothing changes if the merged otx is refused.
+    pub fn merge_otxs(
+        &self,
+        mut merged_otx: OpenTransaction,
+        included_otx_hashes: &[H256],
+        actor: &str,
+    ) -> InnerResult<H256> {
+        if included_otx_hashes.is_empty() {
+            return Err(OtxPoolError::InvalidParams("no otx is merged".to_owned()).into());
+        }
+        let merged_otx_hash = merged_otx.get_or_insert_otx_id()?;
+        let merged = OpenTxStatus::Merged(merged_otx_hash.clone());
+
+        let guard = self.status_lock.lock().expect("lock otx status");
+        self.ensure_not_claimed(included_otx_hashes, actor)?;
+        let mut included_otxs: Vec<(H256, OpenTransaction)> = vec![];
+        for id in included_otx_hashes {
+            match self.raw_otxs.get(id) {
+                Some(otx) => included_otxs.push((id.to_owned(), otx.otx.clone())),
+                None => return Err(otx_not_found(id).into()),
+            }
+        }
+        self.check_transitions(included_otx_hashes, &merged)?;
+        if self.raw_otxs.contains_key(&merged_otx_hash) {
+            return Err(OtxPoolError::OtxAlreadyExists.into());
+        }
+        // the merged otx spends the inputs of the otxs included and takes their place,
+        // so no room needs to be made for it
+        included_otxs
+            .iter()
+            .for_each(|(id, otx)| self.unindex_inputs(id, otx));
+        let admitted = self
+            .resolve_conflicts(&merged_otx_hash, &merged_otx)
+            .and_then(|_| {
+                self.admit(
+                    &merged_otx_hash,
+                    &merged_otx,
+                    actor,
+                    Some(format!("merged from {} otxs", included_otx_hashes.len())),
+                )
+            });
+        if let Err(err) = admitted {
+            included_otxs
+                .iter()
+                .for_each(|(id, otx)| self.index_inputs(id, otx));
+            return Err(err);
+        }
+        self.transit_otxs_status(
+            included_otx_hashes,
+            merged,
+            actor,
+            Some(format!("merged into otx {:#x}", merged_otx_hash)),
+        )?;
+        drop(guard);
+        self.publish_new_otx(merged_otx);
+        Ok(merged_otx_hash)
+    }
+
+    /// Claim the pending otxs for a plugin, so that the other plugins can not merge, send,
+    /// discard or modify them until the lease ends. Either all the otxs are claimed or none.
+    ///
+    /// The claims are released once the otxs are no longer pending, e.g. merged, committed or
+    /// rejected. Claiming the otxs again extends the lease.
+    pub fn claim_otxs(&self, ids: &[H256], actor: &str, lease_secs: u64) -> InnerResult<u64> {
+        if ids.is_empty() {
+            return Err(OtxPoolError::InvalidParams("no otx to claim".to_owned()).into());
+        }
+        if lease_secs == 0 || lease_secs > MAX_CLAIM_LEASE_SECS {
+            return Err(OtxPoolError::InvalidParams(format!(
+                "the lease should be between 1 and {} seconds",
+                MAX_CLAIM_LEASE_SECS
+            ))
+            .into());
+        }
+        let _guard = self.status_lock.lock().expect("lock otx status");
+        for id in ids {
+            self.ensure_pending(id)?;
+        }
+        let now = unix_timestamp();
+        let expires_at = now + lease_secs;
+        self.claims
+            .claim(ids, actor, now, expires_at)
+            .map_err(OtxPoolError::OtxClaimed)?;
+        Ok(expires_at)
+    }
+
+    /// Release the otxs claimed by the plugin before the lease ends.
+    pub fn release_otxs(&self, ids: &[H256], actor: &str) {
+        self.claims.release(ids, actor)
+    }
+
+    /// Reject a pending or merged otx on behalf of a plugin, the plugins are notified to drop it.
+    pub fn discard_otx(&self, id: &H256, reason: String, actor: &str) -> InnerResult<()> {
+        let _guard = self.status_lock.lock().expect("lock otx status");
+        self.ensure_not_claimed(&[id.to_owned()], actor)?;
+        let status = match self.raw_otxs.get(id) {
+            Some(otx) => otx.status.clone(),
+            None => return Err(otx_not_found(id).into()),
+        };
+        let rejected = OpenTxStatus::Rejected(reason.clone());
+        if !status.can_transit_to(&rejected) {
+            return Err(invalid_transition(id, &status, &rejected).into());
+        }
+        self.reject(vec![(id.to_owned(), reason)], actor);
+        Ok(())
+    }
+
+    /// Replace a pending otx with its modified version, e.g. after a plugin adds a signature.
+    ///
+    /// The otx keeps its id if only the witnesses or the meta change. Otherwise the modified otx
+    /// enters the pool with the new id, and the original otx is rejected.
+    /// Either way the modified otx is broadcast to the plugins again, and the change is recorded
+    /// in the status history of both otxs.
+    pub fn modify_otx(
+        &self,
+        id: &H256,
+        mut otx: OpenTransaction,
+        actor: &str,
+    ) -> InnerResult<H256> {
+        let new_id = otx.get_tx_hash()?;
+        if otx.get_or_insert_otx_id()? != new_id {
+            return Err(OtxPoolError::MalformedOtx(
+                "the otx id in the meta does not match the modified otx".to_owned(),
+            )
+            .into());
+        }
+
+        let guard = self.status_lock.lock().expect("lock otx status");
+        self.ensure_not_claimed(&[id.to_owned()], actor)?;
+        let original = match self.raw_otxs.get(id) {
+            Some(original) => original.otx.clone(),
+            None => return Err(otx_not_found(id).into()),
+        };
+        self.ensure_pending(id)?;
+
+        if &new_id == id {
+            self.replace_otx(id, otx.clone(), actor)?;
+            drop(guard);
+            self.publish_new_otx(otx);
+            return Ok(new_id);
+        }
+
+        if self.raw_otxs.contains_key(&new_id) {
+            return Err(OtxPoolError::OtxAlreadyExists.into());
+        }
+        // the modified otx may spend the same inputs, and takes the place of the original otx
+        // so no room needs to be made for it
+        self.unindex_inputs(id, &original);
+        let admitted = self.resolve_conflicts(&new_id, &otx).and_then(|_| {
+            self.admit(
+                &new_id,
+                &otx,
+                actor,
+                Some(format!("modified from otx {:#x}", id)),
+            )
+        });
+        if let Err(err) = admitted {
+            self.index_inputs(id, &original);
+            return Err(err);
+        }
+        // no one sees both the original and the modified otx pending
+        self.reject(
+            vec![(id.to_owned(), format!("modified into otx {:#x}", new_id))],
+            actor,
+        );
+        drop(guard);
+        self.publish_new_otx(otx);
+        Ok(new_id)
+    }
+
+    /// Replay the new otx events of the reloaded pending otxs,
+    /// so that the plugins can rebuild their state after a restart.
+    pub fn notify_pending_otxs(&self) {
+        let pending_otxs: Vec<OpenTransaction> = self
+            .raw_otxs
+            .iter()
+            .filter(|pair| pair.value().status == OpenTxStatus::Pending)
+            .map(|pair| pair.value().otx.clone())
+            .collect();
+        log::info!("replay {} pending otxs", pending_otxs.len());
+        for otx in pending_otxs {
+            // the replayed otxs are not recorded in the event log again
+            self.notify_ctrl.notify::<NewOtx>((0, otx));
+        }
+    }
+
     pub fn get_otx_by_id(&self, id: H256) -> Option<OpenTxWithStatus> {
         self.raw_otxs.get(&id).map(|pair| pair.value().clone())
     }
 
-    pub fn update_otx_status(&self, id: &H256, status: OpenTxStatus) {
-        if let Some(mut otx) = self.raw_otxs.get_mut(id) {
-            otx.status = status;
+    /// Get the otx along with the merged otxs it went through and the final tx including it.
+    pub fn get_otx_detail(&self, id: &H256) -> Option<OpenTxDetail> {
+        let otx_with_status = self.get_otx_by_id(id.to_owned())?;
+
+        // follow the merged otxs until the outermost one, which is the final tx or not sent yet
+        let mut merged_otx_ids: Vec<H256> = vec![];
+        let mut status = otx_with_status.status.clone();
+        let mut current = id.to_owned();
+        loop {
+            let next = match &status {
+                OpenTxStatus::Merged(merged_otx_id) => merged_otx_id.to_owned(),
+                _ => match status.get_final_tx_hash() {
+                    Some(tx_hash) if tx_hash != &current && self.raw_otxs.contains_key(tx_hash) => {
+                        tx_hash.to_owned()
+                    }
+                    _ => break,
+                },
+            };
+            if &next == id || merged_otx_ids.contains(&next) {
+                break;
+            }
+            status = match self.raw_otxs.get(&next) {
+                Some(merged_otx) => merged_otx.status.clone(),
+                None => break,
+            };
+            merged_otx_ids.push(next.clone());
+            current = next;
+        }
+
+        let final_tx_hash = status
+            .get_final_tx_hash()
+            .filter(|tx_hash| self.sent_txs.contains_key(tx_hash))
+            .cloned();
+
+        Some(OpenTxDetail {
+            id: id.to_owned(),
+            otx_with_status,
+            merged_otx_ids,
+            final_tx_hash,
+        })
+    }
+
+    /// Move the otx to the new status, the transition is checked by the otx state machine
+    /// and recorded in the status history of the otx.
+    pub fn update_otx_status(
+        &self,
+        id: &H256,
+        status: OpenTxStatus,
+        actor: &str,
+        reason: Option<String>,
+    ) -> InnerResult<()> {
+        let _guard = self.status_lock.lock().expect("lock otx status");
+        self.transit_otx_status(id, status, actor, reason, OpenTxStatus::can_transit_to)
+    }
+
+    /// Move a batch of otxs to the new status on behalf of a plugin, nothing changes if any of
+    /// the transitions is invalid or any of the otxs is claimed by another plugin.
+    pub fn update_otxs_status(
+        &self,
+        ids: &[H256],
+        status: OpenTxStatus,
+        actor: &str,
+        reason: Option<String>,
+    ) -> InnerResult<()> {
+        let _guard = self.status_lock.lock().expect("lock otx status");
+        self.ensure_not_claimed(ids, actor)?;
+        self.transit_otxs_status(ids, status, actor, reason)
+    }
+
+    pub fn get_status_history(&self, id: &H256) -> InnerResult<Vec<StatusTransition>> {
+        self.store
+            .get_status_history(id)
+            .map_err(|err| OtxPoolError::StorageError(err.to_string()).into())
+    }
+
+    /// Roll back the otxs included in a sent tx which will not be committed,
+    /// the pending otxs are broadcast again so that plugins can retry them.
+    pub fn rollback_sent_tx(&self, tx_hash: &H256, actor: &str, reason: &str) {
+        let _guard = self.status_lock.lock().expect("lock otx status");
+        let otx_hashes = if let Some((_, otx_hashes)) = self.sent_txs.remove(tx_hash) {
+            otx_hashes
+        } else {
+            return;
+        };
+        if let Err(err) = self.store.delete_sent_tx(tx_hash) {
+            log::error!("delete sent tx {:#x} error: {:?}", tx_hash, err);
+        }
+        log::info!(
+            "roll back sent tx {:#x}: {}, includes otxs: {:?}",
+            tx_hash,
+            reason,
+            otx_hashes
+                .iter()
+                .map(|hash| hash.to_string())
+                .collect::<Vec<String>>()
+        );
+        for otx_hash in otx_hashes.iter() {
+            if let Err(err) = self.transit_otx_status(
+                otx_hash,
+                OpenTxStatus::Pending,
+                actor,
+                Some(reason.to_owned()),
+                |from, _| from.can_roll_back(),
+            ) {
+                log::warn!("roll back otx {:#x} error: {}", otx_hash, err);
+                continue;
+            }
+            if let Some(otx) = self.get_otx_by_id(otx_hash.to_owned()) {
+                self.publish_new_otx(otx.otx);
+            }
+        }
+        // the merged otx which became the final tx can not be broadcast again
+        if self.raw_otxs.contains_key(tx_hash) {
+            self.reject(vec![(tx_hash.to_owned(), reason.to_owned())], actor);
+        }
+    }
+
+    pub fn get_sent_tx(&self, tx_hash: &H256) -> Option<Vec<H256>> {
+        self.sent_txs.get(tx_hash).map(|pair| pair.value().clone())
+    }
+
+    /// Update the status of the otxs included in a sent tx, as well as the merged otx of the tx.
+    ///
+    /// Only a new status is recorded as a transition, e.g. the tx moves to another block,
+    /// the confirmations are updated in place.
+    pub fn update_sent_tx_status(
+        &self,
+        tx_hash: &H256,
+        status: OpenTxStatus,
+        confirmations: u64,
+        actor: &str,
+    ) {
+        let _guard = self.status_lock.lock().expect("lock otx status");
+        let mut otx_hashes = if let Some(otx_hashes) = self.get_sent_tx(tx_hash) {
+            otx_hashes
+        } else {
+            return;
+        };
+        otx_hashes.push(tx_hash.to_owned());
+        let changed_otx_hashes: Vec<H256> = otx_hashes
+            .iter()
+            .filter(|id| {
+                self.raw_otxs
+                    .get(id)
+                    .map_or(false, |otx| otx.status != status)
+            })
+            .cloned()
+            .collect();
+        if !changed_otx_hashes.is_empty() {
+            if let Err(err) = self.transit_otxs_status(&changed_otx_hashes, status, actor, None) {
+                log::warn!("update status of sent tx {:#x} error: {}", tx_hash, err);
+                return;
+            }
+        }
+        for id in otx_hashes.iter() {
+            self.update_confirmations(id, confirmations);
+        }
+    }
+
+    /// Get the sent txs which have not been confirmed yet, along with their current status.
+    pub fn get_unconfirmed_sent_txs(&self) -> Vec<(H256, OpenTxStatus)> {
+        self.sent_txs
+            .iter()
+            .filter_map(|pair| {
+                let id = pair.value().first().unwrap_or_else(|| pair.key());
+                let status = self.raw_otxs.get(id)?.status.clone();
+                match status {
+                    OpenTxStatus::Committed(_)
+                    | OpenTxStatus::Proposed(_)
+                    | OpenTxStatus::InBlock(_) => Some((pair.key().to_owned(), status)),
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+
+    pub fn get_pending_otx_by_input(&self, out_point: &OutPoint) -> Option<H256> {
+        self.input_index
+            .get(out_point)
+            .map(|pair| pair.value().clone())
+    }
+
+    pub fn get_stats(&self) -> PoolStats {
+        let count = |status| self.indexes.count_by_status(&status);
+        PoolStats {
+            pending_count: count(OpenTxStatusType::Pending),
+            merged_count: count(OpenTxStatusType::Merged),
+            sent_count: count(OpenTxStatusType::Committed)
+                + count(OpenTxStatusType::Proposed)
+                + count(OpenTxStatusType::InBlock),
+            confirmed_count: count(OpenTxStatusType::Confirmed),
+            rejected_count: count(OpenTxStatusType::Rejected),
+            max_pool_size: self.config.get_max_pool_size(),
         }
     }
 
-    pub fn insert_sent_tx(&self, tx_hash: H256, otx_hashes: Vec<H256>) {
+    pub fn get_chain_tip(&self) -> Option<BlockInfo> {
+        self.chain_tip.read().expect("read chain tip").clone()
+    }
+
+    pub fn set_chain_tip(&self, tip: BlockInfo) {
+        *self.chain_tip.write().expect("write chain tip") = Some(tip);
+    }
+
+    fn insert_sent_tx(&self, tx_hash: H256, otx_hashes: Vec<H256>) {
+        if let Err(err) = self.store.put_sent_tx(&tx_hash, &otx_hashes) {
+            log::error!("persist sent tx {:#x} error: {:?}", tx_hash, err);
+        }
         self.sent_txs.insert(tx_hash, otx_hashes);
     }
 
+    /// Record the sent tx, and notify the plugins that the otxs included are committed.
+    pub fn commit_otxs(&self, tx_hash: H256, otx_hashes: Vec<H256>) {
+        self.event_log
+            .append(PoolEvent::CommitOtx(otx_hashes.clone()), |seq| {
+                self.notify_ctrl
+                    .notify::<CommitOtx>((seq, otx_hashes.clone()))
+            });
+        self.insert_sent_tx(tx_hash, otx_hashes);
+    }
+
+    /// Read the event log from the sequence number on.
+    pub fn get_events(&self, from_seq: u64, limit: usize) -> InnerResult<EventPage> {
+        self.event_log
+            .get_events(from_seq, limit.min(MAX_PAGE_SIZE))
+            .map_err(|err| OtxPoolError::StorageError(err.to_string()).into())
+    }
+
     pub fn get_otxs_by_merged_otx_id(&self, id: &H256) -> Vec<OpenTxWithStatus> {
-        self.raw_otxs
+        self.indexes
+            .get_by_tx_hash(id)
+            .iter()
+            .filter_map(|otx_id| self.get_otx_by_id(otx_id.to_owned()))
+            .filter(|otx| matches!(&otx.status, OpenTxStatus::Merged(merged_otx_id) if merged_otx_id == id))
+            .collect()
+    }
+
+    /// List the otxs matching the filter, sorted by the time they entered the pool.
+    /// The page starts after the cursor returned by the previous call.
+    pub fn get_otxs(
+        &self,
+        filter: &OtxFilter,
+        order: Order,
+        limit: usize,
+        after: Option<JsonBytes>,
+    ) -> InnerResult<Pagination<OpenTxWithId>> {
+        if limit == 0 {
+            return Err(
+                OtxPoolError::InvalidParams("limit should be greater than 0".to_owned()).into(),
+            );
+        }
+        let after = after.map(|cursor| decode_cursor(&cursor)).transpose()?;
+
+        let mut otxs: Vec<((u64, H256), OpenTxWithStatus)> = match self.indexes.query(filter) {
+            Some(ids) => ids
+                .into_iter()
+                .filter_map(|id| {
+                    self.get_otx_by_id(id.clone())
+                        .map(|otx| ((otx.received_at, id), otx))
+                })
+                .collect(),
+            None => self
+                .raw_otxs
+                .iter()
+                .map(|pair| {
+                    (
+                        (pair.value().received_at, pair.key().to_owned()),
+                        pair.value().clone(),
+                    )
+                })
+                .collect(),
+        };
+        otxs.sort_by(|(a, _), (b, _)| a.cmp(b));
+        if order == Order::Desc {
+            otxs.reverse();
+        }
+
+        let objects: Vec<((u64, H256), OpenTxWithStatus)> = otxs
+            .into_iter()
+            .filter(|(key, _)| match (&after, order) {
+                (None, _) => true,
+                (Some(after), Order::Asc) => key > after,
+                (Some(after), Order::Desc) => key < after,
+            })
+            .take(limit.min(MAX_PAGE_SIZE))
+            .collect();
+        let last_cursor = objects
+            .last()
+            .map(|(key, _)| encode_cursor(key))
+            .unwrap_or_default();
+        Ok(Pagination {
+            objects: objects
+                .into_iter()
+                .map(|((_, id), otx_with_status)| OpenTxWithId {
+                    id,
+                    otx_with_status,
+                })
+                .collect(),
+            last_cursor,
+        })
+    }
+
+    /// Reject the pending otxs which have stayed in the pool longer than the configured ttl.
+    pub fn evict_expired_otxs(&self) {
+        let ttl = self.config.get_otx_ttl_secs();
+        if ttl == 0 {
+            return;
+        }
+        let _guard = self.status_lock.lock().expect("lock otx status");
+        let now = unix_timestamp();
+        let expired_otxs: Vec<(H256, String)> = self
+            .raw_otxs
             .iter()
             .filter(|pair| {
-                if let OpenTxStatus::Merged(merged_otx_id) = &pair.value().status {
-                    merged_otx_id == id
-                } else {
-                    false
+                pair.value().status == OpenTxStatus::Pending
+                    && pair.value().received_at.saturating_add(ttl) <= now
+            })
+            .map(|pair| {
+                (
+                    pair.key().to_owned(),
+                    format!("expired after {} seconds", ttl),
+                )
+            })
+            .collect();
+        self.reject(expired_otxs, POOL_ACTOR);
+    }
+
+    /// Move the otxs into the recently rejected store, and notify the plugins to drop them.
+    pub fn reject_otxs(&self, otxs: Vec<(H256, String)>, actor: &str) {
+        let _guard = self.status_lock.lock().expect("lock otx status");
+        self.reject(otxs, actor)
+    }
+
+    pub fn flush(&self) -> Result<()> {
+        self.store.flush()
+    }
+
+    /// Reject the otxs, the caller holds the status lock.
+    fn reject(&self, otxs: Vec<(H256, String)>, actor: &str) {
+        let otxs: Vec<(H256, String)> = otxs
+            .into_iter()
+            .filter(|(id, reason)| {
+                let status = OpenTxStatus::Rejected(reason.to_owned());
+                match self.transit_otx_status(
+                    id,
+                    status,
+                    actor,
+                    Some(reason.to_owned()),
+                    OpenTxStatus::can_transit_to,
+                ) {
+                    Ok(_) => true,
+                    Err(err) => {
+                        log::warn!("reject otx {:#x} error: {}", id, err);
+                        false
+                    }
                 }
             })
-            .map(|pair| pair.value().clone())
-            .collect()
+            .collect();
+        if otxs.is_empty() {
+            return;
+        }
+        log::info!(
+            "reject otxs: {:?}",
+            otxs.iter()
+                .map(|(id, reason)| format!("{:#x}: {}", id, reason))
+                .collect::<Vec<String>>()
+        );
+        for (id, _) in otxs.iter() {
+            self.rejected_otxs
+                .lock()
+                .expect("lock rejected otxs")
+                .push_back(id.to_owned());
+        }
+        self.prune_rejected_otxs();
+        self.notify_ctrl.notify::<RejectOtx>(otxs);
     }
+
+    /// Check whether the new otx spends the same inputs as the pending otxs.
+    /// With replace-by-fee enabled, the conflicting otxs are rejected if the new otx pays a higher fee.
+    fn resolve_conflicts(&self, id: &H256, otx: &OpenTransaction) -> InnerResult<()> {
+        let conflicting_otxs: HashSet<H256> = otx
+            .get_input_out_points()?
+            .iter()
+            .filter_map(|out_point| {
+                self.input_index
+                    .get(out_point)
+                    .map(|pair| pair.value().clone())
+            })
+            .filter(|conflicting_id| conflicting_id != id)
+            .collect();
+        if conflicting_otxs.is_empty() {
+            return Ok(());
+        }
+        if !self.config.is_replace_by_fee_enabled() {
+            let conflicting_id = conflicting_otxs
+                .into_iter()
+                .next()
+                .expect("conflicting otx");
+            return Err(OtxPoolError::InputConflict(conflicting_id).into());
+        }
+
+        let fee = otx.get_max_fee();
+        for conflicting_id in conflicting_otxs.iter() {
+            let conflicting_fee = self
+                .raw_otxs
+                .get(conflicting_id)
+                .map(|pair| pair.value().otx.get_max_fee())
+                .unwrap_or_default();
+            if conflicting_fee >= fee {
+                return Err(OtxPoolError::ReplacementFeeTooLow(conflicting_id.to_owned()).into());
+            }
+        }
+        let replaced_otxs = conflicting_otxs
+            .into_iter()
+            .map(|conflicting_id| {
+                (
+                    conflicting_id,
+                    format!("replaced by otx {:#x} with a higher fee", id),
+                )
+            })
+            .collect();
+        self.reject(replaced_otxs, POOL_ACTOR);
+        Ok(())
+    }
+
+    /// Add a new pending otx, the caller publishes it once the admission is complete.
+    fn admit(
+        &self,
+        tx_hash: &H256,
+        otx: &OpenTransaction,
+        actor: &str,
+        reason: Option<String>,
+    ) -> InnerResult<()> {
+        match self.raw_otxs.entry(tx_hash.to_owned()) {
+            Entry::Vacant(entry) => {
+                let otx_with_status = OpenTxWithStatus::new(otx.to_owned());
+                self.store
+                    .put_otx(tx_hash, &otx_with_status)
+                    .map_err(|err| OtxPoolError::StorageError(err.to_string()))?;
+                self.indexes.insert(tx_hash, &otx_with_status);
+                self.record_status_transition(
+                    tx_hash,
+                    StatusTransition::new(None, OpenTxStatus::Pending, actor, reason),
+                );
+                self.enqueue_eviction(tx_hash, &otx_with_status);
+                entry.insert(otx_with_status);
+                self.index_inputs(tx_hash, otx);
+                Ok(())
+            }
+            Entry::Occupied(_) => Err(OtxPoolError::OtxAlreadyExists.into()),
+        }
+    }
+
+    /// The otxs not in the pool are skipped, e.g. the final tx which is not an otx.
+    fn check_transitions(&self, ids: &[H256], status: &OpenTxStatus) -> InnerResult<()> {
+        for id in ids {
+            if let Some(otx) = self.raw_otxs.get(id) {
+                if !otx.status.can_transit_to(status) {
+                    return Err(invalid_transition(id, &otx.status, status).into());
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Move a batch of otxs to the new status once all the transitions are checked.
+    fn transit_otxs_status(
+        &self,
+        ids: &[H256],
+        status: OpenTxStatus,
+        actor: &str,
+        reason: Option<String>,
+    ) -> InnerResult<()> {
+        self.check_transitions(ids, &status)?;
+        for id in ids {
+            self.transit_otx_status(
+                id,
+                status.clone(),
+                actor,
+                reason.clone(),
+                OpenTxStatus::can_transit_to,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Check that none of the otxs is claimed by a plugin other than the actor.
+    fn ensure_not_claimed(&self, ids: &[H256], actor: &str) -> InnerResult<()> {
+        match self
+            .claims
+            .find_claimed_by_others(ids, actor, unix_timestamp())
+        {
+            Some(id) => Err(OtxPoolError::OtxClaimed(id).into()),
+            None => Ok(()),
+        }
+    }
+
+    fn ensure_pending(&self, id: &H256) -> InnerResult<()> {
+        match self.raw_otxs.get(id) {
+            Some(otx) if otx.status == OpenTxStatus::Pending => Ok(()),
+            Some(otx) => Err(invalid_transition(id, &otx.status, &OpenTxStatus::Pending).into()),
+            None => Err(otx_not_found(id).into()),
+        }
+    }
+
+    /// Replace the content of a pending otx without changing its id, e.g. new witnesses.
+    fn replace_otx(&self, id: &H256, otx: OpenTransaction, actor: &str) -> InnerResult<()> {
+        let mut otx_with_status = match self.raw_otxs.get_mut(id) {
+            Some(otx_with_status) => otx_with_status,
+            None => return Err(otx_not_found(id).into()),
+        };
+        if otx_with_status.status != OpenTxStatus::Pending {
+            return Err(
+                invalid_transition(id, &otx_with_status.status, &OpenTxStatus::Pending).into(),
+            );
+        }
+        let mut modified = otx_with_status.value().clone();
+        modified.otx = otx;
+        self.store
+            .put_otx(id, &modified)
+            .map_err(|err| OtxPoolError::StorageError(err.to_string()))?;
+        self.indexes.remove(id, otx_with_status.value());
+        self.indexes.insert(id, &modified);
+        self.unindex_inputs(id, &otx_with_status.otx);
+        self.index_inputs(id, &modified.otx);
+        self.enqueue_eviction(id, &modified);
+        *otx_with_status = modified;
+        self.record_status_transition(
+            id,
+            StatusTransition::new(
+                Some(OpenTxStatus::Pending),
+                OpenTxStatus::Pending,
+                actor,
+                Some("modified".to_owned()),
+            ),
+        );
+        Ok(())
+    }
+
+    fn update_confirmations(&self, id: &H256, confirmations: u64) {
+        let mut otx = match self.raw_otxs.get_mut(id) {
+            Some(otx) => otx,
+            None => return,
+        };
+        if otx.confirmations == confirmations {
+            return;
+        }
+        otx.confirmations = confirmations;
+        if let Err(err) = self.store.put_otx(id, otx.value()) {
+            log::error!("persist otx {:#x} confirmations error: {:?}", id, err);
+        }
+    }
+
+    fn transit_otx_status(
+        &self,
+        id: &H256,
+        status: OpenTxStatus,
+        actor: &str,
+        reason: Option<String>,
+        is_allowed: fn(&OpenTxStatus, &OpenTxStatus) -> bool,
+    ) -> InnerResult<()> {
+        let mut otx = match self.raw_otxs.get_mut(id) {
+            Some(otx) => otx,
+            None => return Ok(()),
+        };
+        if !is_allowed(&otx.status, &status) {
+            return Err(invalid_transition(id, &otx.status, &status).into());
+        }
+        let was_pending = otx.status == OpenTxStatus::Pending;
+        let is_pending = status == OpenTxStatus::Pending;
+        self.indexes.update_status(id, &otx.status, &status);
+        let transition =
+            StatusTransition::new(Some(otx.status.clone()), status.clone(), actor, reason);
+        otx.status = status.clone();
+        if !matches!(
+            status,
+            OpenTxStatus::InBlock(_) | OpenTxStatus::Confirmed(_)
+        ) {
+            otx.confirmations = 0;
+        }
+        if let Err(err) = self.store.put_otx(id, otx.value()) {
+            log::error!("persist otx {:#x} status error: {:?}", id, err);
+        }
+        self.record_status_transition(id, transition);
+        if was_pending && !is_pending {
+            self.unindex_inputs(id, &otx.otx);
+            self.eviction_queue.remove(id);
+            self.claims.remove(id);
+        } else if !was_pending && is_pending {
+            self.index_inputs(id, &otx.otx);
+            self.enqueue_eviction(id, otx.value());
+        }
+        self.event_log.append(
+            PoolEvent::OtxStatusChanged((id.to_owned(), status.clone())),
+            |seq| {
+                self.notify_ctrl
+                    .notify::<OtxStatusChanged>((seq, id.to_owned(), status))
+            },
+        );
+        Ok(())
+    }
+
+    fn publish_new_otx(&self, otx: OpenTransaction) {
+        self.event_log
+            .append(PoolEvent::NewOtx(otx.clone()), |seq| {
+                self.notify_ctrl.notify::<NewOtx>((seq, otx))
+            });
+    }
+
+    fn record_status_transition(&self, id: &H256, transition: StatusTransition) {
+        log::debug!(
+            "otx {:#x} status {:?} -> {:?} by {}",
+            id,
+            transition.from,
+            transition.to,
+            transition.actor
+        );
+        if let Err(err) = self.store.append_status_transition(id, &transition) {
+            log::error!("persist otx {:#x} status transition error: {:?}", id, err);
+        }
+    }
+
+    fn index_inputs(&self, id: &H256, otx: &OpenTransaction) {
+        match otx.get_input_out_points() {
+            Ok(out_points) => out_points.into_iter().for_each(|out_point| {
+                self.input_index.insert(out_point, id.to_owned());
+            }),
+            Err(err) => log::warn!("index inputs of otx {:#x} error: {:?}", id, err),
+        }
+    }
+
+    fn unindex_inputs(&self, id: &H256, otx: &OpenTransaction) {
+        if let Ok(out_points) = otx.get_input_out_points() {
+            out_points.iter().for_each(|out_point| {
+                self.input_index
+                    .remove_if(out_point, |_, indexed_id| indexed_id == id);
+            })
+        }
+    }
+
+    fn enqueue_eviction(&self, id: &H256, otx: &OpenTxWithStatus) {
+        self.eviction_queue
+            .insert(id, otx.received_at, otx.otx.get_max_fee());
+    }
+
+    fn make_room_for(&self, otx: &OpenTransaction) -> InnerResult<()> {
+        let max_pool_size = self.config.get_max_pool_size();
+        if max_pool_size == 0 {
+            return Ok(());
+        }
+        let evicted_otxs = self.eviction_queue.select_evicted(max_pool_size);
+        if evicted_otxs.is_empty() {
+            return Ok(());
+        }
+        if self.config.get_eviction_policy() == EvictionPolicy::LowestFeeFirst
+            && evicted_otxs
+                .iter()
+                .any(|(_, fee)| *fee >= otx.get_max_fee())
+        {
+            return Err(OtxPoolError::PoolIsFull.into());
+        }
+
+        let evicted_otxs = evicted_otxs
+            .into_iter()
+            .map(|(id, _)| (id, "evicted because the pool is full".to_owned()))
+            .collect();
+        self.reject(evicted_otxs, POOL_ACTOR);
+        Ok(())
+    }
+
+    fn prune_rejected_otxs(&self) {
+        let mut rejected_otxs = self.rejected_otxs.lock().expect("lock rejected otxs");
+        while rejected_otxs.len() > self.config.get_max_rejected_size() {
+            if let Some(id) = rejected_otxs.pop_front() {
+                if let Some((_, otx)) = self.raw_otxs.remove(&id) {
+                    self.indexes.remove(&id, &otx);
+                }
+                self.eviction_queue.remove(&id);
+                if let Err(err) = self.store.delete_otx(&id) {
+                    log::error!("delete otx {:#x} error: {:?}", id, err);
+                }
+                if let Err(err) = self.store.delete_status_history(&id) {
+                    log::error!("delete status history of otx {:#x} error: {:?}", id, err);
+                }
+            }
+        }
+    }
+}
+
+fn encode_cursor((received_at, id): &(u64, H256)) -> JsonBytes {
+    let mut cursor = received_at.to_be_bytes().to_vec();
+    cursor.extend_from_slice(id.as_bytes());
+    JsonBytes::from_vec(cursor)
+}
+
+fn decode_cursor(cursor: &JsonBytes) -> InnerResult<(u64, H256)> {
+    let cursor = cursor.as_bytes();
+    if cursor.len() != 40 {
+        return Err(OtxPoolError::InvalidParams("invalid cursor".to_owned()).into());
+    }
+    let mut received_at = [0u8; 8];
+    received_at.copy_from_slice(&cursor[0..8]);
+    let id = H256::from_slice(&cursor[8..])
+        .map_err(|_| OtxPoolError::InvalidParams("invalid cursor".to_owned()))?;
+    Ok((u64::from_be_bytes(received_at), id))
+}
+
+fn otx_not_found(id: &H256) -> OtxPoolError {
+    OtxPoolError::InvalidParams(format!("otx {:#x} not found", id))
+}
+
+fn invalid_transition(id: &H256, from: &OpenTxStatus, to: &OpenTxStatus) -> OtxPoolError {
+    OtxPoolError::InvalidStatusTransition(format!("otx {:#x} from {:?} to {:?}", id, from, to))
 }
 
 fn _parse_otx(otx: JsonBytes) -> InnerResult<OpenTransaction> {
     let r = packed::OpenTransaction::from_slice(otx.as_bytes());
     r.map(Into::into).map_err(Into::into)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::MemoryStore;
+
+    use otx_format::constant::extra_keys::OTX_ACCOUNTING_META_MAX_FEE;
+    use otx_format::error::OtxError;
+    use otx_format::jsonrpc_types::{OtxKeyPair, OtxMap};
+    use otx_format::types::FinalTxInfo;
+    use otx_pool_config::NotifyConfig;
+
+    use ckb_async_runtime::new_background_runtime;
+    use ckb_jsonrpc_types::CellInput;
+    use ckb_types::prelude::Pack;
+
+    const ACTOR: &str = "test";
+
+    // the otxs are reloaded from the store with the given receiving time
+    fn new_pool(config: PoolConfig, otxs: Vec<(OpenTransaction, u64)>) -> OtxPool {
+        let store: Arc<dyn OtxStore> = Arc::new(MemoryStore::default());
+        for (mut otx, received_at) in otxs {
+            let id = otx.get_or_insert_otx_id().unwrap();
+            let mut otx_with_status = OpenTxWithStatus::new(otx);
+            otx_with_status.received_at = received_at;
+            store.put_otx(&id, &otx_with_status).unwrap();
+        }
+        let event_log = EventLog::open(store.clone(), 0).unwrap();
+        let notify_ctrl = NotifyController::new(new_background_runtime(), NotifyConfig::default());
+        OtxPool::new(notify_ctrl, store, event_log, config).unwrap()
+    }
+
+    // an otx spending the first output of every tx, the inputs make the otx id
+    fn build_otx(inputs: &[u8], fee: u64) -> OpenTransaction {
+        let mut otx = OpenTransaction::default();
+        otx.meta.push(OtxKeyPair::new(
+            OTX_ACCOUNTING_META_MAX_FEE.into(),
+            None,
+            JsonBytes::from_bytes(fee.pack().as_bytes()),
+        ));
+        otx.inputs = inputs
+            .iter()
+            .map(|n| {
+                OtxMap::from(CellInput {
+                    previous_output: build_out_point(*n),
+                    since: 0u64.into(),
+                })
+            })
+            .collect::<Vec<OtxMap>>()
+            .into();
+        otx
+    }
+
+    fn build_out_point(n: u8) -> OutPoint {
+        OutPoint {
+            tx_hash: H256([n; 32]),
+            index: 0u32.into(),
+        }
+    }
+
+    fn otx_id(otx: &OpenTransaction) -> H256 {
+        otx.get_tx_hash().unwrap()
+    }
+
+    fn get_status(pool: &OtxPool, id: &H256) -> OpenTxStatus {
+        pool.get_otx_by_id(id.to_owned()).unwrap().status
+    }
+
+    fn is_rejected(pool: &OtxPool, id: &H256) -> bool {
+        matches!(get_status(pool, id), OpenTxStatus::Rejected(_))
+    }
+
+    fn assert_error<T: std::fmt::Debug>(result: InnerResult<T>, expected: OtxPoolError) {
+        assert_eq!(result.unwrap_err().0.message(), expected.message());
+    }
+
+    #[test]
+    fn test_evict_oldest_first() {
+        let (old, new) = (build_otx(&[1], 300), build_otx(&[2], 100));
+        let pool = new_pool(
+            PoolConfig::new(2, 0, EvictionPolicy::OldestFirst, 10, false),
+            vec![(old.clone(), 10), (new.clone(), 20)],
+        );
+
+        let id = pool.insert(build_otx(&[3], 50), ACTOR).unwrap();
+        assert!(is_rejected(&pool, &otx_id(&old)));
+        assert_eq!(get_status(&pool, &otx_id(&new)), OpenTxStatus::Pending);
+        assert_eq!(get_status(&pool, &id), OpenTxStatus::Pending);
+        assert_eq!(pool.get_stats().pending_count, 2);
+    }
+
+    #[test]
+    fn test_evict_lowest_fee_first() {
+        let (high_fee, low_fee) = (build_otx(&[1], 300), build_otx(&[2], 100));
+        let pool = new_pool(
+            PoolConfig::new(2, 0, EvictionPolicy::LowestFeeFirst, 10, false),
+            vec![(high_fee.clone(), 10), (low_fee.clone(), 20)],
+        );
+
+        // an otx not paying more than the lowest fee is refused
+        assert_error(
+            pool.insert(build_otx(&[3], 100), ACTOR),
+            OtxPoolError::PoolIsFull,
+        );
+        assert_eq!(pool.get_stats().pending_count, 2);
+
+        let id = pool.insert(build_otx(&[3], 200), ACTOR).unwrap();
+        assert!(is_rejected(&pool, &otx_id(&low_fee)));
+        assert_eq!(get_status(&pool, &otx_id(&high_fee)), OpenTxStatus::Pending);
+        assert_eq!(get_status(&pool, &id), OpenTxStatus::Pending);
+    }
+
+    #[test]
+    fn test_no_eviction_below_max_size() {
+        let pool = new_pool(
+            PoolConfig::new(3, 0, EvictionPolicy::OldestFirst, 10, false),
+            vec![(build_otx(&[1], 100), 10)],
+        );
+        pool.insert(build_otx(&[2], 100), ACTOR).unwrap();
+        pool.insert(build_otx(&[3], 100), ACTOR).unwrap();
+        assert_eq!(pool.get_stats().pending_count, 3);
+        assert_eq!(pool.get_stats().rejected_count, 0);
+    }
+
+    #[test]
+    fn test_resubmit() {
+        let pool = new_pool(PoolConfig::default(), vec![]);
+        let id = pool.insert(build_otx(&[1], 100), ACTOR).unwrap();
+        assert_eq!(pool.insert(build_otx(&[1], 100), ACTOR).unwrap(), id);
+
+        pool.reject_otxs(vec![(id.clone(), "test".to_owned())], ACTOR);
+        assert_error(
+            pool.insert(build_otx(&[1], 100), ACTOR),
+            OtxPoolError::OtxAlreadyExists,
+        );
+        assert!(is_rejected(&pool, &id));
+    }
+
+    #[test]
+    fn test_input_conflict() {
+        let pool = new_pool(PoolConfig::default(), vec![]);
+        let id = pool.insert(build_otx(&[1], 100), ACTOR).unwrap();
+        assert_error(
+            pool.insert(build_otx(&[1, 2], 200), ACTOR),
+            OtxPoolError::InputConflict(id.clone()),
+        );
+        assert_eq!(get_status(&pool, &id), OpenTxStatus::Pending);
+    }
+
+    #[test]
+    fn test_replace_by_fee() {
+        let pool = new_pool(
+            PoolConfig::new(0, 0, EvictionPolicy::OldestFirst, 10, true),
+            vec![],
+        );
+        let id = pool.insert(build_otx(&[1], 100), ACTOR).unwrap();
+
+        // the replacement must pay a higher fee
+        assert_error(
+            pool.insert(build_otx(&[1, 2], 100), ACTOR),
+            OtxPoolError::ReplacementFeeTooLow(id.clone()),
+        );
+        assert_eq!(get_status(&pool, &id), OpenTxStatus::Pending);
+
+        let new_id = pool.insert(build_otx(&[1, 2], 200), ACTOR).unwrap();
+        assert!(is_rejected(&pool, &id));
+        assert_eq!(get_status(&pool, &new_id), OpenTxStatus::Pending);
+        assert_eq!(
+            pool.get_pending_otx_by_input(&build_out_point(1)),
+            Some(new_id)
+        );
+    }
+
+    #[test]
+    fn test_merge_otxs() {
+        let pool = new_pool(PoolConfig::default(), vec![]);
+        let first = pool.insert(build_otx(&[1], 100), ACTOR).unwrap();
+        let second = pool.insert(build_otx(&[2], 100), ACTOR).unwrap();
+
+        let merged_id = pool
+            .merge_otxs(
+                build_otx(&[1, 2], 200),
+                &[first.clone(), second.clone()],
+                ACTOR,
+            )
+            .unwrap();
+        assert_eq!(
+            get_status(&pool, &first),
+            OpenTxStatus::Merged(merged_id.clone())
+        );
+        assert_eq!(
+            get_status(&pool, &second),
+            OpenTxStatus::Merged(merged_id.clone())
+        );
+        assert_eq!(get_status(&pool, &merged_id), OpenTxStatus::Pending);
+        assert_eq!(
+            pool.get_pending_otx_by_input(&build_out_point(1)),
+            Some(merged_id)
+        );
+    }
+
+    #[test]
+    fn test_merge_otxs_refused() {
+        let pool = new_pool(PoolConfig::default(), vec![]);
+        let included = pool.insert(build_otx(&[1], 100), ACTOR).unwrap();
+        let other = pool.insert(build_otx(&[2], 100), ACTOR).unwrap();
+
+        // the merged otx spends the input of a pending otx not included
+        let merged_otx = build_otx(&[1, 2], 200);
+        assert_error(
+            pool.merge_otxs(merged_otx.clone(), &[included.clone()], ACTOR),
+            OtxPoolError::InputConflict(other),
+        );
+        assert!(pool.get_otx_by_id(otx_id(&merged_otx)).is_none());
+        assert_eq!(get_status(&pool, &included), OpenTxStatus::Pending);
+        assert_eq!(
+            pool.get_pending_otx_by_input(&build_out_point(1)),
+            Some(included.clone())
+        );
+
+        // an otx included is no longer pending
+        pool.discard_otx(&included, "test".to_owned(), ACTOR)
+            .unwrap();
+        assert!(pool
+            .merge_otxs(build_otx(&[1, 3], 200), &[included], ACTOR)
+            .is_err());
+        assert!(pool
+            .get_otx_by_id(otx_id(&build_otx(&[1, 3], 200)))
+            .is_none());
+    }
+
+    #[test]
+    fn test_sent_tx_confirmations() {
+        let pool = new_pool(PoolConfig::default(), vec![]);
+        let id = pool.insert(build_otx(&[1], 100), ACTOR).unwrap();
+        let tx_hash = H256([9; 32]);
+        pool.update_otxs_status(
+            &[id.clone()],
+            OpenTxStatus::Committed(tx_hash.clone()),
+            ACTOR,
+            None,
+        )
+        .unwrap();
+        pool.commit_otxs(tx_hash.clone(), vec![id.clone()]);
+
+        let info = FinalTxInfo {
+            tx_hash: tx_hash.clone(),
+            block_number: 10,
+            block_hash: H256([10; 32]),
+        };
+        for confirmations in 1..=3 {
+            pool.update_sent_tx_status(
+                &tx_hash,
+                OpenTxStatus::InBlock(info.clone()),
+                confirmations,
+                ACTOR,
+            );
+        }
+        let otx = pool.get_otx_by_id(id.clone()).unwrap();
+        assert_eq!(otx.status, OpenTxStatus::InBlock(info.clone()));
+        assert_eq!(otx.confirmations, 3);
+        // admitted, committed and in block, the new confirmations are not transitions
+        assert_eq!(pool.get_status_history(&id).unwrap().len(), 3);
+
+        pool.update_sent_tx_status(&tx_hash, OpenTxStatus::Confirmed(info.clone()), 4, ACTOR);
+        let otx = pool.get_otx_by_id(id.clone()).unwrap();
+        assert_eq!(otx.status, OpenTxStatus::Confirmed(info));
+        assert_eq!(otx.confirmations, 4);
+        assert_eq!(pool.get_status_history(&id).unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_claim_otxs() {
+        let pool = new_pool(PoolConfig::default(), vec![]);
+        let first = pool.insert(build_otx(&[1], 100), ACTOR).unwrap();
+        let second = pool.insert(build_otx(&[2], 100), ACTOR).unwrap();
+
+        let expires_at = pool
+            .claim_otxs(&[first.clone(), second.clone()], "owner", 60)
+            .unwrap();
+        assert!(expires_at >= unix_timestamp() + 59);
+        // the owner extends the lease and consumes the otxs
+        pool.claim_otxs(&[first.clone()], "owner", 120).unwrap();
+        pool.discard_otx(&first, "test".to_owned(), "owner")
+            .unwrap();
+        assert!(is_rejected(&pool, &first));
+
+        // the claim is released once the otx is no longer pending
+        assert!(pool.claim_otxs(&[first], "other", 60).is_err());
+        pool.release_otxs(&[second.clone()], "owner");
+        pool.claim_otxs(&[second], "other", 60).unwrap();
+
+        assert!(pool.claim_otxs(&[H256([9; 32])], "owner", 60).is_err());
+        assert!(pool.claim_otxs(&[], "owner", 60).is_err());
+    }
+
+    #[test]
+    fn test_conflicting_claims() {
+        let pool = new_pool(PoolConfig::default(), vec![]);
+        let first = pool.insert(build_otx(&[1], 100), ACTOR).unwrap();
+        let second = pool.insert(build_otx(&[2], 100), ACTOR).unwrap();
+        pool.claim_otxs(&[first.clone()], "owner", 60).unwrap();
+
+        // either all the otxs are claimed or none
+        assert_error(
+            pool.claim_otxs(&[second.clone(), first.clone()], "other", 60),
+            OtxPoolError::OtxClaimed(first.clone()),
+        );
+        pool.claim_otxs(&[second.clone()], "owner", 60).unwrap();
+
+        // the other plugins can not consume the claimed otxs
+        assert_error(
+            pool.merge_otxs(build_otx(&[1, 2], 200), &[first.clone()], "other"),
+            OtxPoolError::OtxClaimed(first.clone()),
+        );
+        assert_error(
+            pool.discard_otx(&second, "test".to_owned(), "other"),
+            OtxPoolError::OtxClaimed(second.clone()),
+        );
+        assert_error(
+            pool.update_otxs_status(
+                &[first.clone()],
+                OpenTxStatus::Committed(H256([9; 32])),
+                "other",
+                None,
+            ),
+            OtxPoolError::OtxClaimed(first.clone()),
+        );
+        assert_eq!(get_status(&pool, &first), OpenTxStatus::Pending);
+        assert_eq!(get_status(&pool, &second), OpenTxStatus::Pending);
+
+        // releasing the otxs of another plugin changes nothing
+        pool.release_otxs(&[first.clone()], "other");
+        assert!(pool.claim_otxs(&[first], "other", 60).is_err());
+    }
+
+    #[test]
+    fn test_claim_lease_expiry() {
+        let pool = new_pool(PoolConfig::default(), vec![]);
+        let id = pool.insert(build_otx(&[1], 100), ACTOR).unwrap();
+        assert!(pool.claim_otxs(&[id.clone()], "owner", 0).is_err());
+        assert!(pool
+            .claim_otxs(&[id.clone()], "owner", MAX_CLAIM_LEASE_SECS + 1)
+            .is_err());
+
+        pool.claim_otxs(&[id.clone()], "owner", 1).unwrap();
+        assert!(pool.claim_otxs(&[id.clone()], "other", 60).is_err());
+        std::thread::sleep(std::time::Duration::from_secs(2));
+        pool.claim_otxs(&[id.clone()], "other", 60).unwrap();
+        pool.discard_otx(&id, "test".to_owned(), "other").unwrap();
+        assert!(is_rejected(&pool, &id));
+    }
+}
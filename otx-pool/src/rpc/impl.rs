@@ -1,16 +1,114 @@
-use super::{OtxPoolRpc, OtxPoolRpcImpl};
+use super::{AdminRpc, AdminRpcImpl, OtxPoolRpc, OtxPoolRpcImpl, PluginRpc, PluginRpcImpl};
+use crate::error::{OtxPoolError, OtxRpcError};
+use crate::plugin_extension::manager::PluginManager;
 
-use otx_format::{jsonrpc_types::OpenTransaction, types::OpenTxStatus};
+use otx_format::{
+    jsonrpc_types::OpenTransaction,
+    types::{
+        EventPage, OpenTxDetail, OpenTxStatus, OpenTxWithId, Order, OtxFilter, Pagination,
+        StatusTransition,
+    },
+};
 
+use ckb_jsonrpc_types::{JsonBytes, Uint32, Uint64};
 use ckb_types::H256;
-use jsonrpc_core::Result as RpcResult;
+use jsonrpc_core::{Error, Result as RpcResult};
+
+use otx_pool_plugin_protocol::{PluginDetail, PluginHealth, PluginInfo};
+use std::path::Path;
+
+const ACTOR: &str = "rpc";
+const MAX_IDS_SIZE: usize = 1000;
 
 impl OtxPoolRpc for OtxPoolRpcImpl {
     fn submit_otx(&self, otx: OpenTransaction) -> RpcResult<H256> {
-        self.otx_pool.insert(otx).map_err(Into::into)
+        self.validator_chain.validate(&otx)?;
+        self.otx_pool.insert(otx, ACTOR).map_err(Into::into)
     }
 
     fn query_otx_status_by_id(&self, id: H256) -> RpcResult<Option<OpenTxStatus>> {
         Ok(self.otx_pool.get_otx_by_id(id).map(|otx| otx.status))
     }
+
+    fn get_otx_by_id(&self, id: H256) -> RpcResult<Option<OpenTxDetail>> {
+        Ok(self.otx_pool.get_otx_detail(&id))
+    }
+
+    fn get_otxs_by_ids(&self, ids: Vec<H256>) -> RpcResult<Vec<Option<OpenTxDetail>>> {
+        if ids.len() > MAX_IDS_SIZE {
+            let err = OtxPoolError::InvalidParams(format!(
+                "the number of ids should be no more than {}",
+                MAX_IDS_SIZE
+            ));
+            return Err(OtxRpcError::from(err).into());
+        }
+        Ok(ids
+            .iter()
+            .map(|id| self.otx_pool.get_otx_detail(id))
+            .collect())
+    }
+
+    fn get_otx_status_history(&self, id: H256) -> RpcResult<Vec<StatusTransition>> {
+        self.otx_pool.get_status_history(&id).map_err(Into::into)
+    }
+
+    fn get_otxs(
+        &self,
+        filter: OtxFilter,
+        order: Option<Order>,
+        limit: Uint32,
+        after: Option<JsonBytes>,
+    ) -> RpcResult<Pagination<OpenTxWithId>> {
+        self.otx_pool
+            .get_otxs(
+                &filter,
+                order.unwrap_or_default(),
+                limit.value() as usize,
+                after,
+            )
+            .map_err(Into::into)
+    }
+
+    fn get_events(&self, from_seq: Uint64, limit: Uint32) -> RpcResult<EventPage> {
+        self.otx_pool
+            .get_events(from_seq.value(), limit.value() as usize)
+            .map_err(Into::into)
+    }
+}
+
+impl PluginRpc for PluginRpcImpl {
+    fn get_plugins_health(&self) -> RpcResult<Vec<PluginHealth>> {
+        Ok(self
+            .plugin_manager
+            .read()
+            .expect("read plugin manager")
+            .get_plugins_health())
+    }
+}
+
+impl AdminRpc for AdminRpcImpl {
+    fn list_plugins(&self) -> RpcResult<Vec<PluginDetail>> {
+        Ok(self
+            .plugin_manager
+            .read()
+            .expect("read plugin manager")
+            .list_plugins())
+    }
+
+    fn install_plugin(&self, binary_path: String, activate: bool) -> RpcResult<PluginInfo> {
+        PluginManager::install_plugin(&self.plugin_manager, Path::new(&binary_path), activate)
+            .map_err(plugin_error)
+    }
+
+    fn activate_plugin(&self, plugin_name: String) -> RpcResult<()> {
+        PluginManager::activate_plugin(&self.plugin_manager, &plugin_name).map_err(plugin_error)
+    }
+
+    fn deactivate_plugin(&self, plugin_name: String) -> RpcResult<()> {
+        PluginManager::deactivate_plugin(&self.plugin_manager, &plugin_name).map_err(plugin_error)
+    }
+}
+
+fn plugin_error(err: String) -> Error {
+    OtxRpcError::from(OtxPoolError::PluginError(err)).into()
 }
@@ -1,14 +1,27 @@
 mod r#impl;
+mod router;
 
+pub use router::PluginRpcRouter;
+
+use super::plugin_extension::manager::PluginManager;
 use super::pool::OtxPool;
+use super::validator::OtxValidatorChain;
 
-use otx_format::{jsonrpc_types::OpenTransaction, types::OpenTxStatus};
+use otx_format::{
+    jsonrpc_types::OpenTransaction,
+    types::{
+        EventPage, OpenTxDetail, OpenTxStatus, OpenTxWithId, Order, OtxFilter, Pagination,
+        StatusTransition,
+    },
+};
 
+use ckb_jsonrpc_types::{JsonBytes, Uint32, Uint64};
 use ckb_types::H256;
 use jsonrpc_core::Result as RpcResult;
 use jsonrpc_derive::rpc;
+use otx_pool_plugin_protocol::{PluginDetail, PluginHealth, PluginInfo};
 
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 
 #[rpc(server)]
 pub trait OtxPoolRpc {
@@ -17,14 +30,81 @@ pub trait OtxPoolRpc {
 
     #[rpc(name = "query_otx_status_by_id")]
     fn query_otx_status_by_id(&self, id: H256) -> RpcResult<Option<OpenTxStatus>>;
+
+    #[rpc(name = "get_otx_by_id")]
+    fn get_otx_by_id(&self, id: H256) -> RpcResult<Option<OpenTxDetail>>;
+
+    #[rpc(name = "get_otxs_by_ids")]
+    fn get_otxs_by_ids(&self, ids: Vec<H256>) -> RpcResult<Vec<Option<OpenTxDetail>>>;
+
+    #[rpc(name = "get_otx_status_history")]
+    fn get_otx_status_history(&self, id: H256) -> RpcResult<Vec<StatusTransition>>;
+
+    #[rpc(name = "get_otxs")]
+    fn get_otxs(
+        &self,
+        filter: OtxFilter,
+        order: Option<Order>,
+        limit: Uint32,
+        after: Option<JsonBytes>,
+    ) -> RpcResult<Pagination<OpenTxWithId>>;
+
+    #[rpc(name = "get_events")]
+    fn get_events(&self, from_seq: Uint64, limit: Uint32) -> RpcResult<EventPage>;
 }
 
 pub struct OtxPoolRpcImpl {
     otx_pool: Arc<OtxPool>,
+    validator_chain: Arc<OtxValidatorChain>,
 }
 
 impl OtxPoolRpcImpl {
-    pub fn new(otx_pool: Arc<OtxPool>) -> Self {
-        OtxPoolRpcImpl { otx_pool }
+    pub fn new(otx_pool: Arc<OtxPool>, validator_chain: Arc<OtxValidatorChain>) -> Self {
+        OtxPoolRpcImpl {
+            otx_pool,
+            validator_chain,
+        }
+    }
+}
+
+#[rpc(server)]
+pub trait PluginRpc {
+    #[rpc(name = "get_plugins_health")]
+    fn get_plugins_health(&self) -> RpcResult<Vec<PluginHealth>>;
+}
+
+pub struct PluginRpcImpl {
+    plugin_manager: Arc<RwLock<PluginManager>>,
+}
+
+impl PluginRpcImpl {
+    pub fn new(plugin_manager: Arc<RwLock<PluginManager>>) -> Self {
+        PluginRpcImpl { plugin_manager }
+    }
+}
+
+/// The rpc methods to manage plugins at runtime, only exposed when enabled in the network config.
+#[rpc(server)]
+pub trait AdminRpc {
+    #[rpc(name = "admin_list_plugins")]
+    fn list_plugins(&self) -> RpcResult<Vec<PluginDetail>>;
+
+    #[rpc(name = "admin_install_plugin")]
+    fn install_plugin(&self, binary_path: String, activate: bool) -> RpcResult<PluginInfo>;
+
+    #[rpc(name = "admin_activate_plugin")]
+    fn activate_plugin(&self, plugin_name: String) -> RpcResult<()>;
+
+    #[rpc(name = "admin_deactivate_plugin")]
+    fn deactivate_plugin(&self, plugin_name: String) -> RpcResult<()>;
+}
+
+pub struct AdminRpcImpl {
+    plugin_manager: Arc<RwLock<PluginManager>>,
+}
+
+impl AdminRpcImpl {
+    pub fn new(plugin_manager: Arc<RwLock<PluginManager>>) -> Self {
+        AdminRpcImpl { plugin_manager }
     }
 }
@@ -0,0 +1,69 @@
+use crate::error::{OtxPoolError, OtxRpcError};
+use crate::plugin_extension::manager::PluginManager;
+
+use jsonrpc_core::futures::future::Either;
+use jsonrpc_core::futures::Future;
+use jsonrpc_core::{BoxFuture, Call, Error, Middleware, Output, Params, Response};
+use serde_json::Value;
+use tokio::sync::oneshot;
+
+use std::sync::{Arc, RwLock};
+use std::thread;
+
+/// Routes the calls of the rpc methods named `<namespace>_*` to the external plugin
+/// which declared the namespace in its handshake, the other calls go to the pool.
+pub struct PluginRpcRouter {
+    plugin_manager: Arc<RwLock<PluginManager>>,
+}
+
+impl PluginRpcRouter {
+    pub fn new(plugin_manager: Arc<RwLock<PluginManager>>) -> Self {
+        PluginRpcRouter { plugin_manager }
+    }
+}
+
+impl Middleware<()> for PluginRpcRouter {
+    type Future = BoxFuture<Option<Response>>;
+    type CallFuture = BoxFuture<Option<Output>>;
+
+    fn on_call<F, X>(&self, call: Call, meta: (), next: F) -> Either<Self::CallFuture, X>
+    where
+        F: Fn(Call, ()) -> X + Send + Sync,
+        X: Future<Output = Option<Output>> + Send + 'static,
+    {
+        let method_call = match call {
+            Call::MethodCall(method_call) => method_call,
+            call => return Either::Right(next(call, meta)),
+        };
+        let plugin = self
+            .plugin_manager
+            .read()
+            .expect("read plugin manager")
+            .get_rpc_plugin(&method_call.method);
+        let plugin = match plugin {
+            Some(plugin) => plugin,
+            None => return Either::Right(next(Call::MethodCall(method_call), meta)),
+        };
+
+        let params = match method_call.params {
+            Params::None => Value::Null,
+            Params::Array(params) => Value::Array(params),
+            Params::Map(params) => Value::Object(params),
+        };
+        // the plugin may take up to the rpc timeout to answer, so the call runs
+        // on its own thread instead of blocking the rpc server
+        let (result_sender, result_receiver) = oneshot::channel();
+        let method = method_call.method;
+        thread::spawn(move || {
+            let _ = result_sender.send(plugin.call_rpc(&method, params));
+        });
+        let (id, jsonrpc) = (method_call.id, method_call.jsonrpc);
+        Either::Left(Box::pin(async move {
+            let result = result_receiver
+                .await
+                .unwrap_or_else(|_| Err("the rpc call is dropped".to_owned()))
+                .map_err(|err| Error::from(OtxRpcError::from(OtxPoolError::PluginError(err))));
+            Some(Output::from(result, id, jsonrpc))
+        }))
+    }
+}
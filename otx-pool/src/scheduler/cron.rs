@@ -0,0 +1,191 @@
+const SECS_PER_MINUTE: u64 = 60;
+const SECS_PER_HOUR: u64 = 3600;
+const SECS_PER_DAY: u64 = 86400;
+// a schedule not due within the period never is, e.g. `0 0 30 2 *`
+const SEARCH_LIMIT_SECS: u64 = 5 * 366 * SECS_PER_DAY;
+
+/// A parsed cron expression `minute hour day month weekday` in UTC.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    // like cron, when both day and weekday are restricted, either of them matches
+    day_restricted: bool,
+    weekday_restricted: bool,
+}
+
+impl CronSchedule {
+    pub fn parse(expr: &str) -> Result<CronSchedule, String> {
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(format!(
+                "invalid cron expression {:?}, expect 5 fields: minute hour day month weekday",
+                expr
+            ));
+        }
+        let mut weekdays = parse_field(fields[4], 0, 7)?;
+        // both 0 and 7 are Sunday
+        if weekdays & (1 << 7) != 0 {
+            weekdays = (weekdays & !(1 << 7)) | 1;
+        }
+        Ok(CronSchedule {
+            minutes: parse_field(fields[0], 0, 59)?,
+            hours: parse_field(fields[1], 0, 23)?,
+            days: parse_field(fields[2], 1, 31)?,
+            months: parse_field(fields[3], 1, 12)?,
+            weekdays,
+            day_restricted: !fields[2].starts_with('*'),
+            weekday_restricted: !fields[4].starts_with('*'),
+        })
+    }
+
+    /// The first matching minute after the timestamp, in seconds.
+    pub fn next_after(&self, timestamp: u64) -> Option<u64> {
+        let limit = timestamp + SEARCH_LIMIT_SECS;
+        let mut time = (timestamp / SECS_PER_MINUTE + 1) * SECS_PER_MINUTE;
+        while time < limit {
+            let days = time / SECS_PER_DAY;
+            let (month, day) = month_and_day(days);
+            if !self.matches_day(month, day, (days + 4) % 7) {
+                time = (days + 1) * SECS_PER_DAY;
+                continue;
+            }
+            if !contains(self.hours, time % SECS_PER_DAY / SECS_PER_HOUR) {
+                time = (time / SECS_PER_HOUR + 1) * SECS_PER_HOUR;
+                continue;
+            }
+            if !contains(self.minutes, time % SECS_PER_HOUR / SECS_PER_MINUTE) {
+                time += SECS_PER_MINUTE;
+                continue;
+            }
+            return Some(time);
+        }
+        None
+    }
+
+    fn matches_day(&self, month: u64, day: u64, weekday: u64) -> bool {
+        if !contains(self.months, month) {
+            return false;
+        }
+        let day_matched = contains(self.days, day);
+        let weekday_matched = contains(self.weekdays, weekday);
+        if self.day_restricted && self.weekday_restricted {
+            day_matched || weekday_matched
+        } else {
+            day_matched && weekday_matched
+        }
+    }
+}
+
+fn contains(set: u64, value: u64) -> bool {
+    set & (1 << value) != 0
+}
+
+fn parse_field(field: &str, min: u64, max: u64) -> Result<u64, String> {
+    let parse_value = |value: &str| {
+        value
+            .parse::<u64>()
+            .map_err(|_| format!("invalid cron field {:?}", field))
+    };
+    let mut set = 0;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, parse_value(step)?),
+            None => (part, 1),
+        };
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (parse_value(start)?, parse_value(end)?)
+        } else {
+            let start = parse_value(range)?;
+            // `a/n` means from a to the max every n
+            (start, if part.contains('/') { max } else { start })
+        };
+        if step == 0 || start < min || end > max || start > end {
+            return Err(format!(
+                "invalid cron field {:?}, the values are in {}-{}",
+                field, min, max
+            ));
+        }
+        for value in (start..=end).step_by(step as usize) {
+            set |= 1 << value;
+        }
+    }
+    Ok(set)
+}
+
+// http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn month_and_day(days_since_epoch: u64) -> (u64, u64) {
+    let z = days_since_epoch + 719468;
+    let day_of_era = z % 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    (month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 2023-03-01 00:00:00 UTC, a Wednesday
+    const MARCH_1_2023: u64 = 1677628800;
+
+    #[test]
+    fn test_parse() {
+        assert!(CronSchedule::parse("* * * * *").is_ok());
+        assert!(CronSchedule::parse("*/15 0-6,22 1 */2 1-5").is_ok());
+        assert!(CronSchedule::parse("* * * *").is_err());
+        assert!(CronSchedule::parse("60 * * * *").is_err());
+        assert!(CronSchedule::parse("*/0 * * * *").is_err());
+        assert!(CronSchedule::parse("5-1 * * * *").is_err());
+        assert!(CronSchedule::parse("a * * * *").is_err());
+    }
+
+    #[test]
+    fn test_next_after() {
+        let every_minute = CronSchedule::parse("* * * * *").unwrap();
+        assert_eq!(
+            every_minute.next_after(MARCH_1_2023),
+            Some(MARCH_1_2023 + 60)
+        );
+        assert_eq!(
+            every_minute.next_after(MARCH_1_2023 + 59),
+            Some(MARCH_1_2023 + 60)
+        );
+
+        let quarter_past = CronSchedule::parse("15 * * * *").unwrap();
+        assert_eq!(
+            quarter_past.next_after(MARCH_1_2023),
+            Some(MARCH_1_2023 + 15 * 60)
+        );
+
+        // the next Sunday is 2023-03-05
+        let sunday = CronSchedule::parse("0 12 * * 7").unwrap();
+        assert_eq!(
+            sunday.next_after(MARCH_1_2023),
+            Some(MARCH_1_2023 + 4 * SECS_PER_DAY + 12 * SECS_PER_HOUR)
+        );
+
+        // the first of a month or a Friday, whichever comes first
+        let first_or_friday = CronSchedule::parse("0 0 1 * 5").unwrap();
+        assert_eq!(
+            first_or_friday.next_after(MARCH_1_2023),
+            Some(MARCH_1_2023 + 2 * SECS_PER_DAY)
+        );
+
+        // 2024-02-29 is the next leap day
+        let leap_day = CronSchedule::parse("0 0 29 2 *").unwrap();
+        assert_eq!(leap_day.next_after(MARCH_1_2023), Some(1709164800));
+
+        let never = CronSchedule::parse("0 0 30 2 *").unwrap();
+        assert_eq!(never.next_after(MARCH_1_2023), None);
+    }
+}
@@ -0,0 +1,183 @@
+pub mod cron;
+
+use crate::notify::{NotifyController, Timer};
+use cron::CronSchedule;
+
+use otx_format::types::unix_timestamp;
+use otx_pool_config::SchedulerConfig;
+use otx_pool_plugin_protocol::{Job, Schedule};
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+enum Trigger {
+    Once,
+    Every(u64),
+    Cron(CronSchedule),
+}
+
+struct Entry {
+    trigger: Trigger,
+    // unix timestamp in seconds
+    next_at: u64,
+}
+
+/// The jobs the plugins schedule on the host.
+///
+/// The due jobs are published as `Timer` events with the wall-clock time, a one-shot job
+/// is removed once it is due, and a periodic one is rescheduled from the time it is due.
+pub struct Scheduler {
+    // the jobs by plugin name and job id
+    jobs: Mutex<HashMap<String, HashMap<String, Entry>>>,
+    config: SchedulerConfig,
+    notify_ctrl: NotifyController,
+}
+
+impl Scheduler {
+    pub fn new(config: SchedulerConfig, notify_ctrl: NotifyController) -> Self {
+        Scheduler {
+            jobs: Mutex::new(HashMap::new()),
+            config,
+            notify_ctrl,
+        }
+    }
+
+    /// Add the job of the plugin, or replace its job with the same id.
+    /// Returns the time the job is first due.
+    pub fn schedule(&self, plugin_name: &str, job: Job) -> Result<u64, String> {
+        let now = unix_timestamp();
+        let too_late = || "the job is due too late".to_owned();
+        let (trigger, next_at) = match job.schedule {
+            Schedule::Once(delay_secs) => (
+                Trigger::Once,
+                now.checked_add(delay_secs).ok_or_else(too_late)?,
+            ),
+            Schedule::Every(0) => return Err("the period of a job must be positive".to_owned()),
+            Schedule::Every(period_secs) => (
+                Trigger::Every(period_secs),
+                now.checked_add(period_secs).ok_or_else(too_late)?,
+            ),
+            Schedule::Cron(expr) => {
+                let cron = CronSchedule::parse(&expr)?;
+                let next_at = cron
+                    .next_after(now)
+                    .ok_or_else(|| format!("cron expression {:?} is never due", expr))?;
+                (Trigger::Cron(cron), next_at)
+            }
+        };
+        let mut jobs = self.jobs.lock().expect("lock jobs");
+        let plugin_jobs = jobs.entry(plugin_name.to_owned()).or_default();
+        if !plugin_jobs.contains_key(&job.id)
+            && plugin_jobs.len() >= self.config.get_max_jobs_per_plugin()
+        {
+            return Err(format!(
+                "plugin {} has reached the limit of {} jobs",
+                plugin_name,
+                self.config.get_max_jobs_per_plugin()
+            ));
+        }
+        plugin_jobs.insert(job.id, Entry { trigger, next_at });
+        Ok(next_at)
+    }
+
+    /// Returns false if the plugin has no job with the id.
+    pub fn cancel(&self, plugin_name: &str, job_id: &str) -> bool {
+        let mut jobs = self.jobs.lock().expect("lock jobs");
+        let removed = jobs
+            .get_mut(plugin_name)
+            .map_or(false, |plugin_jobs| plugin_jobs.remove(job_id).is_some());
+        if jobs.get(plugin_name).map_or(false, HashMap::is_empty) {
+            jobs.remove(plugin_name);
+        }
+        removed
+    }
+
+    /// Remove all the jobs of the plugin, e.g. once it is deactivated.
+    pub fn cancel_all(&self, plugin_name: &str) {
+        self.jobs.lock().expect("lock jobs").remove(plugin_name);
+    }
+
+    /// Publish the jobs due by now.
+    pub fn fire_due_jobs(&self) {
+        let now = unix_timestamp();
+        let mut due = vec![];
+        {
+            let mut jobs = self.jobs.lock().expect("lock jobs");
+            for (plugin_name, plugin_jobs) in jobs.iter_mut() {
+                plugin_jobs.retain(|job_id, entry| {
+                    if entry.next_at > now {
+                        return true;
+                    }
+                    due.push((plugin_name.to_owned(), job_id.to_owned(), now));
+                    let next_at = match &entry.trigger {
+                        Trigger::Once => None,
+                        // skip the periods missed, e.g. while the host was busy
+                        Trigger::Every(period_secs) => {
+                            now.checked_add(period_secs - (now - entry.next_at) % period_secs)
+                        }
+                        Trigger::Cron(cron) => cron.next_after(now),
+                    };
+                    match next_at {
+                        Some(next_at) => {
+                            entry.next_at = next_at;
+                            true
+                        }
+                        None => false,
+                    }
+                });
+            }
+            jobs.retain(|_, plugin_jobs| !plugin_jobs.is_empty());
+        }
+        due.into_iter()
+            .for_each(|timer| self.notify_ctrl.notify::<Timer>(timer));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use otx_pool_config::NotifyConfig;
+
+    use ckb_async_runtime::new_background_runtime;
+
+    fn new_scheduler() -> Scheduler {
+        let notify_ctrl = NotifyController::new(new_background_runtime(), NotifyConfig::default());
+        Scheduler::new(SchedulerConfig::default(), notify_ctrl)
+    }
+
+    #[test]
+    fn test_schedule_overflow() {
+        let scheduler = new_scheduler();
+        assert!(scheduler
+            .schedule("plugin", Job::new("once", Schedule::Once(u64::MAX)))
+            .is_err());
+        assert!(scheduler
+            .schedule("plugin", Job::new("every", Schedule::Every(u64::MAX)))
+            .is_err());
+        assert!(!scheduler.cancel("plugin", "once"));
+
+        let next_at = scheduler
+            .schedule("plugin", Job::new("once", Schedule::Once(60)))
+            .unwrap();
+        assert!(next_at >= unix_timestamp() + 59);
+    }
+
+    #[test]
+    fn test_cancel_all() {
+        let scheduler = new_scheduler();
+        for job_id in ["first", "second"] {
+            scheduler
+                .schedule("plugin", Job::new(job_id, Schedule::Every(60)))
+                .unwrap();
+        }
+        scheduler
+            .schedule("other", Job::new("first", Schedule::Every(60)))
+            .unwrap();
+
+        scheduler.cancel_all("plugin");
+        assert!(!scheduler.cancel("plugin", "first"));
+        assert!(!scheduler.cancel("plugin", "second"));
+        assert!(scheduler.cancel("other", "first"));
+    }
+}
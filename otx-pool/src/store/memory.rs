@@ -0,0 +1,145 @@
+use super::OtxStore;
+
+use otx_format::types::{EventRecord, OpenTxWithStatus, StatusTransition};
+
+use anyhow::Result;
+use ckb_types::H256;
+use dashmap::DashMap;
+
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+
+/// A store that lives as long as the process, used when no store path is configured.
+#[derive(Default)]
+pub struct MemoryStore {
+    otxs: DashMap<H256, OpenTxWithStatus>,
+    sent_txs: DashMap<H256, Vec<H256>>,
+    status_histories: DashMap<H256, Vec<StatusTransition>>,
+    events: RwLock<BTreeMap<u64, EventRecord>>,
+}
+
+impl OtxStore for MemoryStore {
+    fn put_otx(&self, id: &H256, otx: &OpenTxWithStatus) -> Result<()> {
+        self.otxs.insert(id.to_owned(), otx.to_owned());
+        Ok(())
+    }
+
+    fn delete_otx(&self, id: &H256) -> Result<()> {
+        self.otxs.remove(id);
+        Ok(())
+    }
+
+    fn get_otxs(&self) -> Result<Vec<(H256, OpenTxWithStatus)>> {
+        Ok(self
+            .otxs
+            .iter()
+            .map(|pair| (pair.key().to_owned(), pair.value().to_owned()))
+            .collect())
+    }
+
+    fn put_sent_tx(&self, tx_hash: &H256, otx_hashes: &[H256]) -> Result<()> {
+        self.sent_txs
+            .insert(tx_hash.to_owned(), otx_hashes.to_owned());
+        Ok(())
+    }
+
+    fn delete_sent_tx(&self, tx_hash: &H256) -> Result<()> {
+        self.sent_txs.remove(tx_hash);
+        Ok(())
+    }
+
+    fn get_sent_txs(&self) -> Result<Vec<(H256, Vec<H256>)>> {
+        Ok(self
+            .sent_txs
+            .iter()
+            .map(|pair| (pair.key().to_owned(), pair.value().to_owned()))
+            .collect())
+    }
+
+    fn append_status_transition(&self, id: &H256, transition: &StatusTransition) -> Result<()> {
+        self.status_histories
+            .entry(id.to_owned())
+            .or_default()
+            .push(transition.to_owned());
+        Ok(())
+    }
+
+    fn delete_status_history(&self, id: &H256) -> Result<()> {
+        self.status_histories.remove(id);
+        Ok(())
+    }
+
+    fn get_status_history(&self, id: &H256) -> Result<Vec<StatusTransition>> {
+        Ok(self
+            .status_histories
+            .get(id)
+            .map(|pair| pair.value().to_owned())
+            .unwrap_or_default())
+    }
+
+    fn append_event(&self, record: &EventRecord) -> Result<()> {
+        self.events
+            .write()
+            .expect("write events")
+            .insert(record.seq, record.to_owned());
+        Ok(())
+    }
+
+    fn delete_events_before(&self, seq: u64) -> Result<()> {
+        let mut events = self.events.write().expect("write events");
+        *events = events.split_off(&seq);
+        Ok(())
+    }
+
+    fn get_events(&self, from_seq: u64, limit: usize) -> Result<Vec<EventRecord>> {
+        Ok(self
+            .events
+            .read()
+            .expect("read events")
+            .range(from_seq..)
+            .take(limit)
+            .map(|(_, record)| record.to_owned())
+            .collect())
+    }
+
+    fn get_last_event_seq(&self) -> Result<u64> {
+        Ok(self
+            .events
+            .read()
+            .expect("read events")
+            .keys()
+            .next_back()
+            .copied()
+            .unwrap_or_default())
+    }
+
+    fn flush(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MemoryStore;
+    use crate::store::tests::*;
+
+    #[test]
+    fn test_otxs() {
+        check_otxs(&MemoryStore::default());
+    }
+
+    #[test]
+    fn test_sent_txs() {
+        check_sent_txs(&MemoryStore::default());
+    }
+
+    #[test]
+    fn test_status_history() {
+        check_status_history(&MemoryStore::default());
+    }
+
+    #[test]
+    fn test_events() {
+        check_events(&MemoryStore::default());
+    }
+}
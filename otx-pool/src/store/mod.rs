@@ -0,0 +1,125 @@
+mod memory;
+mod sled_store;
+
+pub use memory::MemoryStore;
+pub use sled_store::SledStore;
+
+use otx_format::types::{EventRecord, OpenTxWithStatus, StatusTransition};
+use otx_pool_config::StoreConfig;
+
+use anyhow::Result;
+use ckb_types::H256;
+
+use std::sync::Arc;
+
+/// The storage backend behind the otx pool.
+///
+/// The pool keeps its working set in memory and writes every change through to the store,
+/// so that the records can be reloaded after a restart.
+pub trait OtxStore: Send + Sync {
+    fn put_otx(&self, id: &H256, otx: &OpenTxWithStatus) -> Result<()>;
+    fn delete_otx(&self, id: &H256) -> Result<()>;
+    fn get_otxs(&self) -> Result<Vec<(H256, OpenTxWithStatus)>>;
+    fn put_sent_tx(&self, tx_hash: &H256, otx_hashes: &[H256]) -> Result<()>;
+    fn delete_sent_tx(&self, tx_hash: &H256) -> Result<()>;
+    fn get_sent_txs(&self) -> Result<Vec<(H256, Vec<H256>)>>;
+    fn append_status_transition(&self, id: &H256, transition: &StatusTransition) -> Result<()>;
+    fn delete_status_history(&self, id: &H256) -> Result<()>;
+    fn get_status_history(&self, id: &H256) -> Result<Vec<StatusTransition>>;
+    fn append_event(&self, record: &EventRecord) -> Result<()>;
+    /// Delete the events with a sequence number below `seq`.
+    fn delete_events_before(&self, seq: u64) -> Result<()>;
+    /// Up to `limit` events from the sequence number on, in order.
+    fn get_events(&self, from_seq: u64, limit: usize) -> Result<Vec<EventRecord>>;
+    /// The sequence number of the latest event, 0 if there is none.
+    fn get_last_event_seq(&self) -> Result<u64>;
+    fn flush(&self) -> Result<()>;
+}
+
+pub fn open_store(config: &StoreConfig) -> Result<Arc<dyn OtxStore>> {
+    if config.is_persistent() {
+        log::info!("open otx store: {}", config.get_path());
+        Ok(Arc::new(SledStore::open(config.get_path())?))
+    } else {
+        log::info!("no store path configured, otxs will only be kept in memory");
+        Ok(Arc::new(MemoryStore::default()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OtxStore;
+
+    use otx_format::jsonrpc_types::OpenTransaction;
+    use otx_format::types::{
+        EventRecord, OpenTxStatus, OpenTxWithStatus, PoolEvent, StatusTransition,
+    };
+
+    use ckb_types::H256;
+
+    // the checks shared by the store backends
+
+    pub fn check_otxs(store: &dyn OtxStore) {
+        let mut otx = OpenTxWithStatus::new(OpenTransaction::default());
+        store.put_otx(&H256([1; 32]), &otx).unwrap();
+        otx.status = OpenTxStatus::Rejected("test".to_owned());
+        store.put_otx(&H256([1; 32]), &otx).unwrap();
+        assert_eq!(store.get_otxs().unwrap(), vec![(H256([1; 32]), otx)]);
+
+        store.delete_otx(&H256([1; 32])).unwrap();
+        assert!(store.get_otxs().unwrap().is_empty());
+    }
+
+    pub fn check_sent_txs(store: &dyn OtxStore) {
+        let otx_hashes = vec![H256([2; 32]), H256([3; 32])];
+        store.put_sent_tx(&H256([1; 32]), &otx_hashes).unwrap();
+        assert_eq!(
+            store.get_sent_txs().unwrap(),
+            vec![(H256([1; 32]), otx_hashes)]
+        );
+
+        store.delete_sent_tx(&H256([1; 32])).unwrap();
+        assert!(store.get_sent_txs().unwrap().is_empty());
+    }
+
+    pub fn check_status_history(store: &dyn OtxStore) {
+        let id = H256([1; 32]);
+        let admitted = StatusTransition::new(None, OpenTxStatus::Pending, "test", None);
+        let rejected = StatusTransition::new(
+            Some(OpenTxStatus::Pending),
+            OpenTxStatus::Rejected("test".to_owned()),
+            "test",
+            Some("test".to_owned()),
+        );
+        store.append_status_transition(&id, &admitted).unwrap();
+        store.append_status_transition(&id, &rejected).unwrap();
+        assert_eq!(
+            store.get_status_history(&id).unwrap(),
+            vec![admitted, rejected]
+        );
+        assert!(store.get_status_history(&H256([2; 32])).unwrap().is_empty());
+
+        store.delete_status_history(&id).unwrap();
+        assert!(store.get_status_history(&id).unwrap().is_empty());
+    }
+
+    pub fn check_events(store: &dyn OtxStore) {
+        assert_eq!(store.get_last_event_seq().unwrap(), 0);
+        let records: Vec<EventRecord> = (1..=3)
+            .map(|seq| EventRecord::new(seq, PoolEvent::CommitOtx(vec![H256([seq as u8; 32])])))
+            .collect();
+        records
+            .iter()
+            .for_each(|record| store.append_event(record).unwrap());
+        assert_eq!(store.get_last_event_seq().unwrap(), 3);
+        assert_eq!(store.get_events(0, 10).unwrap(), records);
+        assert_eq!(store.get_events(2, 1).unwrap(), records[1..2].to_vec());
+        assert!(store.get_events(4, 10).unwrap().is_empty());
+
+        store.delete_events_before(1).unwrap();
+        assert_eq!(store.get_events(0, 10).unwrap(), records);
+        store.delete_events_before(3).unwrap();
+        assert_eq!(store.get_events(0, 10).unwrap(), records[2..].to_vec());
+        assert_eq!(store.get_last_event_seq().unwrap(), 3);
+    }
+}
@@ -0,0 +1,183 @@
+use super::OtxStore;
+
+use otx_format::types::{EventRecord, OpenTxWithStatus, StatusTransition};
+
+use anyhow::{anyhow, Result};
+use ckb_types::H256;
+
+use std::path::Path;
+
+const OTXS_TREE: &str = "otxs";
+const SENT_TXS_TREE: &str = "sent_txs";
+const STATUS_HISTORIES_TREE: &str = "status_histories";
+const EVENTS_TREE: &str = "events";
+
+/// An embedded on-disk store, the records are encoded as json.
+pub struct SledStore {
+    db: sled::Db,
+    otxs: sled::Tree,
+    sent_txs: sled::Tree,
+    status_histories: sled::Tree,
+    // keyed by the big-endian sequence number, so that the events are iterated in order
+    events: sled::Tree,
+}
+
+impl SledStore {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        SledStore::from_db(sled::open(path)?)
+    }
+
+    fn from_db(db: sled::Db) -> Result<Self> {
+        let otxs = db.open_tree(OTXS_TREE)?;
+        let sent_txs = db.open_tree(SENT_TXS_TREE)?;
+        let status_histories = db.open_tree(STATUS_HISTORIES_TREE)?;
+        let events = db.open_tree(EVENTS_TREE)?;
+        Ok(SledStore {
+            db,
+            otxs,
+            sent_txs,
+            status_histories,
+            events,
+        })
+    }
+}
+
+impl OtxStore for SledStore {
+    fn put_otx(&self, id: &H256, otx: &OpenTxWithStatus) -> Result<()> {
+        let value = serde_json::to_vec(otx)?;
+        self.otxs.insert(id.as_bytes(), value)?;
+        Ok(())
+    }
+
+    fn delete_otx(&self, id: &H256) -> Result<()> {
+        self.otxs.remove(id.as_bytes())?;
+        Ok(())
+    }
+
+    fn get_otxs(&self) -> Result<Vec<(H256, OpenTxWithStatus)>> {
+        self.otxs
+            .iter()
+            .map(|item| {
+                let (key, value) = item?;
+                let id = H256::from_slice(&key).map_err(|err| anyhow!(err.to_string()))?;
+                let otx: OpenTxWithStatus = serde_json::from_slice(&value)?;
+                Ok((id, otx))
+            })
+            .collect()
+    }
+
+    fn put_sent_tx(&self, tx_hash: &H256, otx_hashes: &[H256]) -> Result<()> {
+        let value = serde_json::to_vec(otx_hashes)?;
+        self.sent_txs.insert(tx_hash.as_bytes(), value)?;
+        Ok(())
+    }
+
+    fn delete_sent_tx(&self, tx_hash: &H256) -> Result<()> {
+        self.sent_txs.remove(tx_hash.as_bytes())?;
+        Ok(())
+    }
+
+    fn get_sent_txs(&self) -> Result<Vec<(H256, Vec<H256>)>> {
+        self.sent_txs
+            .iter()
+            .map(|item| {
+                let (key, value) = item?;
+                let tx_hash = H256::from_slice(&key).map_err(|err| anyhow!(err.to_string()))?;
+                let otx_hashes: Vec<H256> = serde_json::from_slice(&value)?;
+                Ok((tx_hash, otx_hashes))
+            })
+            .collect()
+    }
+
+    fn append_status_transition(&self, id: &H256, transition: &StatusTransition) -> Result<()> {
+        let mut history = self.get_status_history(id)?;
+        history.push(transition.to_owned());
+        let value = serde_json::to_vec(&history)?;
+        self.status_histories.insert(id.as_bytes(), value)?;
+        Ok(())
+    }
+
+    fn delete_status_history(&self, id: &H256) -> Result<()> {
+        self.status_histories.remove(id.as_bytes())?;
+        Ok(())
+    }
+
+    fn get_status_history(&self, id: &H256) -> Result<Vec<StatusTransition>> {
+        match self.status_histories.get(id.as_bytes())? {
+            Some(value) => Ok(serde_json::from_slice(&value)?),
+            None => Ok(vec![]),
+        }
+    }
+
+    fn append_event(&self, record: &EventRecord) -> Result<()> {
+        let value = serde_json::to_vec(record)?;
+        self.events.insert(record.seq.to_be_bytes(), value)?;
+        Ok(())
+    }
+
+    fn delete_events_before(&self, seq: u64) -> Result<()> {
+        let mut batch = sled::Batch::default();
+        for key in self.events.range(..seq.to_be_bytes()).keys() {
+            batch.remove(key?);
+        }
+        self.events.apply_batch(batch)?;
+        Ok(())
+    }
+
+    fn get_events(&self, from_seq: u64, limit: usize) -> Result<Vec<EventRecord>> {
+        self.events
+            .range(from_seq.to_be_bytes()..)
+            .take(limit)
+            .map(|item| {
+                let (_, value) = item?;
+                Ok(serde_json::from_slice(&value)?)
+            })
+            .collect()
+    }
+
+    fn get_last_event_seq(&self) -> Result<u64> {
+        match self.events.last()? {
+            Some((key, _)) => {
+                let seq: [u8; 8] = key.as_ref().try_into()?;
+                Ok(u64::from_be_bytes(seq))
+            }
+            None => Ok(0),
+        }
+    }
+
+    fn flush(&self) -> Result<()> {
+        self.db.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SledStore;
+    use crate::store::tests::*;
+
+    fn temporary_store() -> SledStore {
+        let db = sled::Config::new().temporary(true).open().unwrap();
+        SledStore::from_db(db).unwrap()
+    }
+
+    #[test]
+    fn test_otxs() {
+        check_otxs(&temporary_store());
+    }
+
+    #[test]
+    fn test_sent_txs() {
+        check_sent_txs(&temporary_store());
+    }
+
+    #[test]
+    fn test_status_history() {
+        check_status_history(&temporary_store());
+    }
+
+    #[test]
+    fn test_events() {
+        check_events(&temporary_store());
+    }
+}
@@ -0,0 +1,170 @@
+use super::{OtxValidator, ValidationContext};
+use crate::error::{InnerResult, OtxPoolError};
+
+use otx_format::jsonrpc_types::{tx_view::decode_udt_amount, OpenTransaction};
+
+use ckb_jsonrpc_types::{CellOutput, Script, TransactionView};
+use ckb_types::H256;
+
+use std::collections::HashMap;
+
+/// Checks that the accounting meta of the otx matches its input cells and outputs.
+pub struct AccountingValidator {
+    sudt_code_hash: Option<H256>,
+    xudt_code_hash: Option<H256>,
+}
+
+#[derive(Default)]
+struct Balance {
+    capacity: i128,
+    x_udt_amount: HashMap<Script, i128>,
+    s_udt_amount: HashMap<Script, i128>,
+}
+
+impl AccountingValidator {
+    pub fn new(sudt_code_hash: Option<H256>, xudt_code_hash: Option<H256>) -> Self {
+        AccountingValidator {
+            sudt_code_hash,
+            xudt_code_hash,
+        }
+    }
+
+    fn add(&self, balance: &mut Balance, output: &CellOutput, data: &[u8], sign: i128) {
+        balance.capacity += sign * output.capacity.value() as i128;
+        let type_ = if let Some(type_) = &output.type_ {
+            type_
+        } else {
+            return;
+        };
+        let udt_amount = if Some(&type_.code_hash) == self.xudt_code_hash.as_ref() {
+            &mut balance.x_udt_amount
+        } else if Some(&type_.code_hash) == self.sudt_code_hash.as_ref() {
+            &mut balance.s_udt_amount
+        } else {
+            return;
+        };
+        if let Some(amount) = decode_udt_amount(data) {
+            *udt_amount.entry(type_.to_owned()).or_insert(0) += sign * amount as i128;
+        }
+    }
+}
+
+impl OtxValidator for AccountingValidator {
+    fn name(&self) -> &str {
+        "accounting"
+    }
+
+    fn validate(&self, otx: &OpenTransaction, ctx: &mut ValidationContext) -> InnerResult<()> {
+        let payment = otx
+            .get_payment_amount()
+            .map_err(|err| OtxPoolError::MalformedOtx(err.to_string()))?;
+        let tx_view: TransactionView = otx.to_owned().try_into()?;
+
+        let mut balance = Balance::default();
+        for cell in ctx.get_input_cells(otx)? {
+            let data = cell
+                .data
+                .as_ref()
+                .map(|data| data.content.as_bytes())
+                .unwrap_or_default();
+            self.add(&mut balance, &cell.output, data, 1);
+        }
+        for (output, data) in tx_view
+            .inner
+            .outputs
+            .iter()
+            .zip(tx_view.inner.outputs_data.iter())
+        {
+            self.add(&mut balance, output, data.as_bytes(), -1);
+        }
+
+        if payment.capacity != balance.capacity {
+            return Err(OtxPoolError::AccountingMismatch(format!(
+                "capacity {} in meta, {} in cells",
+                payment.capacity, balance.capacity
+            ))
+            .into());
+        }
+        if non_zero(&payment.x_udt_amount) != non_zero(&balance.x_udt_amount) {
+            return Err(OtxPoolError::AccountingMismatch("xudt amount".to_owned()).into());
+        }
+        if non_zero(&payment.s_udt_amount) != non_zero(&balance.s_udt_amount) {
+            return Err(OtxPoolError::AccountingMismatch("sudt amount".to_owned()).into());
+        }
+        Ok(())
+    }
+}
+
+fn non_zero(amounts: &HashMap<Script, i128>) -> HashMap<&Script, i128> {
+    amounts
+        .iter()
+        .filter(|(_, amount)| **amount != 0)
+        .map(|(script, amount)| (script, *amount))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use otx_format::constant::extra_keys::{
+        OTX_ACCOUNTING_META_INPUT_CKB, OTX_ACCOUNTING_META_INPUT_XUDT,
+        OTX_ACCOUNTING_META_OUTPUT_CKB,
+    };
+    use otx_format::error::OtxError;
+    use otx_format::jsonrpc_types::OtxKeyPair;
+
+    use ckb_jsonrpc_types::JsonBytes;
+    use ckb_types::prelude::Pack;
+
+    fn build_otx(meta: Vec<(u32, Option<JsonBytes>, JsonBytes)>) -> OpenTransaction {
+        let mut otx = OpenTransaction::default();
+        for (key_type, key_data, value) in meta {
+            otx.meta
+                .push(OtxKeyPair::new(key_type.into(), key_data, value));
+        }
+        otx
+    }
+
+    fn capacity(capacity: u64) -> JsonBytes {
+        JsonBytes::from_bytes(capacity.pack().as_bytes())
+    }
+
+    fn assert_malformed(otx: &OpenTransaction) {
+        let validator = AccountingValidator::new(None, None);
+        let mut ctx = ValidationContext::new("http://127.0.0.1:8114");
+        let err = validator.validate(otx, &mut ctx).unwrap_err();
+        assert_eq!(
+            err.0.err_code(),
+            OtxPoolError::MalformedOtx(String::new()).err_code()
+        );
+    }
+
+    #[test]
+    fn test_malformed_meta() {
+        // the meta is checked before the input cells are queried
+        let missing_capacity = build_otx(vec![]);
+        assert_malformed(&missing_capacity);
+
+        let short_capacity = build_otx(vec![
+            (
+                OTX_ACCOUNTING_META_INPUT_CKB,
+                None,
+                JsonBytes::from_vec(vec![1, 2]),
+            ),
+            (OTX_ACCOUNTING_META_OUTPUT_CKB, None, capacity(100)),
+        ]);
+        assert_malformed(&short_capacity);
+
+        let missing_script = build_otx(vec![
+            (OTX_ACCOUNTING_META_INPUT_CKB, None, capacity(100)),
+            (OTX_ACCOUNTING_META_OUTPUT_CKB, None, capacity(100)),
+            (
+                OTX_ACCOUNTING_META_INPUT_XUDT,
+                None,
+                JsonBytes::from_bytes(1u128.pack().as_bytes()),
+            ),
+        ]);
+        assert_malformed(&missing_script);
+    }
+}
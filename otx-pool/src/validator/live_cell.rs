@@ -0,0 +1,17 @@
+use super::{OtxValidator, ValidationContext};
+use crate::error::InnerResult;
+
+use otx_format::jsonrpc_types::OpenTransaction;
+
+/// Checks that the inputs of the otx are live cells.
+pub struct LiveCellValidator;
+
+impl OtxValidator for LiveCellValidator {
+    fn name(&self) -> &str {
+        "live_cell"
+    }
+
+    fn validate(&self, otx: &OpenTransaction, ctx: &mut ValidationContext) -> InnerResult<()> {
+        ctx.get_input_cells(otx).map(|_| ())
+    }
+}
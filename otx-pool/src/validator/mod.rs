@@ -0,0 +1,126 @@
+mod accounting;
+mod live_cell;
+mod script;
+mod structure;
+
+pub use accounting::AccountingValidator;
+pub use live_cell::LiveCellValidator;
+pub use script::ScriptValidator;
+pub use structure::StructureValidator;
+
+use crate::error::{InnerResult, OtxPoolError};
+
+use otx_format::jsonrpc_types::OpenTransaction;
+use otx_pool_config::{CkbConfig, ScriptConfig, ValidatorConfig, ValidatorType};
+
+use ckb_jsonrpc_types::CellInfo;
+use ckb_sdk::CkbRpcClient;
+use ckb_types::{prelude::*, H256};
+
+/// A check an otx must pass before it enters the pool.
+pub trait OtxValidator: Send + Sync {
+    fn name(&self) -> &str;
+    fn validate(&self, otx: &OpenTransaction, ctx: &mut ValidationContext) -> InnerResult<()>;
+}
+
+/// The state shared by the validators while validating one otx.
+pub struct ValidationContext {
+    ckb_rpc_client: CkbRpcClient,
+    input_cells: Option<Vec<CellInfo>>,
+}
+
+impl ValidationContext {
+    pub fn new(ckb_uri: &str) -> Self {
+        ValidationContext {
+            ckb_rpc_client: CkbRpcClient::new(ckb_uri),
+            input_cells: None,
+        }
+    }
+
+    pub fn ckb_rpc_client(&mut self) -> &mut CkbRpcClient {
+        &mut self.ckb_rpc_client
+    }
+
+    /// Get the live cells spent by the otx, they are queried from the node only once.
+    pub fn get_input_cells(&mut self, otx: &OpenTransaction) -> InnerResult<&[CellInfo]> {
+        if self.input_cells.is_none() {
+            let mut input_cells = vec![];
+            for out_point in otx.get_input_out_points()? {
+                let cell_with_status = self
+                    .ckb_rpc_client
+                    .get_live_cell(out_point.clone(), true)
+                    .map_err(|err| OtxPoolError::CkbRpcError(err.to_string()))?;
+                match cell_with_status.cell {
+                    Some(cell) if cell_with_status.status == "live" => input_cells.push(cell),
+                    _ => {
+                        return Err(OtxPoolError::InputNotLive(format!(
+                            "{:#x}:{} is {}",
+                            out_point.tx_hash,
+                            out_point.index.value(),
+                            cell_with_status.status
+                        ))
+                        .into())
+                    }
+                }
+            }
+            self.input_cells = Some(input_cells);
+        }
+        Ok(self.input_cells.as_deref().unwrap_or_default())
+    }
+}
+
+/// The validators configured to run in order before an otx enters the pool.
+pub struct OtxValidatorChain {
+    validators: Vec<Box<dyn OtxValidator>>,
+    ckb_uri: String,
+}
+
+impl OtxValidatorChain {
+    pub fn new(
+        config: &ValidatorConfig,
+        ckb_config: &CkbConfig,
+        script_config: &ScriptConfig,
+    ) -> Self {
+        let validators = config
+            .get_validators()
+            .iter()
+            .map(|validator_type| -> Box<dyn OtxValidator> {
+                match validator_type {
+                    ValidatorType::Structure => Box::new(StructureValidator),
+                    ValidatorType::Accounting => Box::new(AccountingValidator::new(
+                        get_code_hash(script_config, "sudt"),
+                        get_code_hash(script_config, "xudt_rce"),
+                    )),
+                    ValidatorType::LiveCell => Box::new(LiveCellValidator),
+                    ValidatorType::Script => {
+                        Box::new(ScriptValidator::new(ckb_config.get_ckb_uri()))
+                    }
+                }
+            })
+            .collect();
+        OtxValidatorChain {
+            validators,
+            ckb_uri: ckb_config.get_ckb_uri().to_owned(),
+        }
+    }
+
+    pub fn validate(&self, otx: &OpenTransaction) -> InnerResult<()> {
+        if self.validators.is_empty() {
+            return Ok(());
+        }
+        let mut ctx = ValidationContext::new(&self.ckb_uri);
+        for validator in self.validators.iter() {
+            if let Err(err) = validator.validate(otx, &mut ctx) {
+                log::info!("otx is refused by validator {}: {}", validator.name(), err);
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn get_code_hash(script_config: &ScriptConfig, script_name: &str) -> Option<H256> {
+    script_config
+        .get_script_info(script_name)
+        .map(|info| info.script.code_hash().unpack())
+}
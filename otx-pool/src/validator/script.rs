@@ -0,0 +1,59 @@
+use super::{OtxValidator, ValidationContext};
+use crate::error::{InnerResult, OtxPoolError};
+
+use otx_format::jsonrpc_types::OpenTransaction;
+use otx_sdk::verifier::OtxScriptVerifier;
+
+use ckb_sdk::traits::DefaultTransactionDependencyProvider;
+use ckb_types::core::HeaderView;
+
+/// Runs the lock scripts of the otx in CKB-VM, with the cells resolved from the node.
+///
+/// The type scripts of a partial transaction may not pass on their own, so they are skipped.
+pub struct ScriptValidator {
+    ckb_uri: String,
+}
+
+impl ScriptValidator {
+    pub fn new(ckb_uri: &str) -> Self {
+        ScriptValidator {
+            ckb_uri: ckb_uri.to_owned(),
+        }
+    }
+}
+
+impl OtxValidator for ScriptValidator {
+    fn name(&self) -> &str {
+        "script"
+    }
+
+    fn validate(&self, otx: &OpenTransaction, ctx: &mut ValidationContext) -> InnerResult<()> {
+        let tip: HeaderView = ctx
+            .ckb_rpc_client()
+            .get_tip_header()
+            .map_err(|err| OtxPoolError::CkbRpcError(err.to_string()))?
+            .into();
+        let provider = DefaultTransactionDependencyProvider::new(&self.ckb_uri, 10);
+        let results = OtxScriptVerifier::new(&provider, &tip)
+            .verify_locks(otx)
+            .map_err(|err| OtxPoolError::ScriptVerificationFailed(err.to_string()))?;
+
+        if let Some(failed) = results.iter().find(|result| !result.is_ok()) {
+            return Err(OtxPoolError::ScriptVerificationFailed(format!(
+                "lock script {:#x} of inputs {:?}: {}",
+                failed.script_hash,
+                failed.input_indices,
+                failed.error.to_owned().unwrap_or_default()
+            ))
+            .into());
+        }
+        log::debug!(
+            "otx lock scripts consumed {} cycles",
+            results
+                .iter()
+                .filter_map(|result| result.cycles)
+                .sum::<u64>()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,51 @@
+use super::{OtxValidator, ValidationContext};
+use crate::error::{InnerResult, OtxPoolError};
+
+use otx_format::jsonrpc_types::OpenTransaction;
+
+use ckb_types::core::TransactionView;
+
+use std::collections::HashSet;
+
+/// Checks that the otx maps can be converted into a transaction.
+pub struct StructureValidator;
+
+impl OtxValidator for StructureValidator {
+    fn name(&self) -> &str {
+        "structure"
+    }
+
+    fn validate(&self, otx: &OpenTransaction, _ctx: &mut ValidationContext) -> InnerResult<()> {
+        let tx_view: TransactionView = otx
+            .to_owned()
+            .try_into()
+            .map_err(|err| OtxPoolError::MalformedOtx(err.to_string()))?;
+
+        if tx_view.inputs().is_empty() {
+            return Err(OtxPoolError::MalformedOtx("no inputs".to_owned()).into());
+        }
+        let mut out_points = HashSet::new();
+        if !tx_view
+            .input_pts_iter()
+            .all(|out_point| out_points.insert(out_point))
+        {
+            return Err(OtxPoolError::MalformedOtx("duplicate inputs".to_owned()).into());
+        }
+
+        // the pool reads the max fee of every otx to make room for the new ones
+        otx.try_get_max_fee()
+            .map_err(|err| OtxPoolError::MalformedOtx(err.to_string()))?;
+
+        // the otx id carried in the meta must be the hash of the otx
+        let id = otx.to_owned().get_or_insert_otx_id()?;
+        let tx_hash = otx.get_tx_hash()?;
+        if id != tx_hash {
+            return Err(OtxPoolError::MalformedOtx(format!(
+                "otx id {:#x} does not match the tx hash {:#x}",
+                id, tx_hash
+            ))
+            .into());
+        }
+        Ok(())
+    }
+}
@@ -1,3 +1,4 @@
 pub mod address;
 pub mod build_tx;
 pub mod signer;
+pub mod verifier;
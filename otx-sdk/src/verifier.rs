@@ -0,0 +1,142 @@
+use otx_format::jsonrpc_types::OpenTransaction;
+
+use anyhow::{anyhow, Result};
+use ckb_chain_spec::consensus::{Consensus, ConsensusBuilder};
+use ckb_script::{ScriptGroupType, TransactionScriptsVerifier, TxVerifyEnv};
+use ckb_sdk::traits::TransactionDependencyProvider;
+use ckb_traits::{CellDataProvider, HeaderProvider};
+use ckb_types::bytes::Bytes;
+use ckb_types::core::cell::{
+    resolve_transaction, CellMetaBuilder, CellProvider, CellStatus, HeaderChecker,
+};
+use ckb_types::core::error::OutPointError;
+use ckb_types::core::{Cycle, HeaderView, TransactionView};
+use ckb_types::packed::{Byte32, CellOutput, OutPoint};
+use ckb_types::prelude::*;
+use ckb_types::H256;
+
+use std::collections::HashSet;
+
+/// The verification result of a script group.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScriptGroupResult {
+    pub script_hash: H256,
+    pub group_type: ScriptGroupType,
+    pub input_indices: Vec<usize>,
+    pub output_indices: Vec<usize>,
+    /// The consumed cycles if the script group passes.
+    pub cycles: Option<Cycle>,
+    /// The error message if the script group fails.
+    pub error: Option<String>,
+}
+
+impl ScriptGroupResult {
+    pub fn is_ok(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Runs the scripts of an otx in CKB-VM.
+///
+/// The input and dep cells are resolved through the dependency provider,
+/// e.g. `DefaultTransactionDependencyProvider` which queries a CKB node.
+pub struct OtxScriptVerifier<'a> {
+    provider: &'a dyn TransactionDependencyProvider,
+    consensus: Consensus,
+    tx_env: TxVerifyEnv,
+}
+
+impl<'a> OtxScriptVerifier<'a> {
+    pub fn new(provider: &'a dyn TransactionDependencyProvider, tip: &HeaderView) -> Self {
+        OtxScriptVerifier {
+            provider,
+            consensus: ConsensusBuilder::default().build(),
+            tx_env: TxVerifyEnv::new_submit(tip),
+        }
+    }
+
+    /// Verify the lock script groups of the otx, the type scripts of a partial transaction
+    /// are not expected to pass on their own.
+    pub fn verify_locks(&self, otx: &OpenTransaction) -> Result<Vec<ScriptGroupResult>> {
+        self.verify(otx, &[ScriptGroupType::Lock])
+    }
+
+    /// Verify the script groups of the given types, every group gets its own result.
+    pub fn verify(
+        &self,
+        otx: &OpenTransaction,
+        group_types: &[ScriptGroupType],
+    ) -> Result<Vec<ScriptGroupResult>> {
+        let tx_view: TransactionView = otx.to_owned().try_into().map_err(|err| anyhow!(err))?;
+        let resolver = Resolver {
+            provider: self.provider,
+        };
+        let rtx = resolve_transaction(tx_view, &mut HashSet::new(), &resolver, &resolver)
+            .map_err(|err| anyhow!("resolve otx error: {}", err))?;
+        let verifier =
+            TransactionScriptsVerifier::new(&rtx, &self.consensus, &resolver, &self.tx_env);
+
+        let max_cycles = self.consensus.max_block_cycles();
+        let results = verifier
+            .groups()
+            .filter(|(_, group)| group_types.contains(&group.group_type))
+            .map(|(script_hash, group)| {
+                let verified = verifier.verify_single(group.group_type, script_hash, max_cycles);
+                ScriptGroupResult {
+                    script_hash: script_hash.unpack(),
+                    group_type: group.group_type,
+                    input_indices: group.input_indices.clone(),
+                    output_indices: group.output_indices.clone(),
+                    cycles: verified.as_ref().ok().copied(),
+                    error: verified.err().map(|err| err.to_string()),
+                }
+            })
+            .collect();
+        Ok(results)
+    }
+}
+
+struct Resolver<'a> {
+    provider: &'a dyn TransactionDependencyProvider,
+}
+
+impl<'a> CellProvider for Resolver<'a> {
+    fn cell(&self, out_point: &OutPoint, _eager_load: bool) -> CellStatus {
+        let output = self.provider.get_cell(out_point);
+        let data = self.provider.get_cell_data(out_point);
+        match (output, data) {
+            (Ok(output), Ok(data)) => CellStatus::live_cell(
+                CellMetaBuilder::from_cell_output(output, data)
+                    .out_point(out_point.to_owned())
+                    .build(),
+            ),
+            _ => CellStatus::Unknown,
+        }
+    }
+}
+
+impl<'a> HeaderChecker for Resolver<'a> {
+    fn check_valid(&self, block_hash: &Byte32) -> Result<(), OutPointError> {
+        self.provider
+            .get_header(block_hash)
+            .map(|_| ())
+            .map_err(|_| OutPointError::InvalidHeader(block_hash.to_owned()))
+    }
+}
+
+impl<'a> CellDataProvider for Resolver<'a> {
+    fn get_cell_data(&self, out_point: &OutPoint) -> Option<Bytes> {
+        self.provider.get_cell_data(out_point).ok()
+    }
+
+    fn get_cell_data_hash(&self, out_point: &OutPoint) -> Option<Byte32> {
+        self.get_cell_data(out_point)
+            .map(|data| CellOutput::calc_data_hash(&data))
+    }
+}
+
+impl<'a> HeaderProvider for Resolver<'a> {
+    fn get_header(&self, hash: &Byte32) -> Option<HeaderView> {
+        self.provider.get_header(hash).ok()
+    }
+}
@@ -4,7 +4,8 @@ use otx_format::jsonrpc_types::OpenTransaction;
 use otx_format::types::PaymentAmount;
 use otx_pool_config::{CkbConfig, ScriptConfig};
 use otx_pool_plugin_protocol::{
-    HostServiceHandler, MessageFromHost, MessageFromPlugin, Plugin, PluginInfo, PluginMeta,
+    HostServiceHandler, Job, MessageFromHost, MessageFromPlugin, Plugin, PluginInfo, PluginMeta,
+    Schedule,
 };
 use otx_sdk::build_tx::send_tx;
 use otx_sdk::build_tx::OtxBuilder;
@@ -18,8 +19,10 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 use std::path::PathBuf;
 
-pub const EVERY_INTERVALS: usize = 10;
+pub const MERGE_JOB_ID: &str = "merge";
+pub const MERGE_INTERVAL_SECS: u64 = 10;
 pub const MIN_FEE: u64 = 1_0000_0000;
+pub const CLAIM_LEASE_SECS: u64 = 60;
 
 #[derive(Clone)]
 struct Context {
@@ -48,6 +51,31 @@ impl Context {
             proposals: DashMap::new(),
         }
     }
+
+    /// Claim the otxs so that no other plugin consumes them while the swap tx is sent.
+    fn claim_otxs(&self, otx_hashes: Vec<H256>) -> bool {
+        let message = MessageFromPlugin::ClaimOtxs((otx_hashes, CLAIM_LEASE_SECS));
+        matches!(
+            Request::call(&self.service_handler, (self.plugin_name.clone(), message)),
+            Some(MessageFromHost::Ok)
+        )
+    }
+
+    fn release_otxs(&self, otx_hashes: Vec<H256>) {
+        let message = MessageFromPlugin::ReleaseOtxs(otx_hashes);
+        Request::call(&self.service_handler, (self.plugin_name.clone(), message));
+    }
+
+    fn schedule_merge(&self) {
+        let job = Job::new(MERGE_JOB_ID, Schedule::Every(MERGE_INTERVAL_SECS));
+        let message = MessageFromPlugin::ScheduleJob(job);
+        if !matches!(
+            Request::call(&self.service_handler, (self.plugin_name.clone(), message)),
+            Some(MessageFromHost::Ok)
+        ) {
+            log::error!("{} failed to schedule the merge job", self.plugin_name);
+        }
+    }
 }
 
 #[derive(Debug, Eq, PartialEq, Hash, Default, Clone, Serialize, Deserialize)]
@@ -184,7 +212,7 @@ impl Plugin for AtomicSwap {
         self.meta.clone()
     }
 
-    fn on_new_otx(&self, otx: OpenTransaction) {
+    fn on_new_otx(&self, _seq: u64, otx: OpenTransaction) {
         log::info!(
             "on_new_open_tx, index otxs count: {:?}",
             self.context.otxs.len()
@@ -243,6 +271,17 @@ impl Plugin for AtomicSwap {
             }
             log::info!("match {:#x} with {:#x}: succeeded", otx_hash, pair_otx_hash);
 
+            // claim the pair, another plugin may be consuming them
+            let otx_hashes = vec![pair_otx_hash.to_owned(), otx_hash.to_owned()];
+            if !self.context.claim_otxs(otx_hashes.clone()) {
+                log::info!(
+                    "{:#x} or {:#x} is claimed by another plugin",
+                    otx_hash,
+                    pair_otx_hash
+                );
+                continue;
+            }
+
             // merge_otx
             let builder = OtxBuilder::new(
                 self.context.script_config.clone(),
@@ -260,6 +299,7 @@ impl Plugin for AtomicSwap {
                         self.context.plugin_name,
                         err
                     );
+                    self.context.release_otxs(otx_hashes);
                     continue;
                 }
             };
@@ -269,6 +309,7 @@ impl Plugin for AtomicSwap {
                 Ok(tx_hash) => tx_hash,
                 Err(err) => {
                     log::error!("failed to send final tx: {}", err);
+                    self.context.release_otxs(otx_hashes);
                     continue;
                 }
             };
@@ -276,12 +317,11 @@ impl Plugin for AtomicSwap {
             found_match = true;
 
             // call host service
-            let message = MessageFromPlugin::MergeOtxsAndSentToCkb((
-                vec![pair_otx_hash.to_owned(), otx_hash.to_owned()],
-                tx_hash,
-            ));
-            if let Some(MessageFromHost::Ok) = Request::call(&self.context.service_handler, message)
-            {
+            let message = MessageFromPlugin::MergeOtxsAndSentToCkb((otx_hashes, tx_hash));
+            if let Some(MessageFromHost::Ok) = Request::call(
+                &self.context.service_handler,
+                (self.context.plugin_name.clone(), message),
+            ) {
                 self.context.otxs.remove(pair_otx_hash);
                 self.context.proposals.retain(|_, hashes| {
                     hashes.remove(pair_otx_hash);
@@ -305,7 +345,7 @@ impl Plugin for AtomicSwap {
         }
     }
 
-    fn on_commit_otx(&self, otx_hashes: Vec<H256>) {
+    fn on_commit_otx(&self, _seq: u64, otx_hashes: Vec<H256>) {
         log::info!(
             "{} on commit open tx remove committed otx: {:?}",
             self.context.plugin_name,
@@ -323,14 +363,35 @@ impl Plugin for AtomicSwap {
         })
     }
 
-    fn on_new_intervel(&self, elapsed: u64) {
-        if elapsed % EVERY_INTERVALS as u64 != 0 || self.context.otxs.len() <= 1 {
+    fn on_reject_otx(&self, otxs: Vec<(H256, String)>) {
+        log::info!(
+            "{} on reject open tx remove rejected otx: {:?}",
+            self.context.plugin_name,
+            otxs.iter()
+                .map(|(hash, reason)| format!("{}: {}", hash, reason))
+                .collect::<Vec<String>>()
+        );
+        otxs.iter().for_each(|(otx_hash, _)| {
+            self.context.otxs.remove(otx_hash);
+            self.context.proposals.retain(|_, hashes| {
+                hashes.remove(otx_hash);
+                !hashes.is_empty()
+            });
+        })
+    }
+
+    fn on_start(&self) {
+        self.context.schedule_merge();
+    }
+
+    fn on_timer(&self, job_id: String, timestamp: u64) {
+        if job_id != MERGE_JOB_ID || self.context.otxs.len() <= 1 {
             return;
         }
 
         log::info!(
-            "on new {} intervals otx set len: {:?}",
-            EVERY_INTERVALS,
+            "on merge job at {}, otx set len: {:?}",
+            timestamp,
             self.context.otxs.len()
         );
     }
@@ -6,7 +6,8 @@ use otx_format::jsonrpc_types::OpenTransaction;
 use otx_pool_config::built_in_plugins::DustCollectorConfig;
 use otx_pool_config::{CkbConfig, ScriptConfig};
 use otx_pool_plugin_protocol::{
-    HostServiceHandler, MessageFromHost, MessageFromPlugin, Plugin, PluginInfo, PluginMeta,
+    HostServiceHandler, Job, MessageFromHost, MessageFromPlugin, Plugin, PluginInfo, PluginMeta,
+    Schedule,
 };
 use otx_sdk::build_tx::OtxBuilder;
 
@@ -22,9 +23,11 @@ use dashmap::DashMap;
 use std::env;
 use std::path::PathBuf;
 
-pub const EVERY_INTERVALS: usize = 10;
+pub const MERGE_JOB_ID: &str = "merge";
+pub const MERGE_INTERVAL_SECS: u64 = 10;
 pub const MIN_PAYMENT: usize = 1_0000_0000;
 pub const DEFAULT_FEE: usize = 1000_0000;
+pub const CLAIM_LEASE_SECS: u64 = 60;
 
 #[derive(Clone)]
 struct Context {
@@ -53,6 +56,31 @@ impl Context {
             service_handler,
         }
     }
+
+    /// Claim the otxs so that no other plugin consumes them while the merged otx is built.
+    fn claim_otxs(&self, otx_hashes: Vec<H256>) -> bool {
+        let message = MessageFromPlugin::ClaimOtxs((otx_hashes, CLAIM_LEASE_SECS));
+        matches!(
+            Request::call(&self.service_handler, (self.plugin_name.clone(), message)),
+            Some(MessageFromHost::Ok)
+        )
+    }
+
+    fn release_otxs(&self, otx_hashes: Vec<H256>) {
+        let message = MessageFromPlugin::ReleaseOtxs(otx_hashes);
+        Request::call(&self.service_handler, (self.plugin_name.clone(), message));
+    }
+
+    fn schedule_merge(&self) {
+        let job = Job::new(MERGE_JOB_ID, Schedule::Every(MERGE_INTERVAL_SECS));
+        let message = MessageFromPlugin::ScheduleJob(job);
+        if !matches!(
+            Request::call(&self.service_handler, (self.plugin_name.clone(), message)),
+            Some(MessageFromHost::Ok)
+        ) {
+            log::error!("{} failed to schedule the merge job", self.plugin_name);
+        }
+    }
 }
 
 pub struct DustCollector {
@@ -100,7 +128,7 @@ impl Plugin for DustCollector {
         self.meta.clone()
     }
 
-    fn on_new_otx(&self, otx: OpenTransaction) {
+    fn on_new_otx(&self, _seq: u64, otx: OpenTransaction) {
         log::info!(
             "on_new_open_tx, index otxs count: {:?}",
             self.context.otxs.len()
@@ -126,7 +154,7 @@ impl Plugin for DustCollector {
         self.context.otxs.insert(otx_hash, otx);
     }
 
-    fn on_commit_otx(&self, otx_hashes: Vec<H256>) {
+    fn on_commit_otx(&self, _seq: u64, otx_hashes: Vec<H256>) {
         log::info!(
             "{} on commit open tx remove committed otx: {:?}",
             self.context.plugin_name,
@@ -140,20 +168,47 @@ impl Plugin for DustCollector {
         })
     }
 
-    fn on_new_intervel(&self, elapsed: u64) {
-        if elapsed % EVERY_INTERVALS as u64 != 0 || self.context.otxs.len() <= 1 {
+    fn on_reject_otx(&self, otxs: Vec<(H256, String)>) {
+        log::info!(
+            "{} on reject open tx remove rejected otx: {:?}",
+            self.context.plugin_name,
+            otxs.iter()
+                .map(|(hash, reason)| format!("{}: {}", hash, reason))
+                .collect::<Vec<String>>()
+        );
+        otxs.iter().for_each(|(otx_hash, _)| {
+            self.context.otxs.remove(otx_hash);
+        })
+    }
+
+    fn on_start(&self) {
+        self.context.schedule_merge();
+    }
+
+    fn on_timer(&self, job_id: String, timestamp: u64) {
+        if job_id != MERGE_JOB_ID || self.context.otxs.len() <= 1 {
             return;
         }
 
         log::info!(
-            "on new {} intervals otx set len: {:?}",
-            EVERY_INTERVALS,
+            "on merge job at {}, otx set len: {:?}",
+            timestamp,
             self.context.otxs.len()
         );
 
-        // merge_otx
+        // claim the otxs, another plugin may be consuming them
         let otx_list: Vec<OpenTransaction> =
             self.context.otxs.iter().map(|otx| otx.clone()).collect();
+        let hashes: Vec<H256> = otx_list
+            .iter()
+            .map(|otx| otx.get_tx_hash().expect("get tx hash"))
+            .collect();
+        if !self.context.claim_otxs(hashes.clone()) {
+            log::info!("otxs are claimed by another plugin, retry later");
+            return;
+        }
+
+        // merge_otx
         let otx_builder = OtxBuilder::new(
             self.context.script_config.clone(),
             self.context.ckb_config.clone(),
@@ -166,6 +221,7 @@ impl Plugin for DustCollector {
                 "Failed to merge otxs, all otxs staged by {} itself will be cleared.",
                 self.context.plugin_name
             );
+            self.context.release_otxs(hashes);
             self.context.otxs.clear();
             return;
         };
@@ -190,6 +246,7 @@ impl Plugin for DustCollector {
             cell.clone()
         } else {
             log::error!("broker has no cells available for input");
+            self.context.release_otxs(hashes);
             return;
         };
 
@@ -216,19 +273,19 @@ impl Plugin for DustCollector {
             ckb_tx
         } else {
             log::error!("failed to assemble final tx.");
+            self.context.release_otxs(hashes);
             return;
         };
 
         // call host service
-        let hashes: Vec<H256> = self
-            .context
-            .otxs
-            .iter()
-            .map(|otx| otx.get_tx_hash().expect("get tx hash"))
-            .collect();
-        let message = MessageFromPlugin::NewMergedOtx((unsigned_otx, hashes));
-        if let Some(MessageFromHost::Ok) = Request::call(&self.context.service_handler, message) {
+        let message = MessageFromPlugin::NewMergedOtx((unsigned_otx, hashes.clone()));
+        if let Some(MessageFromHost::Ok) = Request::call(
+            &self.context.service_handler,
+            (self.context.plugin_name.clone(), message),
+        ) {
             self.context.otxs.clear();
+        } else {
+            self.context.release_otxs(hashes);
         }
     }
 }
@@ -101,7 +101,7 @@ impl Plugin for Signer {
         self.meta.clone()
     }
 
-    fn on_new_otx(&self, otx: OpenTransaction) {
+    fn on_new_otx(&self, _seq: u64, otx: OpenTransaction) {
         log::info!(
             "on_new_open_tx, index otxs count: {:?}",
             self.context.otxs.len()
@@ -142,12 +142,15 @@ impl Plugin for Signer {
 
         // call host service to notify the host that the final tx has been sent
         let message = MessageFromPlugin::SentToCkb(tx_hash);
-        if let Some(MessageFromHost::Ok) = Request::call(&self.context.service_handler, message) {
+        if let Some(MessageFromHost::Ok) = Request::call(
+            &self.context.service_handler,
+            (self.context.plugin_name.clone(), message),
+        ) {
             self.context.otxs.clear();
         }
     }
 
-    fn on_commit_otx(&self, otx_hashes: Vec<H256>) {
+    fn on_commit_otx(&self, _seq: u64, otx_hashes: Vec<H256>) {
         log::info!(
             "{} on commit open tx remove committed otx: {:?}",
             self.context.plugin_name,
@@ -160,4 +163,17 @@ impl Plugin for Signer {
             self.context.otxs.remove(otx_hash);
         })
     }
+
+    fn on_reject_otx(&self, otxs: Vec<(H256, String)>) {
+        log::info!(
+            "{} on reject open tx remove rejected otx: {:?}",
+            self.context.plugin_name,
+            otxs.iter()
+                .map(|(hash, reason)| format!("{}: {}", hash, reason))
+                .collect::<Vec<String>>()
+        );
+        otxs.iter().for_each(|(otx_hash, _)| {
+            self.context.otxs.remove(otx_hash);
+        })
+    }
 }
@@ -7,6 +7,7 @@ use otx_pool_plugin_signer::Signer;
 use anyhow::{anyhow, Result};
 use clap::Parser;
 
+use std::path::Path;
 use std::sync::Arc;
 
 pub const PLUGINS_DIRNAME: &str = "plugins";
@@ -18,10 +19,10 @@ struct Args {
     config_path: String,
 }
 
-fn read_cli_args() -> Result<AppConfig> {
+fn read_cli_args() -> Result<(String, AppConfig)> {
     let args = Args::parse();
-    let config: ConfigFile = parse(args.config_path)?;
-    Ok(config.into())
+    let config: ConfigFile = parse(&args.config_path)?;
+    Ok((args.config_path, config.into()))
 }
 
 fn main() -> Result<()> {
@@ -40,9 +41,22 @@ fn main() -> Result<()> {
         env_logger::init();
     }
 
-    let config = read_cli_args()?;
-
-    let mut otx_pool_service = OtxPoolService::new(config.get_network_config())?;
+    let (config_path, config) = read_cli_args()?;
+
+    let mut otx_pool_service = OtxPoolService::new(
+        config.get_network_config(),
+        config.get_store_config(),
+        config.get_pool_config(),
+        config.get_ckb_config(),
+        config.get_chain_watcher_config(),
+        config.get_validator_config(),
+        config.get_notify_config(),
+        config.get_scheduler_config(),
+        config.get_plugin_supervisor_config(),
+        config.get_plugin_dispatch_config(),
+        config.get_plugins_config(),
+        config.get_script_config(),
+    )?;
 
     // add plugin AtomicUdtSwap
     if config.get_atomic_swap_config().is_enabled() {
@@ -55,7 +69,7 @@ fn main() -> Result<()> {
             .map_err(|err| anyhow!(err))?,
         );
         otx_pool_service.extended_rpc_with(AtomicSwapRpc::to_delegate(atomic_swap.clone()));
-        otx_pool_service.add_plugin(Box::new(atomic_swap));
+        otx_pool_service.add_plugin(Box::new(atomic_swap))?;
     }
 
     // add plugin DustCollector
@@ -69,7 +83,7 @@ fn main() -> Result<()> {
             )
             .map_err(|err| anyhow!(err))?,
         );
-        otx_pool_service.add_plugin(Box::new(dust_collector));
+        otx_pool_service.add_plugin(Box::new(dust_collector))?;
     }
 
     // add plugin Signer
@@ -83,11 +97,12 @@ fn main() -> Result<()> {
             )
             .map_err(|err| anyhow!(err))?,
         );
-        otx_pool_service.add_plugin(Box::new(signer));
+        otx_pool_service.add_plugin(Box::new(signer))?;
     }
 
     // start otx pool service
     otx_pool_service.start();
+    otx_pool_service.watch_plugins_config(Path::new(&config_path))?;
 
     // display all names of plugins
     let plugins = otx_pool_service.get_plugin_configs();
@@ -1,10 +1,17 @@
 use super::{request, RpcClient};
 
-use otx_format::{jsonrpc_types::OpenTransaction, types::OpenTxStatus};
+use otx_format::{
+    jsonrpc_types::OpenTransaction,
+    types::{
+        EventPage, OpenTxDetail, OpenTxStatus, OpenTxWithId, Order, OtxFilter, Pagination,
+        StatusTransition,
+    },
+};
 use otx_pool_plugin_atomic_swap::SwapProposalWithOtxId;
-use otx_pool_plugin_protocol::PluginInfo;
+use otx_pool_plugin_protocol::{PluginDetail, PluginHealth, PluginInfo};
 
 use anyhow::Result;
+use ckb_jsonrpc_types::{JsonBytes, Uint32, Uint64};
 use ckb_types::H256;
 
 pub struct OtxPoolRpcClient {
@@ -25,6 +32,64 @@ impl OtxPoolRpcClient {
         request(&self.client, "query_otx_status_by_id", vec![otx])
     }
 
+    pub fn get_otx_by_id(&self, id: H256) -> Result<Option<OpenTxDetail>> {
+        request(&self.client, "get_otx_by_id", vec![id])
+    }
+
+    pub fn get_otxs_by_ids(&self, ids: Vec<H256>) -> Result<Vec<Option<OpenTxDetail>>> {
+        request(&self.client, "get_otxs_by_ids", vec![ids])
+    }
+
+    pub fn get_otx_status_history(&self, id: H256) -> Result<Vec<StatusTransition>> {
+        request(&self.client, "get_otx_status_history", vec![id])
+    }
+
+    pub fn get_otxs(
+        &self,
+        filter: OtxFilter,
+        order: Option<Order>,
+        limit: u32,
+        after: Option<JsonBytes>,
+    ) -> Result<Pagination<OpenTxWithId>> {
+        request(
+            &self.client,
+            "get_otxs",
+            (filter, order, Uint32::from(limit), after),
+        )
+    }
+
+    pub fn get_events(&self, from_seq: u64, limit: u32) -> Result<EventPage> {
+        request(
+            &self.client,
+            "get_events",
+            (Uint64::from(from_seq), Uint32::from(limit)),
+        )
+    }
+
+    pub fn get_plugins_health(&self) -> Result<Vec<PluginHealth>> {
+        request(&self.client, "get_plugins_health", ())
+    }
+
+    pub fn admin_list_plugins(&self) -> Result<Vec<PluginDetail>> {
+        request(&self.client, "admin_list_plugins", ())
+    }
+
+    pub fn admin_install_plugin(&self, binary_path: String, activate: bool) -> Result<PluginInfo> {
+        request(
+            &self.client,
+            "admin_install_plugin",
+            (binary_path, activate),
+        )
+    }
+
+    pub fn admin_activate_plugin(&self, plugin_name: String) -> Result<()> {
+        request(&self.client, "admin_activate_plugin", vec![plugin_name])
+    }
+
+    pub fn admin_deactivate_plugin(&self, plugin_name: String) -> Result<()> {
+        request(&self.client, "admin_deactivate_plugin", vec![plugin_name])
+    }
+
     pub fn get_atomic_swap_info(&self) -> Result<PluginInfo> {
         request(&self.client, "get_atomic_swap_info", ())
     }
@@ -0,0 +1,57 @@
+use serde::Deserialize;
+
+const DEFAULT_POLL_INTERVAL_SECS: u64 = 3;
+const DEFAULT_REORG_WINDOW: usize = 100;
+const DEFAULT_CONFIRMATION_DEPTH: u64 = 24;
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct ChainWatcherConfig {
+    enabled: bool,
+    poll_interval_secs: u64,
+    reorg_window: usize, // number of recent blocks kept to detect reorgs
+    confirmation_depth: u64,
+}
+
+impl Default for ChainWatcherConfig {
+    fn default() -> Self {
+        ChainWatcherConfig {
+            enabled: true,
+            poll_interval_secs: DEFAULT_POLL_INTERVAL_SECS,
+            reorg_window: DEFAULT_REORG_WINDOW,
+            confirmation_depth: DEFAULT_CONFIRMATION_DEPTH,
+        }
+    }
+}
+
+impl ChainWatcherConfig {
+    pub fn new(
+        enabled: bool,
+        poll_interval_secs: u64,
+        reorg_window: usize,
+        confirmation_depth: u64,
+    ) -> Self {
+        ChainWatcherConfig {
+            enabled,
+            poll_interval_secs,
+            reorg_window,
+            confirmation_depth,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn get_poll_interval_secs(&self) -> u64 {
+        self.poll_interval_secs
+    }
+
+    pub fn get_reorg_window(&self) -> usize {
+        self.reorg_window
+    }
+
+    pub fn get_confirmation_depth(&self) -> u64 {
+        self.confirmation_depth
+    }
+}
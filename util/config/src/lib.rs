@@ -1,11 +1,29 @@
 pub mod built_in_plugins;
+pub mod chain_watcher;
 pub mod ckb_config;
 pub mod network;
+pub mod notify;
+pub mod plugin_dispatch;
+pub mod plugin_supervisor;
+pub mod plugins;
+pub mod pool;
+pub mod scheduler;
 pub mod script;
+pub mod store;
+pub mod validator;
 
+pub use chain_watcher::ChainWatcherConfig;
 pub use ckb_config::CkbConfig;
 pub use network::NetworkConfig;
+pub use notify::NotifyConfig;
+pub use plugin_dispatch::{OverflowPolicy, PluginDispatchConfig};
+pub use plugin_supervisor::PluginSupervisorConfig;
+pub use plugins::PluginsConfig;
+pub use pool::{EvictionPolicy, PoolConfig};
+pub use scheduler::SchedulerConfig;
 pub use script::ScriptConfigItem;
+pub use store::StoreConfig;
+pub use validator::{ValidatorConfig, ValidatorType};
 
 use built_in_plugins::{AtomicSwapConfig, DustCollectorConfig, SignerConfig};
 
@@ -30,6 +48,24 @@ pub struct ScriptInfo {
 pub struct ConfigFile {
     pub network_config: NetworkConfig,
     pub ckb_config: CkbConfig,
+    #[serde(default)]
+    pub store_config: StoreConfig,
+    #[serde(default)]
+    pub pool_config: PoolConfig,
+    #[serde(default)]
+    pub chain_watcher_config: ChainWatcherConfig,
+    #[serde(default)]
+    pub validator_config: ValidatorConfig,
+    #[serde(default)]
+    pub notify_config: NotifyConfig,
+    #[serde(default)]
+    pub scheduler_config: SchedulerConfig,
+    #[serde(default)]
+    pub plugin_supervisor_config: PluginSupervisorConfig,
+    #[serde(default)]
+    pub plugin_dispatch_config: PluginDispatchConfig,
+    #[serde(default)]
+    pub plugins: PluginsConfig,
     pub scripts: Vec<ScriptConfigItem>,
     pub built_in_plugin_dust_collector: DustCollectorConfig,
     pub built_in_plugin_atomic_swap: AtomicSwapConfig,
@@ -66,6 +102,15 @@ impl From<ConfigFile> for AppConfig {
 pub struct AppConfig {
     network_config: NetworkConfig,
     ckb_config: CkbConfig,
+    store_config: StoreConfig,
+    pool_config: PoolConfig,
+    chain_watcher_config: ChainWatcherConfig,
+    validator_config: ValidatorConfig,
+    notify_config: NotifyConfig,
+    scheduler_config: SchedulerConfig,
+    plugin_supervisor_config: PluginSupervisorConfig,
+    plugin_dispatch_config: PluginDispatchConfig,
+    plugins_config: PluginsConfig,
     script_config: ScriptConfig,
     plugin_dust_collector_config: DustCollectorConfig,
     plugin_atomic_swap_config: AtomicSwapConfig,
@@ -78,6 +123,15 @@ impl AppConfig {
         Self {
             network_config: config_file.network_config,
             ckb_config: config_file.ckb_config,
+            store_config: config_file.store_config,
+            pool_config: config_file.pool_config,
+            chain_watcher_config: config_file.chain_watcher_config,
+            validator_config: config_file.validator_config,
+            notify_config: config_file.notify_config,
+            scheduler_config: config_file.scheduler_config,
+            plugin_supervisor_config: config_file.plugin_supervisor_config,
+            plugin_dispatch_config: config_file.plugin_dispatch_config,
+            plugins_config: config_file.plugins,
             script_config: ScriptConfig::new(script_config),
             plugin_dust_collector_config: config_file.built_in_plugin_dust_collector,
             plugin_atomic_swap_config: config_file.built_in_plugin_atomic_swap,
@@ -93,6 +147,42 @@ impl AppConfig {
         self.network_config.clone()
     }
 
+    pub fn get_store_config(&self) -> StoreConfig {
+        self.store_config.clone()
+    }
+
+    pub fn get_pool_config(&self) -> PoolConfig {
+        self.pool_config.clone()
+    }
+
+    pub fn get_chain_watcher_config(&self) -> ChainWatcherConfig {
+        self.chain_watcher_config.clone()
+    }
+
+    pub fn get_validator_config(&self) -> ValidatorConfig {
+        self.validator_config.clone()
+    }
+
+    pub fn get_notify_config(&self) -> NotifyConfig {
+        self.notify_config.clone()
+    }
+
+    pub fn get_scheduler_config(&self) -> SchedulerConfig {
+        self.scheduler_config.clone()
+    }
+
+    pub fn get_plugin_supervisor_config(&self) -> PluginSupervisorConfig {
+        self.plugin_supervisor_config.clone()
+    }
+
+    pub fn get_plugin_dispatch_config(&self) -> PluginDispatchConfig {
+        self.plugin_dispatch_config.clone()
+    }
+
+    pub fn get_plugins_config(&self) -> PluginsConfig {
+        self.plugins_config.clone()
+    }
+
     pub fn get_script_config(&self) -> ScriptConfig {
         self.script_config.clone()
     }
@@ -3,16 +3,24 @@ use serde::Deserialize;
 #[derive(Deserialize, Default, Clone, Debug)]
 pub struct NetworkConfig {
     listen_uri: String,
+    // exposes the admin rpc methods, which manage the plugins
+    #[serde(default)]
+    enable_admin_rpc: bool,
 }
 
 impl NetworkConfig {
-    pub fn new(listen_uri: &str) -> Self {
+    pub fn new(listen_uri: &str, enable_admin_rpc: bool) -> Self {
         NetworkConfig {
             listen_uri: listen_uri.to_string(),
+            enable_admin_rpc,
         }
     }
 
     pub fn get_listen_uri(&self) -> &str {
         &self.listen_uri
     }
+
+    pub fn is_admin_rpc_enabled(&self) -> bool {
+        self.enable_admin_rpc
+    }
 }
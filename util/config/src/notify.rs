@@ -0,0 +1,38 @@
+use crate::OverflowPolicy;
+
+use serde::Deserialize;
+
+const DEFAULT_CHANNEL_SIZE: usize = 128;
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct NotifyConfig {
+    channel_size: usize, // max number of events buffered for a subscriber
+    overflow_policy: OverflowPolicy,
+}
+
+impl Default for NotifyConfig {
+    fn default() -> Self {
+        NotifyConfig {
+            channel_size: DEFAULT_CHANNEL_SIZE,
+            overflow_policy: OverflowPolicy::default(),
+        }
+    }
+}
+
+impl NotifyConfig {
+    pub fn new(channel_size: usize, overflow_policy: OverflowPolicy) -> Self {
+        NotifyConfig {
+            channel_size,
+            overflow_policy,
+        }
+    }
+
+    pub fn get_channel_size(&self) -> usize {
+        self.channel_size
+    }
+
+    pub fn get_overflow_policy(&self) -> OverflowPolicy {
+        self.overflow_policy
+    }
+}
@@ -0,0 +1,61 @@
+use serde::Deserialize;
+
+/// What to do with a new event when the event queue of a plugin is full.
+#[derive(Deserialize, Default, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OverflowPolicy {
+    /// Drop the new event.
+    #[default]
+    Drop,
+    /// Wait until the plugin takes an event, which delays the events of the other plugins.
+    Block,
+    /// Stop sending events to the plugin.
+    Disconnect,
+}
+
+const DEFAULT_QUEUE_SIZE: usize = 1024;
+const DEFAULT_CALLBACK_TIMEOUT_MILLIS: u64 = 5000;
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct PluginDispatchConfig {
+    queue_size: usize,            // max number of events waiting for a plugin
+    callback_timeout_millis: u64, // a callback running longer is reported as slow
+    overflow_policy: OverflowPolicy,
+}
+
+impl Default for PluginDispatchConfig {
+    fn default() -> Self {
+        PluginDispatchConfig {
+            queue_size: DEFAULT_QUEUE_SIZE,
+            callback_timeout_millis: DEFAULT_CALLBACK_TIMEOUT_MILLIS,
+            overflow_policy: OverflowPolicy::default(),
+        }
+    }
+}
+
+impl PluginDispatchConfig {
+    pub fn new(
+        queue_size: usize,
+        callback_timeout_millis: u64,
+        overflow_policy: OverflowPolicy,
+    ) -> Self {
+        PluginDispatchConfig {
+            queue_size,
+            callback_timeout_millis,
+            overflow_policy,
+        }
+    }
+
+    pub fn get_queue_size(&self) -> usize {
+        self.queue_size
+    }
+
+    pub fn get_callback_timeout_millis(&self) -> u64 {
+        self.callback_timeout_millis
+    }
+
+    pub fn get_overflow_policy(&self) -> OverflowPolicy {
+        self.overflow_policy
+    }
+}
@@ -0,0 +1,76 @@
+use serde::Deserialize;
+
+const DEFAULT_PING_INTERVAL_SECS: u64 = 10;
+const DEFAULT_PING_TIMEOUT_SECS: u64 = 5;
+const DEFAULT_MAX_RESTARTS: u32 = 5;
+const DEFAULT_BACKOFF_BASE_SECS: u64 = 1;
+const DEFAULT_BACKOFF_MAX_SECS: u64 = 60;
+const DEFAULT_TERMINATE_TIMEOUT_SECS: u64 = 5;
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct PluginSupervisorConfig {
+    ping_interval_secs: u64,
+    ping_timeout_secs: u64,
+    max_restarts: u32, // consecutive restarts before the plugin is given up
+    backoff_base_secs: u64,
+    backoff_max_secs: u64,
+    terminate_timeout_secs: u64, // wait after SIGTERM before SIGKILL
+}
+
+impl Default for PluginSupervisorConfig {
+    fn default() -> Self {
+        PluginSupervisorConfig {
+            ping_interval_secs: DEFAULT_PING_INTERVAL_SECS,
+            ping_timeout_secs: DEFAULT_PING_TIMEOUT_SECS,
+            max_restarts: DEFAULT_MAX_RESTARTS,
+            backoff_base_secs: DEFAULT_BACKOFF_BASE_SECS,
+            backoff_max_secs: DEFAULT_BACKOFF_MAX_SECS,
+            terminate_timeout_secs: DEFAULT_TERMINATE_TIMEOUT_SECS,
+        }
+    }
+}
+
+impl PluginSupervisorConfig {
+    pub fn new(
+        ping_interval_secs: u64,
+        ping_timeout_secs: u64,
+        max_restarts: u32,
+        backoff_base_secs: u64,
+        backoff_max_secs: u64,
+        terminate_timeout_secs: u64,
+    ) -> Self {
+        PluginSupervisorConfig {
+            ping_interval_secs,
+            ping_timeout_secs,
+            max_restarts,
+            backoff_base_secs,
+            backoff_max_secs,
+            terminate_timeout_secs,
+        }
+    }
+
+    pub fn get_ping_interval_secs(&self) -> u64 {
+        self.ping_interval_secs
+    }
+
+    pub fn get_ping_timeout_secs(&self) -> u64 {
+        self.ping_timeout_secs
+    }
+
+    pub fn get_max_restarts(&self) -> u32 {
+        self.max_restarts
+    }
+
+    pub fn get_backoff_base_secs(&self) -> u64 {
+        self.backoff_base_secs
+    }
+
+    pub fn get_backoff_max_secs(&self) -> u64 {
+        self.backoff_max_secs
+    }
+
+    pub fn get_terminate_timeout_secs(&self) -> u64 {
+        self.terminate_timeout_secs
+    }
+}
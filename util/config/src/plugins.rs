@@ -0,0 +1,21 @@
+use serde::Deserialize;
+use serde_json::Value;
+
+use std::collections::HashMap;
+
+/// The `[plugins.<name>]` tables of the config file, each is passed to the plugin as is.
+#[derive(Deserialize, Default, Clone, Debug, PartialEq)]
+#[serde(transparent)]
+pub struct PluginsConfig {
+    plugins: HashMap<String, Value>,
+}
+
+impl PluginsConfig {
+    pub fn new(plugins: HashMap<String, Value>) -> Self {
+        PluginsConfig { plugins }
+    }
+
+    pub fn get_plugin_config(&self, plugin_name: &str) -> Option<Value> {
+        self.plugins.get(plugin_name).cloned()
+    }
+}
@@ -0,0 +1,71 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Default, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EvictionPolicy {
+    #[default]
+    OldestFirst,
+    LowestFeeFirst,
+}
+
+const DEFAULT_MAX_REJECTED_SIZE: usize = 1000;
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct PoolConfig {
+    max_pool_size: usize, // max number of pending otxs, 0 means unlimited
+    otx_ttl_secs: u64,    // 0 means pending otxs never expire
+    eviction_policy: EvictionPolicy,
+    max_rejected_size: usize, // max number of recently rejected otxs kept in the pool
+    replace_by_fee: bool,     // allow an otx with a higher fee to replace the conflicting ones
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        PoolConfig {
+            max_pool_size: 0,
+            otx_ttl_secs: 0,
+            eviction_policy: EvictionPolicy::default(),
+            max_rejected_size: DEFAULT_MAX_REJECTED_SIZE,
+            replace_by_fee: false,
+        }
+    }
+}
+
+impl PoolConfig {
+    pub fn new(
+        max_pool_size: usize,
+        otx_ttl_secs: u64,
+        eviction_policy: EvictionPolicy,
+        max_rejected_size: usize,
+        replace_by_fee: bool,
+    ) -> Self {
+        PoolConfig {
+            max_pool_size,
+            otx_ttl_secs,
+            eviction_policy,
+            max_rejected_size,
+            replace_by_fee,
+        }
+    }
+
+    pub fn get_max_pool_size(&self) -> usize {
+        self.max_pool_size
+    }
+
+    pub fn get_otx_ttl_secs(&self) -> u64 {
+        self.otx_ttl_secs
+    }
+
+    pub fn get_eviction_policy(&self) -> EvictionPolicy {
+        self.eviction_policy
+    }
+
+    pub fn get_max_rejected_size(&self) -> usize {
+        self.max_rejected_size
+    }
+
+    pub fn is_replace_by_fee_enabled(&self) -> bool {
+        self.replace_by_fee
+    }
+}
@@ -0,0 +1,37 @@
+use serde::Deserialize;
+
+const DEFAULT_INTERVAL_SECS: u64 = 2;
+const DEFAULT_MAX_JOBS_PER_PLUGIN: usize = 64;
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct SchedulerConfig {
+    interval_secs: u64,         // the period of the NewInterval event
+    max_jobs_per_plugin: usize, // max number of jobs a plugin schedules at the same time
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        SchedulerConfig {
+            interval_secs: DEFAULT_INTERVAL_SECS,
+            max_jobs_per_plugin: DEFAULT_MAX_JOBS_PER_PLUGIN,
+        }
+    }
+}
+
+impl SchedulerConfig {
+    pub fn new(interval_secs: u64, max_jobs_per_plugin: usize) -> Self {
+        SchedulerConfig {
+            interval_secs,
+            max_jobs_per_plugin,
+        }
+    }
+
+    pub fn get_interval_secs(&self) -> u64 {
+        self.interval_secs.max(1)
+    }
+
+    pub fn get_max_jobs_per_plugin(&self) -> usize {
+        self.max_jobs_per_plugin
+    }
+}
@@ -0,0 +1,40 @@
+use serde::Deserialize;
+
+const DEFAULT_MAX_EVENTS: u64 = 100_000;
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct StoreConfig {
+    path: String,    // an empty path keeps the pool in memory only
+    max_events: u64, // the latest events kept in the event log, 0 means unlimited
+}
+
+impl Default for StoreConfig {
+    fn default() -> Self {
+        StoreConfig {
+            path: String::default(),
+            max_events: DEFAULT_MAX_EVENTS,
+        }
+    }
+}
+
+impl StoreConfig {
+    pub fn new(path: &str, max_events: u64) -> Self {
+        StoreConfig {
+            path: path.to_string(),
+            max_events,
+        }
+    }
+
+    pub fn get_path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn is_persistent(&self) -> bool {
+        !self.path.is_empty()
+    }
+
+    pub fn get_max_events(&self) -> u64 {
+        self.max_events
+    }
+}
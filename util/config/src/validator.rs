@@ -0,0 +1,38 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ValidatorType {
+    Structure,
+    Accounting,
+    LiveCell,
+    Script,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct ValidatorConfig {
+    validators: Vec<ValidatorType>, // the validators run in order before an otx enters the pool
+}
+
+impl Default for ValidatorConfig {
+    fn default() -> Self {
+        ValidatorConfig {
+            validators: vec![
+                ValidatorType::Structure,
+                ValidatorType::Accounting,
+                ValidatorType::LiveCell,
+            ],
+        }
+    }
+}
+
+impl ValidatorConfig {
+    pub fn new(validators: Vec<ValidatorType>) -> Self {
+        ValidatorConfig { validators }
+    }
+
+    pub fn get_validators(&self) -> &[ValidatorType] {
+        &self.validators
+    }
+}